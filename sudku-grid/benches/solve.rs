@@ -0,0 +1,91 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sudku_grid::{Grid3x3, Grid4x4, Grid5x5, GridNum, Num3x3, Num4x4, SudokuGrid};
+
+// "AI Escargot"
+const HARD_3X3: &str =
+    "1....7.9..3..2...8..96..5....53..9...1..8...26....4...3......1..4......7..7...3..";
+// Built to be slow for backtracking in row-major order (takes seconds with `solve_backtracking`)
+const ANTI_BACKTRACKING_3X3: &str =
+    "..............3.85..1.2.......5.7.....4...1...9.......5......73..2.1........4...9";
+const HARD_4X4: &str = concat!(
+    "..5F....CED7..B..7D.F..E5...43.1.9.375.8F.B.E..CE4..G..B.A1.7.DF..6....5E..8.D.3",
+    "7.4.CE...D62.1F.AE..2G...43B..6..3...8..715C.E49.29.8...BC.36A143.E8.124D...F7C.",
+    "4..A3.F..8.1...5C.G.6.5.4......D..3EA.168..FC....B764.82.3CD19.E8.F..B.C.9E6D.A7",
+    "2A.....D.B.58F.6",
+);
+// Too sparse to finish in a reasonable time with `solve_backtracking`
+const SPARSE_4X4: &str = concat!(
+    "8.G.E.A.2B7..6...2....6.49........D..5..C.1..E...C6FG...E..5A...F..83E...A.49.B.",
+    "...2.A......8..7.B..1D..5.C.2A..1.......B..8.G64.F...3ED......AC...35...F.....2.",
+    ".9.ACF.6..8.14..E67G......5..B3..E3B......F..1...416.B.....A57..C...9G.5..B.4...",
+    ".A9.24.....1....",
+);
+
+fn grid3x3(s: &str) -> Grid3x3 {
+    let mut grid = Grid3x3::empty();
+    for (i, c) in s.chars().enumerate() {
+        grid[(i % 9, i / 9)] = Num3x3::new(c.to_digit(10).unwrap_or(0) as u8);
+    }
+    grid
+}
+
+fn grid4x4(s: &str) -> Grid4x4 {
+    let mut grid = Grid4x4::empty();
+    for (i, c) in s.chars().enumerate() {
+        let n = match c {
+            '1'..='9' => c as u8 - b'0',
+            'A'..='G' => c as u8 - b'A' + 10,
+            _ => 0,
+        };
+        grid[(i % 16, i / 16)] = Num4x4::new(n);
+    }
+    grid
+}
+
+fn solve_3x3(c: &mut Criterion) {
+    let hard = grid3x3(HARD_3X3);
+    let anti = grid3x3(ANTI_BACKTRACKING_3X3);
+
+    let mut group = c.benchmark_group("solve 3x3");
+    group.bench_function("bitmask hard", |b| {
+        b.iter(|| black_box(hard.clone()).solve())
+    });
+    group.bench_function("backtracking hard", |b| {
+        b.iter(|| black_box(hard.clone()).solve_backtracking())
+    });
+    group.bench_function("bitmask anti-backtracking", |b| {
+        b.iter(|| black_box(anti.clone()).solve())
+    });
+    group.bench_function("bitmask unique check", |b| {
+        b.iter(|| black_box(&hard).has_unique_solution())
+    });
+    group.finish();
+}
+
+fn solve_4x4(c: &mut Criterion) {
+    let hard = grid4x4(HARD_4X4);
+    let sparse = grid4x4(SPARSE_4X4);
+
+    let mut group = c.benchmark_group("solve 4x4");
+    group.bench_function("bitmask hard", |b| {
+        b.iter(|| black_box(hard.clone()).solve())
+    });
+    group.bench_function("backtracking hard", |b| {
+        b.iter(|| black_box(hard.clone()).solve_backtracking())
+    });
+    group.bench_function("bitmask sparse", |b| {
+        b.iter(|| black_box(sparse.clone()).solve())
+    });
+    group.bench_function("bitmask generate", |b| b.iter(Grid4x4::generate));
+    group.finish();
+}
+
+fn solve_5x5(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve 5x5");
+    group.sample_size(10);
+    group.bench_function("bitmask generate", |b| b.iter(Grid5x5::generate));
+    group.finish();
+}
+
+criterion_group!(benches, solve_3x3, solve_4x4, solve_5x5);
+criterion_main!(benches);
@@ -0,0 +1,198 @@
+use crate::{GridNum, Pos, SudokuGrid};
+
+/// The candidates of every square of a board, meaning the numbers not yet in its row, column, or
+/// box. Candidates are masks with bit `n - 1` set if `n` is a candidate, the same layout as the
+/// note bits of the grid numbers.
+///
+/// Rather than storing the candidates, how many times each number is in each row, column, and box
+/// is kept, so placing and removing numbers only has to update three counts.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Candidates {
+    rank: usize,
+    size: usize,
+    // Row-major, with 0 being an empty square
+    cells: Vec<u8>,
+    // Indexed by `unit * size + n - 1`, with the rows, then the columns, then the boxes
+    counts: Vec<u8>,
+}
+
+impl Candidates {
+    pub fn new<G: SudokuGrid>(grid: &G) -> Self {
+        let size = G::SIZE;
+        let mut cands = Self {
+            rank: G::RANK,
+            size,
+            cells: vec![0; size * size],
+            counts: vec![0; size * size * 3],
+        };
+        for y in 0..size {
+            for x in 0..size {
+                cands.set((x, y), grid[(x, y)].num_or_zero());
+            }
+        }
+        cands
+    }
+
+    /// Returns the candidates of the square as a mask, 0 if the square is filled.
+    pub fn get(&self, pos: Pos) -> u32 {
+        if self.num(pos) != 0 {
+            return 0;
+        }
+        let used = self
+            .units_of(pos)
+            .into_iter()
+            .flat_map(|unit| (0..self.size).filter(move |i| self.counts[unit * self.size + i] != 0))
+            .fold(0, |used, i| used | 1 << i);
+        !used & (u32::MAX >> (32 - self.size))
+    }
+
+    pub fn contains(&self, pos: Pos, n: u8) -> bool {
+        n != 0 && self.get(pos) & (1 << (n - 1)) != 0
+    }
+
+    /// Returns the candidates of the square in order, empty if the square is filled.
+    pub fn nums(&self, pos: Pos) -> Vec<u8> {
+        let mask = self.get(pos);
+        (1..=self.size as u8)
+            .filter(|n| mask & (1 << (n - 1)) != 0)
+            .collect()
+    }
+
+    /// Returns the number in the square, 0 if it is empty.
+    pub fn num(&self, (x, y): Pos) -> u8 {
+        self.cells[y * self.size + x]
+    }
+
+    /// Updates the candidates for the number placed in the square, with 0 removing the number
+    /// that was there.
+    pub fn set(&mut self, pos: Pos, n: u8) {
+        let old = self.num(pos);
+        if old == n {
+            return;
+        }
+        for unit in self.units_of(pos) {
+            if old != 0 {
+                self.counts[unit * self.size + old as usize - 1] -= 1;
+            }
+            if n != 0 {
+                self.counts[unit * self.size + n as usize - 1] += 1;
+            }
+        }
+        self.cells[pos.1 * self.size + pos.0] = n;
+    }
+
+    /// The other squares in the same row, column, or box as the square, each given once.
+    pub fn peers(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        peers(self.rank, pos)
+    }
+
+    fn units_of(&self, (x, y): Pos) -> [usize; 3] {
+        let b = y / self.rank * self.rank + x / self.rank;
+        [y, self.size + x, self.size * 2 + b]
+    }
+}
+
+/// The other squares in the same row, column, or box as the square on a board of the rank, each
+/// given once: the row first, then the column, then the rest of the box. These are the squares a
+/// number in the square can't repeat in, and the ones [`SudokuGrid::place_num`] takes it out of
+/// the notes of.
+pub fn peers(rank: usize, (x, y): Pos) -> impl Iterator<Item = Pos> {
+    let size = rank * rank;
+    let (bx, by) = (x / rank * rank, y / rank * rank);
+    let row = (0..size).filter(move |&px| px != x).map(move |px| (px, y));
+    let col = (0..size).filter(move |&py| py != y).map(move |py| (x, py));
+    // The box squares in the same row or column were already given
+    let bx_sq = (0..size)
+        .map(move |i| (bx + i % rank, by + i / rank))
+        .filter(move |&(px, py)| px != x && py != y);
+    row.chain(col).chain(bx_sq)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid3x3, Move, Num3x3};
+
+    #[test]
+    fn candidates() {
+        let mut grid = Grid3x3::empty();
+        grid[(0, 0)] = Num3x3::new(1);
+        grid[(4, 0)] = Num3x3::new(2);
+        grid[(0, 4)] = Num3x3::new(3);
+        grid[(1, 1)] = Num3x3::new(4);
+        let mut cands = grid.candidates();
+        assert_eq!(cands.get((0, 0)), 0);
+        assert_eq!(cands.nums((2, 0)), vec![3, 5, 6, 7, 8, 9]);
+        assert!(!cands.contains((2, 2), 4));
+        assert_eq!(cands.nums((8, 8)).len(), 9);
+
+        cands.set((0, 0), 0);
+        assert_eq!(cands.nums((2, 0)), vec![1, 3, 5, 6, 7, 8, 9]);
+        cands.set((8, 0), 9);
+        assert!(!cands.contains((2, 0), 9));
+        assert!(!cands.contains((8, 8), 9));
+        assert!(cands.contains((7, 7), 9));
+
+        // Keeping the candidates up to date gives the same as computing them from scratch
+        grid[(0, 0)] = Num3x3::new(0);
+        grid[(8, 0)] = Num3x3::new(9);
+        assert_eq!(cands, grid.candidates());
+    }
+
+    #[test]
+    fn peers_once() {
+        let mut peers = peers(3, (4, 4)).collect::<Vec<_>>();
+        assert_eq!(peers.len(), 20);
+        peers.sort();
+        peers.dedup();
+        assert_eq!(peers.len(), 20);
+        assert!(!peers.contains(&(4, 4)));
+        assert_eq!(super::peers(4, (0, 0)).count(), 39);
+    }
+
+    #[test]
+    fn fill_and_place() {
+        let mut grid = Grid3x3::generate();
+        let solution = grid.clone();
+        grid.remove_nums(30);
+
+        let moves = grid.fill_notes();
+        assert_eq!(moves.len(), 30);
+        let cands = grid.candidates();
+        for y in 0..9 {
+            for x in 0..9 {
+                let num = grid[(x, y)];
+                if num.num_or_zero() == 0 {
+                    assert_eq!(num.note_bits(), cands.get((x, y)));
+                    assert_eq!(num.has_note(solution[(x, y)].num_or_zero()), Some(true));
+                }
+            }
+        }
+        assert!(grid.fill_notes().is_empty(), "notes already filled");
+
+        let (x, y) = moves[0].pos;
+        let n = solution[(x, y)].num_or_zero();
+        let before = grid.clone();
+        let moves = grid
+            .place_num((x, y), Num3x3::new(n))
+            .expect("solution number not valid");
+        assert_eq!(moves[0], Move::new(before[(x, y)], Num3x3::new(n), (x, y)));
+        for mv in &moves[1..] {
+            assert_eq!(mv.old.has_note(n), Some(true));
+            assert_eq!(mv.new.has_note(n), Some(false));
+        }
+        assert!(peers(3, (x, y)).all(|pos| grid[pos].has_note(n) != Some(true)));
+
+        // Undoing the moves in reverse puts everything back
+        for mv in moves.iter().rev() {
+            grid[mv.pos] = mv.old;
+        }
+        assert!(grid == before);
+
+        let conflict = peers(3, (x, y))
+            .find(|&pos| solution[pos].num_or_zero() != 0 && before[pos].num_or_zero() != 0)
+            .map(|pos| solution[pos].num_or_zero())
+            .expect("no filled peer");
+        assert!(grid.place_num((x, y), Num3x3::new(conflict)).is_none());
+    }
+}
@@ -0,0 +1,414 @@
+use crate::{
+    grid::{num_symbol, positions, symbol_num},
+    Difficulty, GridNum, PuzzleId, SudkuError, SudkuResult, SudokuGrid, Symmetry,
+};
+use rand::Rng;
+use std::collections::HashSet;
+use std::fmt;
+
+/// The version written by [`Collection`]. Collections with a newer version are rejected when
+/// parsing.
+pub const COLLECTION_VERSION: u32 = 1;
+
+const COLLECTION_HEADER: &str = "sudku-collection";
+
+// Generating stops after this many repeats in a row, since small boards only have so many
+// different puzzles.
+const MAX_REPEATS: usize = 20;
+
+/// One puzzle in a [`Collection`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CollectionPuzzle<G: SudokuGrid> {
+    /// Names the puzzle within the collection, without any whitespace. Generated puzzles use
+    /// their [`PuzzleId`].
+    pub id: String,
+    pub difficulty: Difficulty,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// The puzzle, with its numbers marked as given.
+    pub puzzle: G,
+    pub solution: G,
+}
+
+impl<G: SudokuGrid> CollectionPuzzle<G> {
+    /// Solves and rates the puzzle. Returns None if it doesn't have exactly one solution.
+    pub fn from_puzzle(id: impl Into<String>, puzzle: &G) -> Option<Self> {
+        // Only the numbers are kept, all as givens
+        let mut puzzle = {
+            let mut grid = G::empty();
+            grid.fill_from(&puzzle.nums());
+            grid
+        };
+        let solution = puzzle.unique_solution()?;
+        puzzle.set_given();
+        Some(Self {
+            id: id.into(),
+            difficulty: puzzle.difficulty(),
+            title: None,
+            author: None,
+            puzzle,
+            solution,
+        })
+    }
+
+    /// Generates the puzzle for the ID, fails if the ID is for a different board size.
+    pub fn from_id(id: PuzzleId) -> SudkuResult<Self> {
+        let (mut puzzle, solution) = id.generate::<G>()?;
+        puzzle.set_given();
+        Ok(Self {
+            id: id.to_string(),
+            difficulty: puzzle.difficulty(),
+            title: None,
+            author: None,
+            puzzle,
+            solution,
+        })
+    }
+}
+
+/// An ordered pack of puzzles, each stored with its solution so nothing has to be generated or
+/// solved to play them.
+///
+/// Collections are written as text with [`fmt::Display`] and read back with
+/// [`Collection::parse`] (or [`Collection::from_bytes`], for packs bundled with
+/// `include_bytes!`). The first line is `sudku-collection <version>`, then `rank`, then the
+/// puzzles. Each puzzle is a line with its ID, difficulty, givens and solution:
+///
+/// ```text
+/// sudku-collection 1
+/// rank 2
+/// title Starter Pack
+/// author Sudku
+/// puzzle 2E-16 Easy 1..4.4.......... 1234341221434321
+/// title First Steps
+/// puzzle warm-up Easy .2...4.2.1...... 1234341221434321
+/// ```
+///
+/// The givens are written row by row with `.` for empty squares, and the solution the same way
+/// without any. `title` and `author` lines before the first puzzle are for the whole
+/// collection, and after it for the puzzle before them. Unknown keys are skipped, so fields can be
+/// added without bumping the version.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Collection<G: SudokuGrid> {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub puzzles: Vec<CollectionPuzzle<G>>,
+}
+
+impl<G: SudokuGrid> Default for Collection<G> {
+    fn default() -> Self {
+        Self {
+            title: None,
+            author: None,
+            puzzles: Vec::new(),
+        }
+    }
+}
+
+impl<G: SudokuGrid> Collection<G> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates `count` different puzzles (see [`SudokuGrid::is_equivalent`]), each from a
+    /// random [`PuzzleId`] so any of them can be shared. There can be fewer if too many repeats
+    /// come up in a row, which happens on small boards.
+    pub fn generate(count: usize, difficulty: Difficulty, symmetry: Symmetry) -> Self {
+        Self::generate_with_rng(count, difficulty, symmetry, &mut rand::thread_rng())
+    }
+
+    /// Same as [`generate`](Self::generate), with the seeds of the IDs drawn from the RNG.
+    pub fn generate_with_rng(
+        count: usize,
+        difficulty: Difficulty,
+        symmetry: Symmetry,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let mut collection = Self::new();
+        let mut seen = HashSet::new();
+        let mut repeats = 0;
+        while collection.puzzles.len() < count && repeats < MAX_REPEATS {
+            let id = PuzzleId::new(G::RANK, difficulty, rng.gen()).with_symmetry(symmetry);
+            let puzzle = CollectionPuzzle::<G>::from_id(id).expect("ID is for the board's rank");
+            if seen.insert(puzzle.puzzle.canonical().nums()) {
+                collection.puzzles.push(puzzle);
+                repeats = 0;
+            } else {
+                repeats += 1;
+            }
+        }
+        collection
+    }
+
+    /// Removes every puzzle that can be transformed into one before it, returning how many were
+    /// removed.
+    pub fn dedup(&mut self) -> usize {
+        let len = self.puzzles.len();
+        let mut seen = HashSet::new();
+        self.puzzles
+            .retain(|p| seen.insert(p.puzzle.canonical().nums()));
+        len - self.puzzles.len()
+    }
+
+    pub fn find(&self, id: &str) -> Option<&CollectionPuzzle<G>> {
+        self.puzzles.iter().find(|p| p.id == id)
+    }
+
+    /// Reads a collection from bytes, which have to be UTF-8.
+    pub fn from_bytes(bytes: &[u8]) -> SudkuResult<Self> {
+        let s = std::str::from_utf8(bytes).map_err(|e| SudkuError::InvalidCollection {
+            line: bytes[..e.valid_up_to()]
+                .iter()
+                .filter(|&&b| b == b'\n')
+                .count()
+                + 1,
+            reason: "not UTF-8",
+        })?;
+        Self::parse(s)
+    }
+
+    /// Reads a collection written with [`fmt::Display`]. Fails if the text isn't a collection,
+    /// is from a newer version, is for a different board size, or holds a puzzle whose givens
+    /// don't match its solution.
+    pub fn parse(s: &str) -> SudkuResult<Self> {
+        let invalid = |line, reason| SudkuError::InvalidCollection { line, reason };
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty());
+        let (first, header) = lines.next().ok_or(invalid(1, "empty"))?;
+        let version = header
+            .strip_prefix(COLLECTION_HEADER)
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .ok_or(invalid(first, "missing header"))?;
+        if version == 0 || version > COLLECTION_VERSION {
+            return Err(invalid(first, "unsupported version"));
+        }
+
+        let mut collection = Self::new();
+        let mut rank = None;
+        for (i, line) in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            let text = || (!value.is_empty()).then(|| value.to_string());
+            match key {
+                "rank" => {
+                    let r = value.parse().map_err(|_| invalid(i, "bad rank"))?;
+                    if r != G::RANK {
+                        return Err(SudkuError::WrongRank {
+                            rank: r,
+                            expected: G::RANK,
+                        });
+                    }
+                    rank = Some(r);
+                }
+                "puzzle" => {
+                    if rank.is_none() {
+                        return Err(invalid(i, "puzzle before rank"));
+                    }
+                    collection
+                        .puzzles
+                        .push(parse_puzzle(value).ok_or(invalid(i, "bad puzzle"))?);
+                }
+                "title" => match collection.puzzles.last_mut() {
+                    Some(puzzle) => puzzle.title = text(),
+                    None => collection.title = text(),
+                },
+                "author" => match collection.puzzles.last_mut() {
+                    Some(puzzle) => puzzle.author = text(),
+                    None => collection.author = text(),
+                },
+                _ => (),
+            }
+        }
+        if rank.is_none() {
+            return Err(invalid(first, "missing rank"));
+        }
+        Ok(collection)
+    }
+}
+
+impl<G: SudokuGrid> fmt::Display for Collection<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{COLLECTION_HEADER} {COLLECTION_VERSION}")?;
+        writeln!(f, "rank {}", G::RANK)?;
+        write_info(f, &self.title, &self.author)?;
+        for p in &self.puzzles {
+            writeln!(
+                f,
+                "puzzle {} {} {} {}",
+                p.id,
+                p.difficulty.name(),
+                squares(&p.puzzle),
+                squares(&p.solution)
+            )?;
+            write_info(f, &p.title, &p.author)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_info(
+    f: &mut fmt::Formatter<'_>,
+    title: &Option<String>,
+    author: &Option<String>,
+) -> fmt::Result {
+    if let Some(title) = title {
+        writeln!(f, "title {}", title.replace('\n', " "))?;
+    }
+    if let Some(author) = author {
+        writeln!(f, "author {}", author.replace('\n', " "))?;
+    }
+    Ok(())
+}
+
+fn squares<G: SudokuGrid>(grid: &G) -> String {
+    positions::<G>()
+        .map(|pos| match grid[pos].num_or_zero() {
+            0 => '.',
+            n => num_symbol(n),
+        })
+        .collect()
+}
+
+fn parse_puzzle<G: SudokuGrid>(s: &str) -> Option<CollectionPuzzle<G>> {
+    let mut parts = s.split_whitespace();
+    let (id, difficulty, givens, solution) =
+        (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let difficulty = Difficulty::ALL
+        .into_iter()
+        .find(|d| d.name().eq_ignore_ascii_case(difficulty))?;
+    let (givens, nums) = (parse_squares::<G>(givens)?, parse_squares::<G>(solution)?);
+
+    let (mut puzzle, mut solution) = (G::empty(), G::empty());
+    for ((pos, g), n) in positions::<G>().zip(givens).zip(nums) {
+        if n == 0 || (g != 0 && g != n) {
+            return None;
+        }
+        if g != 0 {
+            puzzle[pos] = G::Num::new(g);
+            puzzle[pos].set_given();
+        }
+        solution[pos] = G::Num::new(n);
+    }
+    if solution.is_valid().is_some() {
+        return None;
+    }
+    Some(CollectionPuzzle {
+        id: id.to_string(),
+        difficulty,
+        title: None,
+        author: None,
+        puzzle,
+        solution,
+    })
+}
+
+fn parse_squares<G: SudokuGrid>(s: &str) -> Option<Vec<u8>> {
+    let nums = s
+        .chars()
+        .map(|c| match c {
+            '.' => Some(0),
+            c => symbol_num(c).filter(|&n| n as usize <= G::SIZE),
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (nums.len() == G::CELLS).then_some(nums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid2x2, Grid3x3, Grid4x4};
+
+    #[test]
+    fn generate_write_parse() {
+        let mut collection = Collection::<Grid3x3>::generate(3, Difficulty::Medium, Symmetry::None);
+        assert_eq!(collection.puzzles.len(), 3);
+        for p in &collection.puzzles {
+            assert!(p.puzzle.unique_solution().unwrap() == p.solution);
+            assert_eq!(p.puzzle.difficulty(), p.difficulty);
+            let id = p.id.parse::<PuzzleId>().unwrap();
+            assert!(CollectionPuzzle::<Grid3x3>::from_id(id).unwrap() == *p);
+            assert!(CollectionPuzzle::from_puzzle(p.id.clone(), &p.puzzle).as_ref() == Some(p));
+        }
+        collection.title = Some("Test Pack".into());
+        collection.puzzles[1].author = Some("Someone".into());
+
+        let text = collection.to_string();
+        assert!(text.starts_with("sudku-collection 1\nrank 3\ntitle Test Pack\n"));
+        let parsed = Collection::<Grid3x3>::from_bytes(text.as_bytes()).unwrap();
+        assert!(parsed == collection);
+        assert!(parsed.find(&collection.puzzles[2].id) == Some(&collection.puzzles[2]));
+
+        // A transformed copy of a puzzle is removed, even with another ID
+        let mut copy = collection.puzzles[0].clone();
+        copy.puzzle.rotate_90();
+        copy.solution.rotate_90();
+        copy.id = "copy".into();
+        collection.puzzles.push(copy);
+        assert_eq!(collection.dedup(), 1);
+        assert_eq!(collection.puzzles.len(), 3);
+
+        // Only so many different 4x4 puzzles can be generated
+        let small = Collection::<Grid2x2>::generate(100_000, Difficulty::Easy, Symmetry::None);
+        assert!(!small.puzzles.is_empty() && small.puzzles.len() < 100_000);
+
+        let big = Collection::<Grid4x4>::from_bytes(text.as_bytes());
+        assert_eq!(
+            big.err(),
+            Some(SudkuError::WrongRank {
+                rank: 3,
+                expected: 4
+            })
+        );
+    }
+
+    #[test]
+    fn parse_example() {
+        let text = "sudku-collection 1
+            rank 2
+            title Starter Pack
+            author Sudku
+            puzzle 2E-16 Easy 1..4.4.......... 1234341221434321
+            title First Steps
+            puzzle warm-up Easy .2...4.2.1...... 1234341221434321";
+        let collection = Collection::<Grid2x2>::parse(text).unwrap();
+        assert_eq!(collection.title.as_deref(), Some("Starter Pack"));
+        assert_eq!(collection.author.as_deref(), Some("Sudku"));
+        assert_eq!(collection.puzzles.len(), 2);
+        let first = &collection.puzzles[0];
+        assert_eq!(first.title.as_deref(), Some("First Steps"));
+        assert_eq!(first.author, None);
+        assert!(first.puzzle[(0, 0)].is_given());
+        assert_eq!(first.puzzle.mistakes(&first.solution), Vec::new());
+        assert_eq!(collection.puzzles[1].id, "warm-up");
+
+        let error = |line, reason| Some(SudkuError::InvalidCollection { line, reason });
+        // The givens don't match the solution
+        assert_eq!(
+            Collection::<Grid2x2>::parse(&text.replace("1..4.4", "2..4.4")).err(),
+            error(5, "bad puzzle")
+        );
+        // The solution isn't solved
+        assert_eq!(
+            Collection::<Grid2x2>::parse(
+                &text.replace(" 1234341221434321\n", " 1234341221434312\n")
+            )
+            .err(),
+            error(5, "bad puzzle")
+        );
+        assert_eq!(
+            Collection::<Grid2x2>::parse(&text.replace("collection 1", "collection 2")).err(),
+            error(1, "unsupported version")
+        );
+        assert_eq!(
+            Collection::<Grid2x2>::parse(&text.replace("rank 2", "")).err(),
+            error(5, "puzzle before rank")
+        );
+        assert_eq!(
+            Collection::<Grid2x2>::from_bytes(b"sudku-collection 1\nrank 2\n\xff").err(),
+            error(3, "not UTF-8")
+        );
+    }
+}
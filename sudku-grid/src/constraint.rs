@@ -0,0 +1,327 @@
+use crate::{grid::positions, Cage, GridNum, Pos, SudokuGrid};
+use rand::{seq::SliceRandom, Rng};
+
+/// A rule on the numbers in some of the squares, on top of every region holding each number once.
+/// [`Rules`](crate::Rules) follows its constraints when checking and solving boards.
+pub trait Constraint {
+    /// The squares the constraint is on, each given once.
+    fn cells(&self) -> &[Pos];
+
+    /// Returns true if the numbers in the squares (in the order of [`cells`](Self::cells), with 0
+    /// for empty squares) follow the constraint, or still could once the empty squares are filled
+    /// with numbers up to `size`.
+    fn fits(&self, nums: &[u8], size: u8) -> bool;
+}
+
+impl Constraint for Cage {
+    fn cells(&self) -> &[Pos] {
+        &self.cells
+    }
+
+    fn fits(&self, nums: &[u8], _size: u8) -> bool {
+        let (mut seen, mut total, mut filled) = (0u64, 0, 0);
+        for &n in nums.iter().filter(|&&n| n != 0) {
+            if seen & 1 << n != 0 {
+                return false;
+            }
+            seen |= 1 << n;
+            total += n as u32;
+            filled += 1;
+        }
+        if filled == self.cells.len() {
+            total == self.sum
+        } else {
+            total < self.sum
+        }
+    }
+}
+
+/// A thermometer, whose numbers go up from its bulb (the first square) along its path.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Thermo {
+    pub cells: Vec<Pos>,
+}
+
+impl Thermo {
+    pub fn new(cells: Vec<Pos>) -> Self {
+        Self { cells }
+    }
+
+    /// Random thermometers that the filled board follows, made by walking from a square to ones
+    /// next to it (diagonally too) holding bigger numbers. They don't cross each other.
+    pub fn for_solution<G: SudokuGrid>(solution: &G, rng: &mut impl Rng) -> Vec<Self> {
+        let mut used = vec![false; G::CELLS];
+        let mut thermos = Vec::new();
+        let mut starts = positions::<G>().collect::<Vec<_>>();
+        starts.shuffle(rng);
+        for start in starts {
+            if thermos.len() == G::SIZE {
+                break;
+            }
+            let mut cells = vec![start];
+            while let Some(&(x, y)) = cells.last() {
+                let options = king_moves::<G>((x, y))
+                    .filter(|&(nx, ny)| !used[ny * G::SIZE + nx] && !cells.contains(&(nx, ny)))
+                    .filter(|&p| solution[p].num_or_zero() > solution[(x, y)].num_or_zero())
+                    .filter(|&p| !crosses(&thermos, &cells, (x, y), p))
+                    .collect::<Vec<_>>();
+                let Some(&next) = options.choose(rng) else {
+                    break;
+                };
+                cells.push(next);
+            }
+            if cells.len() >= 3 {
+                for &(x, y) in &cells {
+                    used[y * G::SIZE + x] = true;
+                }
+                thermos.push(Self::new(cells));
+            }
+        }
+        thermos
+    }
+}
+
+impl Constraint for Thermo {
+    fn cells(&self) -> &[Pos] {
+        &self.cells
+    }
+
+    fn fits(&self, nums: &[u8], size: u8) -> bool {
+        // Each number leaves room for the ones before and after it, and for the gaps between it
+        // and the last one filled in
+        let len = nums.len();
+        let mut last: Option<(usize, u8)> = None;
+        for (i, &n) in nums.iter().enumerate().filter(|(_, &n)| n != 0) {
+            if (n as usize) <= i || n as usize + (len - 1 - i) > size as usize {
+                return false;
+            }
+            if let Some((j, m)) = last {
+                if n <= m || ((n - m) as usize) < i - j {
+                    return false;
+                }
+            }
+            last = Some((i, n));
+        }
+        true
+    }
+}
+
+/// An arrow, whose circle (the first square) holds the sum of the numbers along the rest of its
+/// path. The numbers along the path can repeat, unless they share a region.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arrow {
+    pub cells: Vec<Pos>,
+}
+
+impl Arrow {
+    pub fn new(cells: Vec<Pos>) -> Self {
+        Self { cells }
+    }
+
+    /// Random arrows that the filled board follows, each with a path of 1 to 3 squares starting
+    /// next to its circle (diagonally too). They don't cross each other.
+    pub fn for_solution<G: SudokuGrid>(solution: &G, rng: &mut impl Rng) -> Vec<Self> {
+        let mut used = vec![false; G::CELLS];
+        let mut arrows = Vec::new();
+        let mut starts = positions::<G>().collect::<Vec<_>>();
+        starts.shuffle(rng);
+        for circle in starts {
+            if arrows.len() == G::SIZE {
+                break;
+            }
+            let target = solution[circle].num_or_zero();
+            // A few random walks from the circle, keeping the first that adds up
+            let found = (0..8).find_map(|_| {
+                let mut cells = vec![circle];
+                let mut total = 0;
+                while total < target && cells.len() <= 3 {
+                    let &(x, y) = cells.last().expect("empty arrow");
+                    let options = king_moves::<G>((x, y))
+                        .filter(|&(nx, ny)| !used[ny * G::SIZE + nx] && !cells.contains(&(nx, ny)))
+                        .filter(|&p| total + solution[p].num_or_zero() <= target)
+                        .filter(|&p| !crosses(&arrows, &cells, (x, y), p))
+                        .collect::<Vec<_>>();
+                    let &next = options.choose(rng)?;
+                    total += solution[next].num_or_zero();
+                    cells.push(next);
+                }
+                (total == target).then_some(cells)
+            });
+            if let Some(cells) = found {
+                for &(x, y) in &cells {
+                    used[y * G::SIZE + x] = true;
+                }
+                arrows.push(Self::new(cells));
+            }
+        }
+        arrows
+    }
+
+    pub fn circle(&self) -> Pos {
+        self.cells[0]
+    }
+
+    pub fn path(&self) -> &[Pos] {
+        &self.cells[1..]
+    }
+}
+
+impl Constraint for Arrow {
+    fn cells(&self) -> &[Pos] {
+        &self.cells
+    }
+
+    fn fits(&self, nums: &[u8], size: u8) -> bool {
+        let Some((&circle, path)) = nums.split_first() else {
+            return true;
+        };
+        let total = path.iter().map(|&n| n as u32).sum::<u32>();
+        let empty = path.iter().filter(|&&n| n == 0).count() as u32;
+        let (low, high) = (total + empty, total + empty * size as u32);
+        if circle == 0 {
+            low <= size as u32
+        } else {
+            low <= circle as u32 && circle as u32 <= high
+        }
+    }
+}
+
+/// The kinds of Kropki dot.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dot {
+    /// The numbers are consecutive.
+    White,
+    /// One number is double the other.
+    Black,
+}
+
+impl Dot {
+    fn fits(self, a: u8, b: u8) -> bool {
+        match self {
+            Dot::White => a.abs_diff(b) == 1,
+            Dot::Black => a as u32 == 2 * b as u32 || b as u32 == 2 * a as u32,
+        }
+    }
+}
+
+/// A Kropki dot between two squares next to each other.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Kropki {
+    pub dot: Dot,
+    pub cells: [Pos; 2],
+}
+
+impl Kropki {
+    pub fn new(dot: Dot, a: Pos, b: Pos) -> Self {
+        Self { dot, cells: [a, b] }
+    }
+
+    /// Every dot the filled board has room for. Squares holding 1 and 2 get a black dot.
+    pub fn for_solution<G: SudokuGrid>(solution: &G) -> Vec<Self> {
+        let mut dots = Vec::new();
+        for (x, y) in positions::<G>() {
+            for b in [(x + 1, y), (x, y + 1)] {
+                if b.0 >= G::SIZE || b.1 >= G::SIZE {
+                    continue;
+                }
+                let (m, n) = (solution[(x, y)].num_or_zero(), solution[b].num_or_zero());
+                if let Some(dot) = [Dot::Black, Dot::White].into_iter().find(|d| d.fits(m, n)) {
+                    dots.push(Self::new(dot, (x, y), b));
+                }
+            }
+        }
+        dots
+    }
+}
+
+impl Constraint for Kropki {
+    fn cells(&self) -> &[Pos] {
+        &self.cells
+    }
+
+    fn fits(&self, nums: &[u8], size: u8) -> bool {
+        match (nums[0], nums[1]) {
+            (0, 0) => true,
+            (0, n) | (n, 0) => (1..=size).any(|m| self.dot.fits(n, m)),
+            (m, n) => self.dot.fits(m, n),
+        }
+    }
+}
+
+// The squares around the square, diagonally too.
+fn king_moves<G: SudokuGrid>((x, y): Pos) -> impl Iterator<Item = Pos> {
+    (-1..=1isize)
+        .flat_map(|dy| (-1..=1isize).map(move |dx| (dx, dy)))
+        .filter(|&d| d != (0, 0))
+        .map(move |(dx, dy)| (x as isize + dx, y as isize + dy))
+        .filter(|&(nx, ny)| {
+            nx >= 0 && ny >= 0 && (nx as usize) < G::SIZE && (ny as usize) < G::SIZE
+        })
+        .map(|(nx, ny)| (nx as usize, ny as usize))
+}
+
+// Returns true if a diagonal step from `a` to `b` would cross a diagonal step of one of the paths
+// (or of the one being made), which can't be drawn clearly.
+fn crosses<C: Constraint>(others: &[C], cells: &[Pos], a: Pos, b: Pos) -> bool {
+    if a.0 == b.0 || a.1 == b.1 {
+        return false;
+    }
+    let (c, d) = ((a.0, b.1), (b.0, a.1));
+    let steps = |path: &[Pos]| {
+        path.windows(2)
+            .any(|w| (w[0] == c && w[1] == d) || (w[0] == d && w[1] == c))
+    };
+    steps(cells) || others.iter().any(|other| steps(other.cells()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{seeded_rng, Grid3x3};
+
+    #[test]
+    fn fits() {
+        let thermo = Thermo::new(vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+        assert!(thermo.fits(&[0, 0, 0, 0], 9));
+        assert!(thermo.fits(&[1, 0, 5, 9], 9));
+        assert!(!thermo.fits(&[0, 0, 0, 1], 9));
+        assert!(!thermo.fits(&[7, 0, 0, 0], 9));
+        assert!(!thermo.fits(&[2, 0, 3, 0], 9));
+        assert!(!thermo.fits(&[3, 2, 0, 0], 9));
+
+        let arrow = Arrow::new(vec![(0, 0), (1, 0), (2, 0)]);
+        assert!(arrow.fits(&[0, 0, 0], 9) && arrow.fits(&[9, 4, 5], 9));
+        assert!(!arrow.fits(&[1, 0, 0], 9));
+        assert!(!arrow.fits(&[9, 4, 4], 9));
+        assert!(!arrow.fits(&[0, 4, 6], 9));
+        assert!(arrow.fits(&[0, 4, 0], 9) && !arrow.fits(&[4, 4, 0], 9));
+
+        let white = Kropki::new(Dot::White, (0, 0), (1, 0));
+        let black = Kropki::new(Dot::Black, (0, 0), (1, 0));
+        assert!(white.fits(&[4, 5], 9) && !white.fits(&[4, 6], 9));
+        assert!(black.fits(&[6, 3], 9) && black.fits(&[1, 2], 9) && !black.fits(&[4, 5], 9));
+        assert!(black.fits(&[0, 4], 9) && !black.fits(&[7, 0], 9));
+    }
+
+    #[test]
+    fn for_solution() {
+        let solution = Grid3x3::generate_with_rng(&mut seeded_rng(3));
+        let nums = |c: &dyn Constraint| {
+            c.cells()
+                .iter()
+                .map(|&p| solution[p].num_or_zero())
+                .collect::<Vec<_>>()
+        };
+        let thermos = Thermo::for_solution(&solution, &mut seeded_rng(4));
+        assert!(!thermos.is_empty() && thermos.iter().all(|t| t.fits(&nums(t), 9)));
+        let arrows = Arrow::for_solution(&solution, &mut seeded_rng(4));
+        assert!(!arrows.is_empty() && arrows.iter().all(|a| a.fits(&nums(a), 9)));
+        assert!(arrows.iter().all(|a| (2..=4).contains(&a.cells.len())));
+        let dots = Kropki::for_solution(&solution);
+        assert!(!dots.is_empty() && dots.iter().all(|d| d.fits(&nums(d), 9)));
+    }
+}
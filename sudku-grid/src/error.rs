@@ -0,0 +1,100 @@
+use crate::{ParseGridError, Pos, Transform};
+use std::error::Error;
+use std::fmt;
+
+/// The errors returned by the `try_*` functions, which are the fallible versions of the functions
+/// that would otherwise panic or only return a `bool`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SudkuError {
+    /// A position off the board.
+    InvalidPosition(Pos),
+    /// A number above the board's size, or a note of 0.
+    InvalidSymbol(u8),
+    /// An encoding with the wrong number of bytes for the board.
+    MalformedEncoding { len: usize, expected: usize },
+    /// A board with no solution.
+    Unsolvable,
+    /// Asked to remove more numbers than the board can lose while still having any left.
+    TooManyRemovals { requested: usize, max: usize },
+    /// Text that couldn't be read as a puzzle.
+    Parse(ParseGridError),
+    /// Text that isn't a [`PuzzleId`](crate::PuzzleId).
+    InvalidPuzzleId(String),
+    /// Something meant for a board of one rank used with a board of another.
+    WrongRank { rank: usize, expected: usize },
+    /// A [`Transform`] that doesn't fit the board, like swapping rows from different bands.
+    InvalidTransform(Transform),
+    /// Text that couldn't be read as a [`Collection`](crate::Collection), with the line it went
+    /// wrong on.
+    InvalidCollection { line: usize, reason: &'static str },
+    /// Text that couldn't be read as a [`GameSave`](crate::GameSave) or a
+    /// [`Samurai`](crate::Samurai) board, with the line it went wrong on.
+    InvalidSave { line: usize, reason: &'static str },
+    /// A Killer cage, by its index in [`Rules::cages`](crate::Rules::cages), that's empty, too
+    /// big, off the board, or overlaps another.
+    InvalidCage(usize),
+    /// An extra region, by its index in [`Rules::regions`](crate::Rules::regions), that doesn't
+    /// have one square for each number, or has squares off the board.
+    InvalidRegion(usize),
+    /// A Jigsaw layout, [`Rules::boxes`](crate::Rules::boxes), that doesn't split the board into
+    /// one connected box of the right size for each number.
+    InvalidBoxes,
+    /// A thermometer, arrow or Kropki dot, by its index in
+    /// [`Rules::constraints`](crate::Rules::constraints), that's off the board or isn't shaped
+    /// right.
+    InvalidConstraint(usize),
+}
+
+impl fmt::Display for SudkuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SudkuError::InvalidPosition((x, y)) => {
+                write!(f, "position ({x}, {y}) is off the board")
+            }
+            SudkuError::InvalidSymbol(n) => write!(f, "{n} isn't a valid number for the board"),
+            SudkuError::MalformedEncoding { len, expected } => {
+                write!(f, "encoding is {len} bytes instead of {expected}")
+            }
+            SudkuError::Unsolvable => write!(f, "board has no solution"),
+            SudkuError::TooManyRemovals { requested, max } => {
+                write!(f, "can't remove {requested} numbers, at most {max} can be")
+            }
+            SudkuError::Parse(e) => write!(f, "error parsing puzzle: {e}"),
+            SudkuError::InvalidPuzzleId(s) => write!(f, "{s:?} isn't a puzzle ID"),
+            SudkuError::WrongRank { rank, expected } => {
+                write!(
+                    f,
+                    "rank {rank} doesn't match the board's rank of {expected}"
+                )
+            }
+            SudkuError::InvalidTransform(t) => write!(f, "{t:?} doesn't fit the board"),
+            SudkuError::InvalidCollection { line, reason } => {
+                write!(f, "invalid collection on line {line}: {reason}")
+            }
+            SudkuError::InvalidSave { line, reason } => {
+                write!(f, "invalid save on line {line}: {reason}")
+            }
+            SudkuError::InvalidCage(i) => write!(f, "cage {i} doesn't fit the board"),
+            SudkuError::InvalidRegion(i) => write!(f, "region {i} doesn't fit the board"),
+            SudkuError::InvalidBoxes => write!(f, "jigsaw boxes don't fit the board"),
+            SudkuError::InvalidConstraint(i) => write!(f, "constraint {i} doesn't fit the board"),
+        }
+    }
+}
+
+impl Error for SudkuError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SudkuError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseGridError> for SudkuError {
+    fn from(e: ParseGridError) -> Self {
+        SudkuError::Parse(e)
+    }
+}
+
+pub type SudkuResult<T> = Result<T, SudkuError>;
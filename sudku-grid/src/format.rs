@@ -0,0 +1,324 @@
+use crate::{
+    grid::{num_symbol, positions, symbol_num},
+    GridNum, SudokuGrid,
+};
+use std::error::Error;
+use std::fmt;
+
+/// The text formats puzzles are traded in. Empty squares are `.` (`0` is also read), and numbers
+/// above 9 are letters starting from `A`, so 16x16 boards use `1` to `9` and `A` to `G`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PuzzleFormat {
+    /// Every square on one line, row by row (81 characters for a 9x9 board).
+    Line,
+    /// SadMan Sudoku's `.sdk`: one row per line, with `#` comment lines before the board.
+    Sdk,
+    /// Simple Sudoku's `.ss`: one row per line, with `|` between boxes and `---+---+---` lines
+    /// between bands.
+    Ss,
+}
+
+impl PuzzleFormat {
+    /// Guesses the format of the text: `.ss` if it has box separators, a line if it has just one
+    /// line with a board, and `.sdk` otherwise.
+    pub fn detect(s: &str) -> Self {
+        if s.contains('|') {
+            return PuzzleFormat::Ss;
+        }
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        if lines.next().is_some() && lines.next().is_none() {
+            PuzzleFormat::Line
+        } else {
+            PuzzleFormat::Sdk
+        }
+    }
+}
+
+/// Why text couldn't be read as a puzzle. Lines and columns count from 1.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ParseGridError {
+    /// There was no board in the text.
+    Empty,
+    /// A character that isn't a number on the board or an empty square.
+    InvalidSymbol {
+        symbol: char,
+        line: usize,
+        col: usize,
+    },
+    /// A row with the wrong number of squares.
+    RowLength {
+        line: usize,
+        len: usize,
+        expected: usize,
+    },
+    /// The wrong number of squares on the one line.
+    Length { len: usize, expected: usize },
+    /// The wrong number of rows.
+    RowCount { rows: usize, expected: usize },
+}
+
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseGridError::Empty => write!(f, "no puzzle found"),
+            ParseGridError::InvalidSymbol { symbol, line, col } => {
+                write!(f, "invalid symbol {symbol:?} at line {line}, column {col}")
+            }
+            ParseGridError::RowLength {
+                line,
+                len,
+                expected,
+            } => write!(
+                f,
+                "row on line {line} has {len} squares instead of {expected}"
+            ),
+            ParseGridError::Length { len, expected } => {
+                write!(f, "found {len} squares instead of {expected}")
+            }
+            ParseGridError::RowCount { rows, expected } => {
+                write!(f, "found {rows} rows instead of {expected}")
+            }
+        }
+    }
+}
+
+impl Error for ParseGridError {}
+
+/// Reads a puzzle in the format, leaving the numbers unmarked as givens (see
+/// [`SudokuGrid::set_given`]).
+pub(crate) fn parse_puzzle<G: SudokuGrid>(
+    s: &str,
+    format: PuzzleFormat,
+) -> Result<G, ParseGridError> {
+    let nums = match format {
+        PuzzleFormat::Line => parse_line::<G>(s)?,
+        PuzzleFormat::Sdk | PuzzleFormat::Ss => parse_rows::<G>(s)?,
+    };
+    let mut grid = G::empty();
+    for (pos, n) in positions::<G>().zip(nums) {
+        grid[pos] = G::Num::new(n);
+    }
+    Ok(grid)
+}
+
+/// Writes the numbers on the board in the format. Notes and which numbers are given aren't kept.
+pub(crate) fn write_puzzle<G: SudokuGrid>(grid: &G, format: PuzzleFormat) -> String {
+    let (rank, size) = (G::RANK, G::SIZE);
+    let symbol = |x, y| match grid[(x, y)].num_or_zero() {
+        0 => '.',
+        n => num_symbol(n),
+    };
+    let mut s = String::with_capacity(G::CELLS * 2);
+    match format {
+        PuzzleFormat::Line => {
+            s.extend(positions::<G>().map(|(x, y)| symbol(x, y)));
+            s.push('\n');
+        }
+        PuzzleFormat::Sdk => {
+            for y in 0..size {
+                s.extend((0..size).map(|x| symbol(x, y)));
+                s.push('\n');
+            }
+        }
+        PuzzleFormat::Ss => {
+            let separator = vec!["-".repeat(rank); rank].join("+");
+            for y in 0..size {
+                if y != 0 && y % rank == 0 {
+                    s.push_str(&separator);
+                    s.push('\n');
+                }
+                for x in 0..size {
+                    if x != 0 && x % rank == 0 {
+                        s.push('|');
+                    }
+                    s.push(symbol(x, y));
+                }
+                s.push('\n');
+            }
+        }
+    }
+    s
+}
+
+fn parse_symbol<G: SudokuGrid>(c: char, line: usize, col: usize) -> Result<u8, ParseGridError> {
+    match c {
+        '.' | '0' => Ok(0),
+        c => symbol_num(c)
+            .filter(|&n| n <= <G::Num as GridNum>::MAX)
+            .ok_or(ParseGridError::InvalidSymbol {
+                symbol: c,
+                line,
+                col,
+            }),
+    }
+}
+
+fn parse_line<G: SudokuGrid>(s: &str) -> Result<Vec<u8>, ParseGridError> {
+    let (i, line) = s
+        .lines()
+        .enumerate()
+        .find(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#'))
+        .ok_or(ParseGridError::Empty)?;
+    let start = line.chars().take_while(|c| c.is_whitespace()).count();
+    let nums = line
+        .trim()
+        .chars()
+        .enumerate()
+        .map(|(col, c)| parse_symbol::<G>(c, i + 1, start + col + 1))
+        .collect::<Result<Vec<_>, _>>()?;
+    if nums.len() != G::CELLS {
+        return Err(ParseGridError::Length {
+            len: nums.len(),
+            expected: G::CELLS,
+        });
+    }
+    Ok(nums)
+}
+
+// Reads `.sdk` and `.ss` boards, which are the same apart from the separators.
+fn parse_rows<G: SudokuGrid>(s: &str) -> Result<Vec<u8>, ParseGridError> {
+    let mut nums = Vec::with_capacity(G::CELLS);
+    let mut rows = 0;
+    for (i, line) in s.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed == "[Puzzle]" {
+            continue;
+        }
+        // Later sections of a `.sdk` file hold the solver's state rather than the puzzle
+        if trimmed.starts_with('[') {
+            break;
+        }
+        if trimmed.chars().all(|c| c == '-' || c == '+') {
+            continue;
+        }
+        let start = nums.len();
+        for (col, c) in line.chars().enumerate() {
+            if c != '|' && !c.is_whitespace() {
+                nums.push(parse_symbol::<G>(c, i + 1, col + 1)?);
+            }
+        }
+        if nums.len() - start != G::SIZE {
+            return Err(ParseGridError::RowLength {
+                line: i + 1,
+                len: nums.len() - start,
+                expected: G::SIZE,
+            });
+        }
+        rows += 1;
+    }
+    if rows == 0 {
+        return Err(ParseGridError::Empty);
+    }
+    if rows != G::SIZE {
+        return Err(ParseGridError::RowCount {
+            rows,
+            expected: G::SIZE,
+        });
+    }
+    Ok(nums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid3x3, Grid4x4};
+
+    const LINE: &str =
+        "53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79";
+
+    #[test]
+    fn line() {
+        let grid = LINE.parse::<Grid3x3>().expect("bad parse");
+        assert_eq!(grid[(0, 0)].num(), Some(5));
+        assert_eq!(grid[(2, 0)].num(), Some(0));
+        assert_eq!(grid.to_line().trim_end(), LINE);
+        assert!(Grid3x3::from_line(&LINE.replace('.', "0")).unwrap() == grid);
+
+        assert_eq!(
+            Grid3x3::from_line(&LINE[1..]).err(),
+            Some(ParseGridError::Length {
+                len: 80,
+                expected: 81
+            })
+        );
+        assert_eq!(
+            Grid3x3::from_line(&LINE.replacen('7', "A", 1)).err(),
+            Some(ParseGridError::InvalidSymbol {
+                symbol: 'A',
+                line: 1,
+                col: 5
+            })
+        );
+        // Columns count characters, not bytes, when the line is indented
+        assert_eq!(
+            Grid3x3::from_line(&format!("\u{3000} {}", LINE.replacen('7', "A", 1))).err(),
+            Some(ParseGridError::InvalidSymbol {
+                symbol: 'A',
+                line: 1,
+                col: 7
+            })
+        );
+        assert_eq!(
+            Grid3x3::from_line("\n  \n").err(),
+            Some(ParseGridError::Empty)
+        );
+    }
+
+    #[test]
+    fn sdk_ss() {
+        let grid = Grid3x3::from_line(LINE).unwrap();
+        let sdk = grid.to_sdk();
+        assert_eq!(sdk.lines().next(), Some("53..7...."));
+        assert_eq!(PuzzleFormat::detect(&sdk), PuzzleFormat::Sdk);
+        assert!(sdk.parse::<Grid3x3>().unwrap() == grid);
+        let commented = format!("#A someone\n#D a puzzle\n[Puzzle]\n{sdk}[State]\n123");
+        assert!(Grid3x3::from_sdk(&commented).unwrap() == grid);
+
+        let ss = grid.to_ss();
+        let lines = ss.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "53.|.7.|...");
+        assert_eq!(lines[3], "---+---+---");
+        assert_eq!(lines.len(), 11);
+        assert_eq!(PuzzleFormat::detect(&ss), PuzzleFormat::Ss);
+        assert!(ss.parse::<Grid3x3>().unwrap() == grid);
+
+        assert_eq!(
+            Grid3x3::from_ss(&ss.replacen("53.|", "53|", 1)).err(),
+            Some(ParseGridError::RowLength {
+                line: 1,
+                len: 8,
+                expected: 9
+            })
+        );
+        assert_eq!(
+            Grid3x3::from_sdk(&sdk.lines().skip(1).collect::<Vec<_>>().join("\n")).err(),
+            Some(ParseGridError::RowCount {
+                rows: 8,
+                expected: 9
+            })
+        );
+    }
+
+    #[test]
+    fn formats_16x16() {
+        let mut grid = Grid4x4::base();
+        grid.remove_nums(100);
+        for format in [PuzzleFormat::Line, PuzzleFormat::Sdk, PuzzleFormat::Ss] {
+            let s = grid.to_format(format);
+            assert_eq!(PuzzleFormat::detect(&s), format);
+            assert!(s.parse::<Grid4x4>().unwrap() == grid, "bad {format:?}");
+        }
+        let line = grid.to_line();
+        assert_eq!(line.trim_end().len(), 256);
+        assert!(line.contains('G'));
+        assert!(Grid4x4::from_line(&line.to_lowercase()).unwrap() == grid);
+        assert!(matches!(
+            Grid4x4::from_line(&line.replacen('G', "H", 1)),
+            Err(ParseGridError::InvalidSymbol { symbol: 'H', .. })
+        ));
+        assert_eq!(grid.to_ss().lines().nth(4), Some("----+----+----+----"));
+    }
+}
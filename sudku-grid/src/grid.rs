@@ -0,0 +1,1402 @@
+use crate::{
+    candidates::peers,
+    format::{parse_puzzle, write_puzzle},
+    solver::Solver,
+    transform::{canonical_nums, stable_hash},
+    Arrow, Candidates, Difficulty, Hint, Kropki, LogicSolver, Move, ParseGridError, Pos,
+    PuzzleFormat, Rules, Step, SudkuError, SudkuResult, Symmetry, Thermo, Transforms, Variant,
+};
+use rand::{seq::SliceRandom, Rng};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+// How many numbers the search may try when checking that a puzzle the techniques get stuck on has
+// one solution, before the puzzle is treated as ambiguous. Without a limit, the checks can take
+// minutes on 25x25 boards.
+const MAX_GUESSES: usize = 2000;
+
+// How many boards to try when generating a puzzle of a given difficulty.
+const GENERATE_ATTEMPTS: usize = 50;
+
+/// A number (or set of notes) held by a square of a [`SudokuGrid`]. The low bits hold either the
+/// number or, if the note bit is set, a mask with bit `n - 1` set for note `n`. The top two bits
+/// mark notes and given numbers.
+pub trait GridNum: Copy + Eq + Default + fmt::Debug {
+    /// The largest number a square can hold, which is also the number of notes it can have.
+    const MAX: u8;
+    /// Number of bytes each square takes up when encoded.
+    const BYTES: usize;
+    const NOTE_BIT: u32 = 1 << (Self::BYTES * 8 - 1);
+    const GIVEN_BIT: u32 = 1 << (Self::BYTES * 8 - 2);
+    const NOTE_MASK: u32 = u32::MAX >> (32 - Self::MAX as u32);
+
+    fn from_bits(bits: u32) -> Self;
+
+    fn bits(self) -> u32;
+
+    fn new(num: u8) -> Self {
+        Self::from_bits(num as _)
+    }
+
+    fn new_note(num: u8) -> Self {
+        if num == 0 {
+            Self::from_bits(0)
+        } else {
+            Self::from_bits(note_for_num::<Self>(num))
+        }
+    }
+
+    /// Like [`GridNum::new`], but fails if the number is above [`GridNum::MAX`].
+    fn try_new(num: u8) -> SudkuResult<Self> {
+        if num > Self::MAX {
+            return Err(SudkuError::InvalidSymbol(num));
+        }
+        Ok(Self::new(num))
+    }
+
+    /// Like [`GridNum::new_note`], but fails if the note isn't between 1 and [`GridNum::MAX`].
+    fn try_new_note(num: u8) -> SudkuResult<Self> {
+        check_note::<Self>(num)?;
+        Ok(Self::new_note(num))
+    }
+
+    fn num(self) -> Option<u8> {
+        // The given bit fits in the low byte of the 4x4 board's squares, so it has to be masked off
+        (!self.is_note()).then_some((self.bits() & !Self::GIVEN_BIT) as u8)
+    }
+
+    fn num_or_zero(self) -> u8 {
+        self.num().unwrap_or(0)
+    }
+
+    fn is_note(self) -> bool {
+        self.bits() & Self::NOTE_BIT != 0
+    }
+
+    fn is_given(self) -> bool {
+        self.bits() & Self::GIVEN_BIT != 0
+    }
+
+    fn has_note(self, num: u8) -> Option<bool> {
+        (num != 0 && self.is_note()).then_some(self.bits() & (1 << (num - 1)) != 0)
+    }
+
+    fn notes(self) -> Option<Vec<bool>> {
+        (1..=Self::MAX).map(|n| self.has_note(n)).collect()
+    }
+
+    /// Returns the notes as a mask with bit `n - 1` set for note `n`, or 0 if the square doesn't
+    /// hold notes.
+    fn note_bits(self) -> u32 {
+        if self.is_note() {
+            self.bits() & Self::NOTE_MASK
+        } else {
+            0
+        }
+    }
+
+    fn with_num(self, num: u8) -> Self {
+        Self::new(num)
+    }
+
+    fn with_note(self, num: u8) -> Self {
+        // TODO: check with is_note()?
+        if self.bits() <= Self::MAX as u32 {
+            return Self::from_bits(note_for_num::<Self>(num));
+        }
+        Self::from_bits(self.bits() | note_for_num::<Self>(num))
+    }
+
+    fn with_notes(mut self, nums: &[bool]) -> Self {
+        for n in nums
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &b)| b.then_some(i + 1))
+        {
+            self = self.with_note(n as _);
+        }
+        self
+    }
+
+    fn with_toggle_note(self, num: u8) -> Self {
+        // TODO: check with is_note()?
+        if self.bits() <= Self::MAX as u32 {
+            return Self::from_bits(note_for_num::<Self>(num));
+        }
+        Self::from_bits(Self::NOTE_BIT | (self.bits() ^ note_for_num::<Self>(num)))
+    }
+
+    fn try_with_note(self, num: u8) -> SudkuResult<Self> {
+        check_note::<Self>(num)?;
+        Ok(self.with_note(num))
+    }
+
+    fn try_with_toggle_note(self, num: u8) -> SudkuResult<Self> {
+        check_note::<Self>(num)?;
+        Ok(self.with_toggle_note(num))
+    }
+
+    fn set_num(&mut self, num: u8) {
+        *self = Self::new(num);
+    }
+
+    fn set_note(&mut self, num: u8) {
+        *self = self.with_note(num);
+    }
+
+    // Returns whether the note is set by the function (true) or unset (false).
+    fn set_toggle_note(&mut self, num: u8) -> bool {
+        let set = self.has_note(num) != Some(true);
+        *self = self.with_toggle_note(num);
+        set
+    }
+
+    fn set_given(&mut self) {
+        *self = Self::from_bits(Self::GIVEN_BIT | self.num_or_zero() as u32);
+    }
+}
+
+fn check_note<N: GridNum>(num: u8) -> SudkuResult<()> {
+    if num == 0 || num > N::MAX {
+        return Err(SudkuError::InvalidSymbol(num));
+    }
+    Ok(())
+}
+
+#[inline(always)]
+fn note_for_num<N: GridNum>(num: u8) -> u32 {
+    assert_ne!(num, 0);
+    N::NOTE_BIT | (1 << (num - 1))
+}
+
+/// Functionality shared by all of the board sizes. Implementors only need to provide storage for
+/// the squares; everything else is built on top of indexing with a [`Pos`].
+pub trait SudokuGrid: Clone + Eq + Index<Pos, Output = Self::Num> + IndexMut<Pos> {
+    type Num: GridNum;
+    /// The width of a box.
+    const RANK: usize;
+    /// The width of the board.
+    const SIZE: usize = Self::RANK * Self::RANK;
+    /// The number of squares on the board.
+    const CELLS: usize = Self::SIZE * Self::SIZE;
+    const ENCODED_LEN: usize = Self::CELLS * <Self::Num as GridNum>::BYTES;
+
+    fn empty() -> Self;
+
+    /// A filled board to start from when randomizing.
+    fn base() -> Self {
+        let (rank, size) = (Self::RANK, Self::SIZE);
+        let mut grid = Self::empty();
+        for pos in positions::<Self>() {
+            let (x, y) = pos;
+            let n = (y % rank * rank + y / rank + x) % size + 1;
+            grid[pos] = Self::Num::new(n as _);
+        }
+        grid
+    }
+
+    fn randomized() -> Self {
+        let mut grid = Self::base();
+        grid.randomize();
+        grid
+    }
+
+    fn generate() -> Self {
+        Self::generate_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Same as [`generate`](Self::generate), drawing from the RNG so a seeded one (see
+    /// [`seeded_rng`](crate::seeded_rng)) always gives the same board.
+    fn generate_with_rng(rng: &mut impl Rng) -> Self {
+        Self::try_generate_with_rng(rng).unwrap_or_else(|e| panic!("failed to generate board: {e}"))
+    }
+
+    /// Generates a filled board, failing if no solution to the empty board can be found.
+    fn try_generate() -> SudkuResult<Self> {
+        Self::try_generate_with_rng(&mut rand::thread_rng())
+    }
+
+    fn try_generate_with_rng(rng: &mut impl Rng) -> SudkuResult<Self> {
+        let mut solver =
+            Solver::new(Self::RANK, &vec![0; Self::CELLS]).ok_or(SudkuError::Unsolvable)?;
+        if !solver.next_random_solution(rng) {
+            return Err(SudkuError::Unsolvable);
+        }
+        let mut grid = Self::empty();
+        grid.fill_from(solver.cells());
+        Ok(grid)
+    }
+
+    /// Generates a puzzle rated as the given difficulty, generating new boards until one is.
+    /// Some difficulties can't be reached on every board size (4x4 puzzles are always easy), so
+    /// after enough boards the puzzle with the closest rating is returned instead.
+    fn generate_with_difficulty(difficulty: Difficulty) -> Self {
+        Self::generate_with_solution(difficulty).0
+    }
+
+    /// Same as [`generate_with_difficulty`](Self::generate_with_difficulty), but also returns the
+    /// puzzle's solution, which is the filled board the numbers were removed from.
+    fn generate_with_solution(difficulty: Difficulty) -> (Self, Self) {
+        Self::generate_rated_with_rng(difficulty, &mut rand::thread_rng())
+    }
+
+    /// Same as [`generate_with_solution`](Self::generate_with_solution), drawing from the RNG so a
+    /// seeded one gives the same puzzle every time. [`PuzzleId`](crate::PuzzleId) wraps this up
+    /// with the seed.
+    fn generate_rated_with_rng(difficulty: Difficulty, rng: &mut impl Rng) -> (Self, Self) {
+        Self::generate_symmetric_with_rng(difficulty, Symmetry::None, rng)
+    }
+
+    /// Same as [`generate_with_solution`](Self::generate_with_solution), but the givens follow the
+    /// `symmetry` pattern.
+    fn generate_symmetric(difficulty: Difficulty, symmetry: Symmetry) -> (Self, Self) {
+        Self::generate_symmetric_with_rng(difficulty, symmetry, &mut rand::thread_rng())
+    }
+
+    fn generate_symmetric_with_rng(
+        difficulty: Difficulty,
+        symmetry: Symmetry,
+        rng: &mut impl Rng,
+    ) -> (Self, Self) {
+        let mut closest: Option<(Self, Self, usize)> = None;
+        for _ in 0..GENERATE_ATTEMPTS {
+            let solution = Self::generate_with_rng(rng);
+            let mut grid = solution.clone();
+            if grid.remove_nums_rated_symmetric_with_rng(difficulty, symmetry, rng) {
+                return (grid, solution);
+            }
+            let distance = (grid.difficulty() as usize).abs_diff(difficulty as usize);
+            if closest.as_ref().is_none_or(|&(_, _, d)| distance < d) {
+                closest = Some((grid, solution, distance));
+            }
+        }
+        let (grid, solution, _) = closest.expect("no boards generated");
+        (grid, solution)
+    }
+
+    /// Generates a Killer Sudoku: a filled board cut into random cages (see
+    /// [`Rules::killer_cages`]), with numbers removed like
+    /// [`generate_variant`](Self::generate_variant), which usually leaves only a few. Returns the
+    /// puzzle, its solution, and the rules with the cages. Boards bigger than 9x9 take a long time.
+    fn generate_killer() -> (Self, Self, Rules) {
+        Self::generate_killer_with_rng(&mut rand::thread_rng())
+    }
+
+    fn generate_killer_with_rng(rng: &mut impl Rng) -> (Self, Self, Rules) {
+        Self::generate_variant_with_rng(Variant::Killer, Difficulty::default(), Symmetry::None, rng)
+    }
+
+    /// Generates a puzzle of the variant, returning it with its solution and rules. Classic
+    /// puzzles are made like [`generate_symmetric`](Self::generate_symmetric). The others (which
+    /// the difficulty ratings don't know the rules of) have as many numbers removed as can be while
+    /// they can still quickly be shown to have one solution, so the difficulty is ignored.
+    fn generate_variant(
+        variant: Variant,
+        difficulty: Difficulty,
+        symmetry: Symmetry,
+    ) -> (Self, Self, Rules) {
+        Self::generate_variant_with_rng(variant, difficulty, symmetry, &mut rand::thread_rng())
+    }
+
+    fn generate_variant_with_rng(
+        variant: Variant,
+        difficulty: Difficulty,
+        symmetry: Symmetry,
+        rng: &mut impl Rng,
+    ) -> (Self, Self, Rules) {
+        let (solution, rules) = match variant {
+            Variant::Classic => {
+                let (grid, solution) = Self::generate_symmetric_with_rng(difficulty, symmetry, rng);
+                return (grid, solution, Rules::default());
+            }
+            // The constraints are made to fit a classic solution
+            Variant::Killer | Variant::Thermo | Variant::Arrow | Variant::Kropki => {
+                let solution = Self::generate_with_rng(rng);
+                let mut rules = Rules::default();
+                match variant {
+                    Variant::Killer => rules.cages = Rules::killer_cages(&solution, rng),
+                    Variant::Thermo => rules.thermos = Thermo::for_solution(&solution, rng),
+                    Variant::Arrow => rules.arrows = Arrow::for_solution(&solution, rng),
+                    _ => rules.dots = Kropki::for_solution(&solution),
+                }
+                (solution, rules)
+            }
+            // Many layouts have no solution (or none found quickly), and are swapped for new ones
+            Variant::Jigsaw => loop {
+                let rules = Rules::jigsaw(Rules::jigsaw_boxes::<Self>(rng));
+                if let Some(Some(solution)) = rules.random_solution_bounded(rng, MAX_GUESSES) {
+                    break (solution, rules);
+                }
+            },
+            _ => {
+                let rules = variant.rules::<Self>();
+                let solution = rules
+                    .random_solution(rng)
+                    .expect("variant rules can't be followed");
+                (solution, rules)
+            }
+        };
+        let mut grid = solution.clone();
+        for group in symmetric_groups::<Self>(symmetry, rng) {
+            for &pos in &group {
+                grid[pos] = Self::Num::new(0);
+            }
+            if !rules.has_unique_solution_bounded(&grid, MAX_GUESSES) {
+                for &pos in &group {
+                    grid[pos] = solution[pos];
+                }
+            }
+        }
+        (grid, solution, rules)
+    }
+
+    /// Returns None if the slice passed is too small
+    fn from_encoded(encoded: impl AsRef<[u8]>) -> Option<Self> {
+        let mut grid = Self::empty();
+        if grid.decode_from(encoded) {
+            Some(grid)
+        } else {
+            None
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let bytes = <Self::Num as GridNum>::BYTES;
+        let mut res = Vec::with_capacity(Self::ENCODED_LEN);
+        for pos in positions::<Self>() {
+            res.extend_from_slice(&self[pos].bits().to_be_bytes()[4 - bytes..]);
+        }
+        res
+    }
+
+    /// Like [`SudokuGrid::from_encoded`], but the encoding has to be exactly
+    /// [`SudokuGrid::ENCODED_LEN`] bytes.
+    fn try_from_encoded(encoded: impl AsRef<[u8]>) -> SudkuResult<Self> {
+        let mut grid = Self::empty();
+        grid.try_decode_from(encoded)?;
+        Ok(grid)
+    }
+
+    fn try_decode_from(&mut self, encoded: impl AsRef<[u8]>) -> SudkuResult<()> {
+        let encoded = encoded.as_ref();
+        if encoded.len() != Self::ENCODED_LEN {
+            return Err(SudkuError::MalformedEncoding {
+                len: encoded.len(),
+                expected: Self::ENCODED_LEN,
+            });
+        }
+        self.decode_from(encoded);
+        Ok(())
+    }
+
+    /// Returns false if the slice passed is too small
+    fn decode_from(&mut self, encoded: impl AsRef<[u8]>) -> bool {
+        let encoded = encoded.as_ref();
+        if encoded.len() < Self::ENCODED_LEN {
+            return false;
+        }
+        let bytes = <Self::Num as GridNum>::BYTES;
+        for (pos, s) in positions::<Self>().zip(encoded.chunks(bytes)) {
+            let bits = s.iter().fold(0, |bits, &b| bits << 8 | b as u32);
+            self[pos] = Self::Num::from_bits(bits);
+        }
+        true
+    }
+
+    /// Reads a puzzle in one of the text formats. The numbers aren't marked as givens.
+    fn from_format(s: &str, format: PuzzleFormat) -> Result<Self, ParseGridError> {
+        parse_puzzle(s, format)
+    }
+
+    /// Writes the numbers on the board in one of the text formats, dropping notes and givens.
+    fn to_format(&self, format: PuzzleFormat) -> String {
+        write_puzzle(self, format)
+    }
+
+    fn from_line(s: &str) -> Result<Self, ParseGridError> {
+        Self::from_format(s, PuzzleFormat::Line)
+    }
+
+    fn to_line(&self) -> String {
+        self.to_format(PuzzleFormat::Line)
+    }
+
+    fn from_sdk(s: &str) -> Result<Self, ParseGridError> {
+        Self::from_format(s, PuzzleFormat::Sdk)
+    }
+
+    fn to_sdk(&self) -> String {
+        self.to_format(PuzzleFormat::Sdk)
+    }
+
+    fn from_ss(s: &str) -> Result<Self, ParseGridError> {
+        Self::from_format(s, PuzzleFormat::Ss)
+    }
+
+    fn to_ss(&self) -> String {
+        self.to_format(PuzzleFormat::Ss)
+    }
+
+    /// Shuffles the board with transforms that keep it valid: swapping rows and columns within
+    /// their bands, swapping whole bands and stacks, rotations, reflections, and relabeling the
+    /// numbers. See [`Transforms::random`].
+    fn randomize(&mut self) {
+        self.randomize_with_rng(&mut rand::thread_rng());
+    }
+
+    fn randomize_with_rng(&mut self, rng: &mut impl Rng) {
+        Transforms::random_with_rng(Self::RANK, rng).apply(self);
+    }
+
+    // n is the number to remove
+    // TODO: return error if too many are attempted to be removed?
+    /// Returns the number removed, may be less than requested.
+    fn remove_nums(&mut self, n_remove: usize) -> usize {
+        self.try_remove_nums(n_remove)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Like [`SudokuGrid::remove_nums`], but fails instead of panicking if asked to remove every
+    /// square.
+    fn try_remove_nums(&mut self, n_remove: usize) -> SudkuResult<usize> {
+        self.try_remove_nums_with_rng(n_remove, &mut rand::thread_rng())
+    }
+
+    fn remove_nums_with_rng(&mut self, n_remove: usize, rng: &mut impl Rng) -> usize {
+        self.try_remove_nums_with_rng(n_remove, rng)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn try_remove_nums_with_rng(
+        &mut self,
+        n_remove: usize,
+        rng: &mut impl Rng,
+    ) -> SudkuResult<usize> {
+        check_removals::<Self>(n_remove)?;
+        let size = Self::SIZE;
+        let mut indexes = (0..Self::CELLS).collect::<Vec<usize>>();
+        indexes.shuffle(rng);
+
+        // At least n^2 - 1 distict symbols must be kept when removing symbols to have a unique
+        // solution (having n^2 - 1 does not mean the solution is unique, though).
+        // Source: https://pi.math.cornell.edu/~mec/Summer2009/Mahmood/More.html
+
+        // Keeps track of whether the one number that can be fully removed has been
+        let mut num_gone = false;
+        // Allocate one extra so that the nums from the squares can be converted from their values
+        // to a usize without having to subtract 1 to get the index and worry about zeros
+        let mut nums = vec![size; size + 1];
+        let mut num_removed = 0;
+        for i in indexes {
+            if num_removed == n_remove {
+                break;
+            }
+            let pos = (i % size, i / size);
+            let num = self[pos].num_or_zero() as usize;
+            if num != 0 && (!num_gone || nums[num] != 1) {
+                self[pos] = Self::Num::new(0);
+                nums[num] -= 1;
+                num_removed += 1;
+                if nums[num] == 0 {
+                    num_gone = true;
+                }
+            }
+        }
+        Ok(num_removed)
+    }
+
+    /// Removes up to `n_remove` numbers, only removing a number if the board still has exactly one
+    /// solution afterwards. Expects the board to be filled.
+    /// Returns the number removed, which may be less than requested if no more numbers can be
+    /// removed without the solution becoming ambiguous.
+    fn remove_nums_unique(&mut self, n_remove: usize) -> usize {
+        self.try_remove_nums_unique(n_remove)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn try_remove_nums_unique(&mut self, n_remove: usize) -> SudkuResult<usize> {
+        self.try_remove_nums_unique_with_rng(n_remove, &mut rand::thread_rng())
+    }
+
+    fn remove_nums_unique_with_rng(&mut self, n_remove: usize, rng: &mut impl Rng) -> usize {
+        self.try_remove_nums_unique_with_rng(n_remove, rng)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn try_remove_nums_unique_with_rng(
+        &mut self,
+        n_remove: usize,
+        rng: &mut impl Rng,
+    ) -> SudkuResult<usize> {
+        self.try_remove_nums_symmetric_with_rng(n_remove, Symmetry::None, rng)
+    }
+
+    /// Like [`remove_nums_unique`](Self::remove_nums_unique), but removes numbers a symmetric group
+    /// at a time so the givens left follow the `symmetry` pattern. A group is only removed if all
+    /// of it fits within `n_remove`.
+    fn remove_nums_symmetric(&mut self, n_remove: usize, symmetry: Symmetry) -> usize {
+        self.try_remove_nums_symmetric_with_rng(n_remove, symmetry, &mut rand::thread_rng())
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    fn try_remove_nums_symmetric_with_rng(
+        &mut self,
+        n_remove: usize,
+        symmetry: Symmetry,
+        rng: &mut impl Rng,
+    ) -> SudkuResult<usize> {
+        check_removals::<Self>(n_remove)?;
+        let mut num_removed = 0;
+        for group in symmetric_groups::<Self>(symmetry, rng) {
+            if num_removed == n_remove {
+                break;
+            }
+            let old = group.iter().map(|&pos| self[pos]).collect::<Vec<_>>();
+            let filled = old.iter().filter(|num| num.num_or_zero() != 0).count();
+            if filled == 0 || num_removed + filled > n_remove {
+                continue;
+            }
+            for &pos in &group {
+                self[pos] = Self::Num::new(0);
+            }
+            if self.has_unique_solution() {
+                num_removed += filled;
+            } else {
+                for (&pos, num) in group.iter().zip(old) {
+                    self[pos] = num;
+                }
+            }
+        }
+        Ok(num_removed)
+    }
+
+    /// Removes as many numbers as possible while keeping exactly one solution and not making the
+    /// puzzle harder than `difficulty`. Expects the board to be filled. Puzzles the techniques
+    /// can't finish only lose a number if the search can quickly show there is still one solution.
+    /// Returns true if the puzzle left is rated as exactly `difficulty`.
+    fn remove_nums_rated(&mut self, difficulty: Difficulty) -> bool {
+        self.remove_nums_rated_with_rng(difficulty, &mut rand::thread_rng())
+    }
+
+    fn remove_nums_rated_with_rng(&mut self, difficulty: Difficulty, rng: &mut impl Rng) -> bool {
+        self.remove_nums_rated_symmetric_with_rng(difficulty, Symmetry::None, rng)
+    }
+
+    /// Like [`remove_nums_rated`](Self::remove_nums_rated), but removes numbers a symmetric group
+    /// at a time so the givens left follow the `symmetry` pattern.
+    fn remove_nums_rated_symmetric_with_rng(
+        &mut self,
+        difficulty: Difficulty,
+        symmetry: Symmetry,
+        rng: &mut impl Rng,
+    ) -> bool {
+        for group in symmetric_groups::<Self>(symmetry, rng) {
+            let old = group.iter().map(|&pos| self[pos]).collect::<Vec<_>>();
+            if old.iter().all(|num| num.num_or_zero() == 0) {
+                continue;
+            }
+            for &pos in &group {
+                self[pos] = Self::Num::new(0);
+            }
+            let mut logic = self.logic_solver();
+            // Puzzles the techniques can finish only have one solution, so the (much slower)
+            // search is only needed for those they get stuck on, and it starts from where the
+            // techniques got to.
+            let keep = logic.rate() <= difficulty
+                && (logic.is_solved() || {
+                    let nums = positions::<Self>()
+                        .map(|pos| logic.num(pos))
+                        .collect::<Vec<_>>();
+                    Solver::new(Self::RANK, &nums)
+                        .is_some_and(|mut solver| solver.count_bounded(2, MAX_GUESSES) == Some(1))
+                });
+            if !keep {
+                for (&pos, num) in group.iter().zip(old) {
+                    self[pos] = num;
+                }
+            }
+        }
+        self.difficulty() == difficulty
+    }
+
+    /// Rates how hard the puzzle is to solve by hand.
+    fn difficulty(&self) -> Difficulty {
+        self.logic_solver().rate()
+    }
+
+    /// Returns true if the board has exactly one solution.
+    fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    /// Counts the solutions of the board without modifying it, stopping once `limit` have been
+    /// found. Notes are treated as empty squares. A board whose numbers already conflict has no
+    /// solutions.
+    fn count_solutions(&self, limit: usize) -> usize {
+        solver(self)
+            .map(|mut solver| solver.count(limit))
+            .unwrap_or(0)
+    }
+
+    /// Returns the solution of the board if it has exactly one. None of its numbers are marked as
+    /// given, the same as the solutions from
+    /// [`generate_with_solution`](Self::generate_with_solution).
+    fn unique_solution(&self) -> Option<Self> {
+        let mut solutions = self.solutions();
+        let mut solution = solutions.next()?;
+        if solutions.next().is_some() {
+            return None;
+        }
+        for pos in positions::<Self>() {
+            solution[pos] = Self::Num::new(solution[pos].num_or_zero());
+        }
+        Some(solution)
+    }
+
+    /// Returns the squares holding a number that differs from the solution's, row by row. Unlike
+    /// [`pos_is_valid`](Self::pos_is_valid), this catches numbers that fit with the rest of the
+    /// board but are still wrong.
+    fn mistakes(&self, solution: &Self) -> Vec<Pos> {
+        positions::<Self>()
+            .filter(|&pos| {
+                let n = self[pos].num_or_zero();
+                n != 0 && n != solution[pos].num_or_zero()
+            })
+            .collect()
+    }
+
+    /// A copy of the board with only the given numbers, as the puzzle was before it was played.
+    fn givens(&self) -> Self {
+        let mut grid = Self::empty();
+        for pos in positions::<Self>() {
+            if self[pos].is_given() {
+                grid[pos] = self[pos];
+            }
+        }
+        grid
+    }
+
+    /// Returns an iterator over every solution of the board. The board itself is not modified.
+    fn solutions(&self) -> Solutions<Self> {
+        Solutions {
+            grid: self.clone(),
+            solver: solver(self),
+        }
+    }
+
+    /// The numbers in row-major order, with 0 for empty squares (and those with notes).
+    fn nums(&self) -> Vec<u8> {
+        positions::<Self>()
+            .map(|pos| self[pos].num_or_zero())
+            .collect()
+    }
+
+    /// Fills the empty squares (and those with notes) from the row-major numbers.
+    fn fill_from(&mut self, nums: &[u8]) {
+        for (pos, &n) in positions::<Self>().zip(nums) {
+            if self[pos].num_or_zero() == 0 {
+                self[pos] = Self::Num::new(n);
+            }
+        }
+    }
+
+    /// Returns a solver that works through the board using human techniques. The candidates of
+    /// each empty square are worked out from the numbers placed; notes are ignored.
+    fn logic_solver(&self) -> LogicSolver {
+        LogicSolver::new(Self::RANK, &self.nums())
+    }
+
+    /// Same as [`logic_solver`](Self::logic_solver), except squares with notes only keep the
+    /// candidates that are noted.
+    fn logic_solver_with_notes(&self) -> LogicSolver {
+        let notes = positions::<Self>()
+            .map(|pos| self[pos].note_bits())
+            .collect::<Vec<_>>();
+        LogicSolver::with_candidates(Self::RANK, &self.nums(), &notes)
+    }
+
+    /// Solves as much of the board as possible using only human techniques, without modifying
+    /// it. Returns the steps taken and whether they solve the board.
+    fn solve_logically(&self) -> (Vec<Step>, bool) {
+        let mut solver = self.logic_solver();
+        let steps = solver.solve();
+        (steps, solver.is_solved())
+    }
+
+    /// Applies the step to the board, placing its numbers (like [`place_num`](Self::place_num))
+    /// and removing its eliminations from the notes of the squares. Returns the moves made so
+    /// they can be added to a history.
+    fn apply_step(&mut self, step: &Step) -> Vec<Move<Self::Num>> {
+        let mut moves = Vec::new();
+        for &(pos, n) in &step.placements {
+            if let Some(mvs) = self.place_num(pos, Self::Num::new(n)) {
+                moves.extend(mvs);
+            }
+        }
+        for &(pos, n) in &step.eliminations {
+            let old = self[pos];
+            if old.has_note(n) == Some(true) {
+                self[pos].set_toggle_note(n);
+                moves.push(Move::new(old, self[pos], pos));
+            }
+        }
+        moves
+    }
+
+    /// Finds the next step a person could take on the board, using the easiest technique
+    /// possible. Squares with notes are taken to only have the noted candidates. Steps that would
+    /// only remove candidates nobody has noted are worked through rather than given, since there
+    /// would be nothing on the board for them to change. Returns None if the board is solved or
+    /// stuck.
+    fn hint(&self) -> Option<Hint> {
+        let mut solver = self.logic_solver_with_notes();
+        while !solver.is_solved() && !solver.is_broken() {
+            let step = solver.next_step()?;
+            let shown = !step.placements.is_empty()
+                || step
+                    .eliminations
+                    .iter()
+                    .any(|&(pos, n)| self[pos].has_note(n) == Some(true));
+            if shown {
+                let reason = solver.explain(&step);
+                return Some(Hint { step, reason });
+            }
+            solver.apply(&step);
+        }
+        None
+    }
+
+    // Sets all non-zero numbers as given
+    fn set_given(&mut self) {
+        for pos in positions::<Self>() {
+            if self[pos].num_or_zero() != 0 {
+                self[pos].set_given();
+            }
+        }
+    }
+
+    /// Computes the candidates of every empty square from the numbers on the board.
+    fn candidates(&self) -> Candidates {
+        Candidates::new(self)
+    }
+
+    /// Sets the notes of every square without a number to its candidates. Returns the moves made,
+    /// which can be undone together as one history entry.
+    fn fill_notes(&mut self) -> Vec<Move<Self::Num>> {
+        let cands = self.candidates();
+        let mut moves = Vec::new();
+        for pos in positions::<Self>() {
+            let old = self[pos];
+            if old.num_or_zero() != 0 {
+                continue;
+            }
+            let mask = cands.get(pos);
+            let new = if mask == 0 {
+                Self::Num::new(0)
+            } else {
+                Self::Num::from_bits(Self::Num::NOTE_BIT | mask)
+            };
+            if new != old {
+                self[pos] = new;
+                moves.push(Move::new(old, new, pos));
+            }
+        }
+        moves
+    }
+
+    /// Puts the number (or notes) in the square, taking the number out of the notes of the
+    /// squares in the same row, column, and box. Returns the moves made, starting with the one
+    /// for the square, or None if the number is already in the row, column, or box.
+    ///
+    /// Only the classic rules are followed. Variant boards have to be played with
+    /// [`Rules::place_num`](crate::Rules::place_num).
+    fn place_num(&mut self, pos: Pos, num: Self::Num) -> Option<Vec<Move<Self::Num>>> {
+        let n = num.num_or_zero();
+        if !self.pos_is_valid(pos, n) {
+            return None;
+        }
+        let mut moves = vec![Move::new(self[pos], num, pos)];
+        self[pos] = num;
+        if n != 0 {
+            for peer in peers(Self::RANK, pos) {
+                let old = self[peer];
+                if old.has_note(n) == Some(true) {
+                    self[peer] = old.with_toggle_note(n);
+                    moves.push(Move::new(old, self[peer], peer));
+                }
+            }
+        }
+        Some(moves)
+    }
+
+    /// Gets the square, failing if the position is off the board instead of panicking like
+    /// indexing does.
+    fn try_get(&self, pos: Pos) -> SudkuResult<Self::Num> {
+        check_pos::<Self>(pos)?;
+        Ok(self[pos])
+    }
+
+    fn try_set(&mut self, pos: Pos, num: Self::Num) -> SudkuResult<()> {
+        check_pos::<Self>(pos)?;
+        self[pos] = num;
+        Ok(())
+    }
+
+    /// Like [`SudokuGrid::pos_is_valid`], but fails if the position or number isn't on the board.
+    fn try_pos_is_valid(&self, pos: Pos, n: u8) -> SudkuResult<bool> {
+        check_pos::<Self>(pos)?;
+        if n as usize > Self::SIZE {
+            return Err(SudkuError::InvalidSymbol(n));
+        }
+        Ok(self.pos_is_valid(pos, n))
+    }
+
+    /// Returns true if the number can be placed in the square, meaning it isn't already in the
+    /// square's row, column, or box.
+    ///
+    /// The board doesn't know about any rules beyond those, so extra regions (Sudoku X,
+    /// Windoku), Jigsaw boxes, cages, and other constraints are ignored. Variant boards have to
+    /// be checked with [`Rules::pos_is_valid`](crate::Rules::pos_is_valid).
+    fn pos_is_valid(&self, pos: Pos, n: u8) -> bool {
+        if n == 0 {
+            return true;
+        }
+
+        let mut arr = vec![Self::Num::default(); Self::SIZE];
+        self.get_row_for(pos, &mut arr);
+        if arr.iter().any(|num| num.num_or_zero() == n) {
+            return false;
+        }
+        self.get_col_for(pos, &mut arr);
+        if arr.iter().any(|num| num.num_or_zero() == n) {
+            return false;
+        }
+        self.get_box_for(pos, &mut arr);
+        !arr.iter().any(|num| num.num_or_zero() == n)
+    }
+
+    /// Returns true if placed
+    fn try_place(&mut self, pos: Pos, num: Self::Num) -> bool {
+        if self.pos_is_valid(pos, num.num_or_zero()) {
+            self[pos] = num;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns None if valid, otherwise, returns the position of the first bad square encountered
+    /// (an empty square or one repeating a number in its row, column, or box). The board must be
+    /// filled for it to be valid in this case.
+    ///
+    /// Like [`pos_is_valid`](Self::pos_is_valid), only the classic rules are checked. Variant
+    /// boards have to be checked with [`Rules::is_valid`](crate::Rules::is_valid).
+    fn is_valid(&self) -> Option<Pos> {
+        for x in 0..Self::SIZE {
+            for y in 0..Self::SIZE {
+                if self[(x, y)].num_or_zero() == 0 {
+                    return Some((x, y));
+                }
+            }
+        }
+
+        let rank = Self::RANK;
+        let mut arr = vec![Self::Num::default(); Self::SIZE];
+        for i in 0..Self::SIZE {
+            let (bx, by) = (i % rank * rank, i / rank * rank);
+            // Check the row, column, and box
+            for unit in 0..3 {
+                match unit {
+                    0 => self.get_row_for((0, i), &mut arr),
+                    1 => self.get_col_for((i, 0), &mut arr),
+                    _ => self.get_box_for((bx, by), &mut arr),
+                }
+                let mut check = 0u32;
+                for (j, num) in arr.iter().enumerate() {
+                    let bit = 1 << (num.num_or_zero() - 1);
+                    if check & bit != 0 {
+                        return Some(match unit {
+                            0 => (j, i),
+                            1 => (i, j),
+                            _ => (bx + j % rank, by + j / rank),
+                        });
+                    }
+                    check |= bit;
+                }
+            }
+        }
+        None
+    }
+
+    fn get_row_for(&self, (_, y): Pos, arr: &mut [Self::Num]) {
+        for (x, num) in arr.iter_mut().enumerate() {
+            *num = self[(x, y)];
+        }
+    }
+
+    fn get_col_for(&self, (x, _): Pos, arr: &mut [Self::Num]) {
+        for (y, num) in arr.iter_mut().enumerate() {
+            *num = self[(x, y)];
+        }
+    }
+
+    fn get_box_for(&self, (x, y): Pos, arr: &mut [Self::Num]) {
+        let rank = Self::RANK;
+        let (x, y) = (x / rank * rank, y / rank * rank);
+        for (i, num) in arr.iter_mut().enumerate() {
+            *num = self[(x + i % rank, y + i / rank)];
+        }
+    }
+
+    fn swap_pos(&mut self, p1: Pos, p2: Pos) {
+        let temp = self[p1];
+        self[p1] = self[p2];
+        self[p2] = temp;
+    }
+
+    fn swap_rows(&mut self, y1: usize, y2: usize) {
+        for x in 0..Self::SIZE {
+            self.swap_pos((x, y1), (x, y2));
+        }
+    }
+
+    fn swap_cols(&mut self, x1: usize, x2: usize) {
+        for y in 0..Self::SIZE {
+            self.swap_pos((x1, y), (x2, y));
+        }
+    }
+
+    /// Swaps two horizontal groups of rows (one box tall).
+    fn swap_bands(&mut self, b1: usize, b2: usize) {
+        for i in 0..Self::RANK {
+            self.swap_rows(b1 * Self::RANK + i, b2 * Self::RANK + i);
+        }
+    }
+
+    /// Swaps two vertical groups of columns (one box wide).
+    fn swap_stacks(&mut self, s1: usize, s2: usize) {
+        for i in 0..Self::RANK {
+            self.swap_cols(s1 * Self::RANK + i, s2 * Self::RANK + i);
+        }
+    }
+
+    /// Swaps every occurrence of the two numbers, including in notes.
+    fn swap_nums(&mut self, n1: u8, n2: u8) {
+        for pos in positions::<Self>() {
+            let num = self[pos];
+            if num.is_note() {
+                if num.has_note(n1) != num.has_note(n2) {
+                    self[pos] = num.with_toggle_note(n1).with_toggle_note(n2);
+                }
+                continue;
+            }
+            let n = num.num_or_zero();
+            if n == n1 || n == n2 {
+                let mut new = Self::Num::new(if n == n1 { n2 } else { n1 });
+                if num.is_given() {
+                    new.set_given();
+                }
+                self[pos] = new;
+            }
+        }
+    }
+
+    // Reflects on the line y = x
+    fn transpose(&mut self) {
+        remap(self, |(x, y)| (y, x));
+    }
+
+    // Reflects on the line y = -x
+    fn anti_transpose(&mut self) {
+        let last = Self::SIZE - 1;
+        remap(self, |(x, y)| (last - y, last - x));
+    }
+
+    /// Rotates clockwise.
+    fn rotate_90(&mut self) {
+        let last = Self::SIZE - 1;
+        remap(self, |(x, y)| (y, last - x));
+    }
+
+    fn rotate_180(&mut self) {
+        let last = Self::SIZE - 1;
+        remap(self, |(x, y)| (last - x, last - y));
+    }
+
+    fn rotate_270(&mut self) {
+        let last = Self::SIZE - 1;
+        remap(self, |(x, y)| (last - y, x));
+    }
+
+    // Flips top to bottom
+    fn reflect_x(&mut self) {
+        let last = Self::SIZE - 1;
+        remap(self, |(x, y)| (x, last - y));
+    }
+
+    // Flips left to right
+    fn reflect_y(&mut self) {
+        let last = Self::SIZE - 1;
+        remap(self, |(x, y)| (last - x, y));
+    }
+
+    /// The one board, out of every board the numbers can be [transformed](crate::Transform) into,
+    /// that all of them map to, so two puzzles are really the same one if their canonical boards
+    /// are equal. It's the smallest when read row by row, with empty squares before numbers and
+    /// the numbers relabeled in the order they first appear. Notes and givens are dropped.
+    ///
+    /// The search splits on ties, so it's quick for puzzles up to 16x16, but filled boards that
+    /// size or bigger (with every number tying in the first row) take far longer.
+    fn canonical(&self) -> Self {
+        let mut grid = Self::empty();
+        grid.fill_from(&canonical_nums(&self.nums(), Self::RANK));
+        grid
+    }
+
+    /// A hash of the [`canonical`](Self::canonical) board that stays the same between versions
+    /// and platforms, for storing alongside puzzles to find repeats.
+    fn canonical_hash(&self) -> u64 {
+        stable_hash(&self.canonical().nums())
+    }
+
+    /// Returns true if one board can be transformed into the other.
+    fn is_equivalent(&self, other: &Self) -> bool {
+        self.canonical().nums() == other.canonical().nums()
+    }
+
+    /// Returns true if a solution was found, in which case the empty squares (and those with
+    /// notes) are filled in with it. Otherwise, the board is left unchanged.
+    fn solve(&mut self) -> bool {
+        self.try_solve().is_ok()
+    }
+
+    /// Fills in the board with its first solution, failing if it has none.
+    fn try_solve(&mut self) -> SudkuResult<()> {
+        let mut solver = solver(self).ok_or(SudkuError::Unsolvable)?;
+        if !solver.next_solution() {
+            return Err(SudkuError::Unsolvable);
+        }
+        self.fill_from(solver.cells());
+        Ok(())
+    }
+
+    /// Solves the board with plain backtracking, rescanning the row, column, and box for every
+    /// number tried. This is much slower than [`solve`](Self::solve) and is only kept to compare
+    /// against it. Assumes the board is not already in an invalid state.
+    fn solve_backtracking(&mut self) -> bool {
+        match positions::<Self>().find(|&pos| self[pos].num_or_zero() == 0) {
+            Some(pos) => solve_helper(self, pos),
+            None => true,
+        }
+    }
+}
+
+fn solve_helper<G: SudokuGrid>(grid: &mut G, pos: Pos) -> bool {
+    let old = grid[pos];
+    for n in 1..=G::SIZE as u8 {
+        if grid.try_place(pos, G::Num::new(n)) {
+            let Some(new_pos) = positions::<G>()
+                .skip(pos.1 * G::SIZE + pos.0 + 1)
+                .find(|&pos| grid[pos].num_or_zero() == 0)
+            else {
+                return true;
+            };
+            if solve_helper(grid, new_pos) {
+                return true;
+            }
+        }
+    }
+    grid[pos] = old;
+    false
+}
+
+// Fails if the square is off the board.
+fn check_pos<G: SudokuGrid>(pos: Pos) -> SudkuResult<()> {
+    if pos.0 >= G::SIZE || pos.1 >= G::SIZE {
+        return Err(SudkuError::InvalidPosition(pos));
+    }
+    Ok(())
+}
+
+// At least one number has to be left on the board.
+fn check_removals<G: SudokuGrid>(n_remove: usize) -> SudkuResult<()> {
+    if n_remove >= G::CELLS {
+        return Err(SudkuError::TooManyRemovals {
+            requested: n_remove,
+            max: G::CELLS - 1,
+        });
+    }
+    Ok(())
+}
+
+fn solver<G: SudokuGrid>(grid: &G) -> Option<Solver> {
+    Solver::new(G::RANK, &grid.nums())
+}
+
+// Splits the board into the groups of squares `symmetry` keeps together, in a random order.
+fn symmetric_groups<G: SudokuGrid>(symmetry: Symmetry, rng: &mut impl Rng) -> Vec<Vec<Pos>> {
+    let mut indexes = (0..G::CELLS).collect::<Vec<usize>>();
+    indexes.shuffle(rng);
+
+    let mut grouped = vec![false; G::CELLS];
+    let mut groups = Vec::new();
+    for i in indexes {
+        if grouped[i] {
+            continue;
+        }
+        let group = symmetry.group((i % G::SIZE, i / G::SIZE), G::SIZE);
+        for &(x, y) in &group {
+            grouped[y * G::SIZE + x] = true;
+        }
+        groups.push(group);
+    }
+    groups
+}
+
+/// Every square of a board of type `G`, row by row, which is the order
+/// [`SudokuGrid::nums`] and the text formats list them in.
+pub fn positions<G: SudokuGrid>() -> impl Iterator<Item = Pos> {
+    (0..G::CELLS).map(|i| (i % G::SIZE, i / G::SIZE))
+}
+
+// Moves every square to its new position, with `from` giving the old position of the square that
+// ends up at each position.
+fn remap<G: SudokuGrid>(grid: &mut G, from: impl Fn(Pos) -> Pos) {
+    let nums = positions::<G>()
+        .map(|pos| grid[from(pos)])
+        .collect::<Vec<_>>();
+    for (pos, num) in positions::<G>().zip(nums) {
+        grid[pos] = num;
+    }
+}
+
+// Ranges are drawn as u32 rather than usize, which rand samples differently on 32 and 64 bit
+// targets, so seeded boards come out the same on the web as natively.
+pub(crate) fn two_distinct(rng: &mut impl Rng, n: usize) -> (usize, usize) {
+    let n = n as u32;
+    let a = rng.gen_range(0..n);
+    let b = (a + rng.gen_range(1..n)) % n;
+    (a as usize, b as usize)
+}
+
+/// The character a number is written as: `1` to `9`, then `A` onwards for 10 and up.
+pub fn num_symbol(n: u8) -> char {
+    match n {
+        1..=9 => (n + b'0') as char,
+        n => (b'A' + n - 10) as char,
+    }
+}
+
+/// The inverse of [`num_symbol`], also accepting lowercase letters. Returns None for anything
+/// else, including `0`.
+pub fn symbol_num(c: char) -> Option<u8> {
+    match c {
+        '1'..='9' => Some(c as u8 - b'0'),
+        'A'..='Z' => Some(c as u8 - b'A' + 10),
+        'a'..='z' => Some(c as u8 - b'a' + 10),
+        _ => None,
+    }
+}
+
+/// Writes the board out with `_` for empty squares and letters for numbers above 9.
+pub(crate) fn fmt_grid<G: SudokuGrid>(grid: &G, w: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (rank, size) = (G::RANK, G::SIZE);
+    for y in 0..size {
+        for x in 0..size {
+            let c = match grid[(x, y)].num_or_zero() {
+                0 => '_',
+                n => num_symbol(n),
+            };
+            if x == size - 1 {
+                write!(w, "{}", c)?;
+            } else if x % rank == rank - 1 {
+                write!(w, "{} | ", c)?;
+            } else {
+                write!(w, "{} ", c)?;
+            }
+        }
+        if y % rank == rank - 1 && y != size - 1 {
+            write!(w, "\n{}\n", "-".repeat(size * 2 + (rank - 1) * 2 - 1))?;
+        } else {
+            writeln!(w)?;
+        }
+    }
+    Ok(())
+}
+
+/// Iterator over the solutions of a board, created by [`SudokuGrid::solutions`].
+#[derive(Clone)]
+pub struct Solutions<G> {
+    grid: G,
+    solver: Option<Solver>,
+}
+
+impl<G: SudokuGrid> Iterator for Solutions<G> {
+    type Item = G;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let solver = self.solver.as_mut()?;
+        if !solver.next_solution() {
+            self.solver = None;
+            return None;
+        }
+        let mut grid = self.grid.clone();
+        grid.fill_from(solver.cells());
+        Some(grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid3x3, Grid4x4, Num3x3};
+
+    #[test]
+    fn transforms() {
+        let base = Grid3x3::base();
+        assert!(base.is_valid().is_none());
+
+        let mut grid = base.clone();
+        for _ in 0..4 {
+            grid.rotate_90();
+        }
+        assert!(grid == base);
+        grid.rotate_90();
+        grid.rotate_270();
+        assert!(grid == base);
+        grid.rotate_90();
+        grid.rotate_90();
+        let mut rotated = base.clone();
+        rotated.rotate_180();
+        assert!(grid == rotated);
+
+        // Reflecting on both axes is the same as turning halfway
+        grid = base.clone();
+        grid.reflect_x();
+        grid.reflect_y();
+        assert!(grid == rotated);
+        grid = base.clone();
+        grid.transpose();
+        grid.anti_transpose();
+        assert!(grid == rotated);
+
+        grid = base.clone();
+        grid.swap_nums(1, 9);
+        assert_eq!(grid[(0, 0)].num(), Some(9));
+        assert!(grid.is_valid().is_none());
+
+        for _ in 0..10 {
+            grid.randomize();
+            assert!(grid.is_valid().is_none());
+        }
+    }
+
+    #[test]
+    fn mistakes() {
+        let (mut grid, solution) = Grid3x3::generate_with_solution(Difficulty::Easy);
+        assert!(grid.unique_solution().unwrap() == solution);
+        grid.set_given();
+        assert!(grid.mistakes(&solution).is_empty());
+
+        // A number that fits with the rest of the board but isn't the solution's
+        let (pos, n) = positions::<Grid3x3>()
+            .filter(|&pos| grid[pos].num_or_zero() == 0)
+            .find_map(|pos| {
+                (1..=9)
+                    .find(|&n| n != solution[pos].num_or_zero() && grid.pos_is_valid(pos, n))
+                    .map(|n| (pos, n))
+            })
+            .expect("no wrong number fits");
+        let givens = grid.clone();
+        grid[pos] = Num3x3::new(n);
+        let right = positions::<Grid3x3>()
+            .find(|&p| p != pos && grid[p].num_or_zero() == 0)
+            .unwrap();
+        grid[right] = solution[right];
+        assert_eq!(grid.mistakes(&solution), vec![pos]);
+        assert!(grid.givens() == givens);
+        assert!(Grid3x3::empty().unique_solution().is_none());
+    }
+
+    #[test]
+    fn symmetric() {
+        for symmetry in Symmetry::ALL {
+            let (grid, solution) = Grid3x3::generate_symmetric(Difficulty::Medium, symmetry);
+            assert!(symmetry.matches(&grid.nums(), 9), "{symmetry}\n{grid}");
+            assert!(grid.unique_solution().unwrap() == solution);
+        }
+
+        let mut grid = Grid4x4::generate_with_rng(&mut crate::seeded_rng(3));
+        let removed = grid.try_remove_nums_symmetric_with_rng(
+            120,
+            Symmetry::Rotational90,
+            &mut crate::seeded_rng(4),
+        );
+        assert!(removed.is_ok_and(|n| n <= 120 && n % 4 == 0));
+        assert!(Symmetry::Rotational90.matches(&grid.nums(), 16));
+        assert!(grid.has_unique_solution());
+    }
+
+    #[test]
+    fn try_errors() {
+        let mut grid = Grid3x3::generate();
+        assert_eq!(
+            grid.try_remove_nums(81),
+            Err(SudkuError::TooManyRemovals {
+                requested: 81,
+                max: 80
+            })
+        );
+        assert_eq!(grid.try_remove_nums_unique(30), Ok(30));
+        assert_eq!(
+            grid.try_get((9, 0)),
+            Err(SudkuError::InvalidPosition((9, 0)))
+        );
+        assert_eq!(
+            grid.try_set((0, 9), GridNum::new(1)),
+            Err(SudkuError::InvalidPosition((0, 9)))
+        );
+        assert_eq!(
+            grid.try_pos_is_valid((0, 0), 10),
+            Err(SudkuError::InvalidSymbol(10))
+        );
+        assert!(grid.try_pos_is_valid((0, 0), 0).is_ok());
+
+        assert_eq!(Num3x3::try_new(10), Err(SudkuError::InvalidSymbol(10)));
+        assert_eq!(Num3x3::try_new_note(0), Err(SudkuError::InvalidSymbol(0)));
+        assert_eq!(Num3x3::try_new_note(9), Ok(Num3x3::new_note(9)));
+        assert!(Num3x3::new(0).try_with_toggle_note(0).is_err());
+
+        let encoded = grid.encode();
+        assert!(Grid3x3::try_from_encoded(&encoded).is_ok_and(|g| g == grid));
+        assert_eq!(
+            Grid3x3::try_from_encoded(&encoded[1..]).err(),
+            Some(SudkuError::MalformedEncoding {
+                len: 161,
+                expected: 162
+            })
+        );
+
+        assert!(grid.try_solve().is_ok());
+        let mut conflicting = Grid3x3::empty();
+        conflicting[(0, 0)] = GridNum::new(1);
+        conflicting[(1, 0)] = GridNum::new(1);
+        assert_eq!(conflicting.try_solve(), Err(SudkuError::Unsolvable));
+
+        let err = SudkuError::from(Grid3x3::from_line("12").err().expect("parsed bad line"));
+        assert!(err.to_string().starts_with("error parsing puzzle: "));
+    }
+
+    #[test]
+    fn encode_decode() {
+        let mut grid = Grid3x3::generate();
+        grid.remove_nums_unique(50);
+        grid.set_given();
+        grid[(0, 0)] = GridNum::new_note(3);
+        let encoded = grid.encode();
+        assert_eq!(encoded.len(), Grid3x3::ENCODED_LEN);
+        let decoded = Grid3x3::from_encoded(&encoded).expect("bad encoding");
+        assert!(decoded == grid);
+        assert!(Grid3x3::from_encoded(&encoded[1..]).is_none());
+    }
+}
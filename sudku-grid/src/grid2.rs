@@ -0,0 +1,148 @@
+use crate::{
+    grid::fmt_grid, GridNum, History, Move, MultiHistory, ParseGridError, Pos, PuzzleFormat,
+    Solutions, SudokuGrid,
+};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Num2x2(u8);
+
+impl GridNum for Num2x2 {
+    const MAX: u8 = 4;
+    const BYTES: usize = 1;
+
+    fn from_bits(bits: u32) -> Self {
+        Self(bits as _)
+    }
+
+    fn bits(self) -> u32 {
+        self.0 as _
+    }
+}
+
+pub type Move2x2 = Move<Num2x2>;
+pub type History2x2 = History<Num2x2>;
+pub type MultiHistory2x2 = MultiHistory<Num2x2>;
+
+pub type Solutions2x2 = Solutions<Grid2x2>;
+
+pub type Nums2x2 = [[Num2x2; 4]; 4];
+
+pub const BASE_2X2: Nums2x2 = [
+    [Num2x2(1), Num2x2(2), Num2x2(3), Num2x2(4)],
+    [Num2x2(3), Num2x2(4), Num2x2(1), Num2x2(2)],
+    [Num2x2(2), Num2x2(1), Num2x2(4), Num2x2(3)],
+    [Num2x2(4), Num2x2(3), Num2x2(2), Num2x2(1)],
+];
+
+pub const EMPTY_2X2: Nums2x2 = [[Num2x2(0); 4]; 4];
+
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Grid2x2(Nums2x2);
+
+impl Grid2x2 {
+    pub const EMPTY: Self = Self(EMPTY_2X2);
+
+    pub const fn new() -> Self {
+        Self(BASE_2X2)
+    }
+}
+
+impl SudokuGrid for Grid2x2 {
+    type Num = Num2x2;
+    const RANK: usize = 2;
+
+    fn empty() -> Self {
+        Self::EMPTY
+    }
+
+    fn base() -> Self {
+        Self::new()
+    }
+}
+
+impl Index<Pos> for Grid2x2 {
+    type Output = Num2x2;
+    fn index(&self, (x, y): Pos) -> &Self::Output {
+        &self.0[y][x]
+    }
+}
+
+impl IndexMut<Pos> for Grid2x2 {
+    fn index_mut(&mut self, (x, y): Pos) -> &mut Self::Output {
+        &mut self.0[y][x]
+    }
+}
+
+impl Index<usize> for Grid2x2 {
+    type Output = [Num2x2; 4];
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Grid2x2 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl fmt::Display for Grid2x2 {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_grid(self, w)
+    }
+}
+
+impl FromStr for Grid2x2 {
+    type Err = ParseGridError;
+
+    /// Reads a puzzle in any of the [`PuzzleFormat`]s, detecting which one it's in.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_format(s, PuzzleFormat::detect(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Difficulty;
+
+    #[test]
+    fn generate_2x2() {
+        let grid = Grid2x2::generate();
+        assert!(grid.is_valid().is_none());
+        assert_eq!(Grid2x2::empty().count_solutions(usize::MAX), 288);
+
+        let mut puzzle = grid.clone();
+        puzzle.remove_nums_unique(12);
+        assert!(puzzle.has_unique_solution());
+        assert!(puzzle.solve());
+        assert!(puzzle == grid);
+    }
+
+    #[test]
+    fn generate_with_difficulty_2x2() {
+        // Nothing harder than singles is ever needed on boards this small.
+        let grid = Grid2x2::generate_with_difficulty(Difficulty::Expert);
+        assert!(grid.has_unique_solution());
+        assert_eq!(grid.difficulty(), Difficulty::Easy);
+    }
+
+    #[test]
+    fn encode_display_2x2() {
+        let mut grid = Grid2x2::new();
+        grid[(0, 0)] = Num2x2::new_note(4);
+        grid[(1, 0)] = Num2x2(0);
+        grid[(2, 0)].set_given();
+        assert_eq!(grid[(2, 0)].num(), Some(3));
+        let decoded = Grid2x2::from_encoded(grid.encode()).expect("bad encoding");
+        assert!(decoded == grid);
+        assert_eq!(
+            decoded[(0, 0)].notes(),
+            Some(vec![false, false, false, true])
+        );
+        assert_eq!(grid.to_string().lines().next(), Some("_ _ | 3 4"),);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{
+    grid::fmt_grid, GridNum, History, Move, MultiHistory, ParseGridError, Pos, PuzzleFormat,
+    Solutions, SudokuGrid,
+};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Num5x5(u32);
+
+impl GridNum for Num5x5 {
+    const MAX: u8 = 25;
+    const BYTES: usize = 4;
+
+    fn from_bits(bits: u32) -> Self {
+        Self(bits as _)
+    }
+
+    fn bits(self) -> u32 {
+        self.0 as _
+    }
+}
+
+pub type Move5x5 = Move<Num5x5>;
+pub type History5x5 = History<Num5x5>;
+pub type MultiHistory5x5 = MultiHistory<Num5x5>;
+
+pub type Solutions5x5 = Solutions<Grid5x5>;
+
+pub type Nums5x5 = [[Num5x5; 25]; 25];
+
+pub const EMPTY_5X5: Nums5x5 = [[Num5x5(0); 25]; 25];
+
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Grid5x5(Nums5x5);
+
+impl Grid5x5 {
+    pub const EMPTY: Self = Self(EMPTY_5X5);
+}
+
+impl SudokuGrid for Grid5x5 {
+    type Num = Num5x5;
+    const RANK: usize = 5;
+
+    fn empty() -> Self {
+        Self::EMPTY
+    }
+}
+
+impl Index<Pos> for Grid5x5 {
+    type Output = Num5x5;
+    fn index(&self, (x, y): Pos) -> &Self::Output {
+        &self.0[y][x]
+    }
+}
+
+impl IndexMut<Pos> for Grid5x5 {
+    fn index_mut(&mut self, (x, y): Pos) -> &mut Self::Output {
+        &mut self.0[y][x]
+    }
+}
+
+impl Index<usize> for Grid5x5 {
+    type Output = [Num5x5; 25];
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for Grid5x5 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl fmt::Display for Grid5x5 {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_grid(self, w)
+    }
+}
+
+impl FromStr for Grid5x5 {
+    type Err = ParseGridError;
+
+    /// Reads a puzzle in any of the [`PuzzleFormat`]s, detecting which one it's in.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_format(s, PuzzleFormat::detect(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_5x5() {
+        let grid = Grid5x5::generate();
+        assert!(grid.is_valid().is_none());
+        assert!(grid.has_unique_solution());
+
+        let mut puzzle = grid.clone();
+        assert_eq!(puzzle.remove_nums_unique(200), 200);
+        assert!(puzzle.solve());
+        assert!(puzzle == grid);
+    }
+
+    #[test]
+    fn notes_5x5() {
+        let mut grid = Grid5x5::base();
+        assert!(grid.is_valid().is_none());
+        grid[(0, 0)] = Num5x5::new_note(25).with_toggle_note(1);
+        grid.set_given();
+        let notes = grid[(0, 0)].notes().expect("missing notes");
+        assert!(notes[0] && notes[24]);
+        assert_eq!(notes.iter().filter(|&&b| b).count(), 2);
+        assert!(grid[(1, 0)].is_given());
+        assert_eq!(grid[(24, 24)].num(), Some(24));
+
+        let decoded = Grid5x5::from_encoded(grid.encode()).expect("bad encoding");
+        assert!(decoded == grid);
+    }
+}
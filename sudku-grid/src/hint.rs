@@ -0,0 +1,79 @@
+use crate::{Pos, Step};
+use std::fmt;
+
+/// The next step towards solving a board, found by [`SudokuGrid::hint`](crate::SudokuGrid::hint).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Hint {
+    pub step: Step,
+    /// Why the step can be made, meant to be shown to the player.
+    pub reason: String,
+}
+
+impl Hint {
+    /// The squares the step places numbers in or removes notes from, each given once. These are
+    /// separate from the step's `cells`, which make up the pattern allowing the deduction.
+    pub fn targets(&self) -> Vec<Pos> {
+        let mut targets = Vec::new();
+        let changed = self.step.placements.iter().chain(&self.step.eliminations);
+        for &(pos, _) in changed {
+            if !targets.contains(&pos) {
+                targets.push(pos);
+            }
+        }
+        targets
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.step.technique, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Difficulty, Grid3x3, GridNum, Num3x3, SudokuGrid, Technique};
+
+    #[test]
+    fn hints_solve() {
+        let mut grid = Grid3x3::generate_with_difficulty(Difficulty::Easy);
+        let mut solution = grid.clone();
+        assert!(solution.solve());
+        grid.set_given();
+        grid.fill_notes();
+        while let Some(hint) = grid.hint() {
+            assert!(!hint.reason.is_empty());
+            assert!(!hint.targets().is_empty());
+            for &(pos, n) in &hint.step.placements {
+                assert_eq!(solution[pos].num_or_zero(), n, "bad hint {hint}");
+            }
+            let moves = grid.apply_step(&hint.step);
+            assert!(!moves.is_empty(), "hint {hint} changed nothing");
+        }
+        assert_eq!(grid.is_valid(), None);
+        assert_eq!(grid.nums(), solution.nums());
+    }
+
+    #[test]
+    fn hint_uses_notes() {
+        let mut grid = Grid3x3::empty();
+        assert_eq!(grid.hint(), None);
+
+        grid[(3, 2)] = Num3x3::new_note(5);
+        let hint = grid.hint().expect("no hint");
+        assert_eq!(hint.step.technique, Technique::NakedSingle);
+        assert_eq!(hint.step.placements, vec![((3, 2), 5)]);
+        assert_eq!(hint.targets(), vec![(3, 2)]);
+        assert_eq!(hint.reason, "5 is the only number left that can go in r3c4");
+        assert_eq!(
+            hint.to_string(),
+            "Naked Single: 5 is the only number left that can go in r3c4"
+        );
+
+        // Placing the number takes it out of the notes around it
+        grid[(3, 3)] = Num3x3::new_note(5).with_note(6);
+        let moves = grid.apply_step(&hint.step);
+        assert_eq!(moves.len(), 2);
+        assert_eq!(grid[(3, 3)], Num3x3::new_note(6));
+    }
+}
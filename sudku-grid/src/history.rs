@@ -3,6 +3,7 @@ use std::fmt;
 use std::ptr::NonNull;
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Move<N: Clone> {
     pub old: N,
     pub new: N,
@@ -49,10 +50,10 @@ impl<N: Clone> Node<N> {
     }
 
     fn new_onode(mv: Move<N>, prev: ONode<N>, next: ONode<N>) -> ONode<N> {
-        Self::new(mv, prev, next).to_onode()
+        Self::new(mv, prev, next).into_onode()
     }
 
-    fn to_onode(self) -> ONode<N> {
+    fn into_onode(self) -> ONode<N> {
         NonNull::new(Box::into_raw(Box::new(self)))
     }
 }
@@ -124,7 +125,7 @@ impl<N: Clone> History<N> {
         self.loc != Where::Back && self.curr.is_some()
     }
 
-    pub fn undo<'a>(&'a mut self) -> Option<&Move<N>> {
+    pub fn undo(&mut self) -> Option<&Move<N>> {
         if !self.can_undo() {
             return None;
         }
@@ -141,7 +142,7 @@ impl<N: Clone> History<N> {
         }
     }
 
-    pub fn redo<'a>(&'a mut self) -> Option<&'a Move<N>> {
+    pub fn redo(&mut self) -> Option<&Move<N>> {
         if !self.can_redo() {
             return None;
         }
@@ -170,6 +171,42 @@ impl<N: Clone> History<N> {
     pub fn clear(&mut self) {
         *self = Self::new()
     }
+
+    /// Returns every move in the history, oldest first, along with how many of them are currently
+    /// applied (i.e., how many times undo can be called).
+    pub fn moves(&self) -> (Vec<Move<N>>, usize) {
+        unsafe {
+            let Some(mut node) = self.curr else {
+                return (Vec::new(), 0);
+            };
+            let mut applied = usize::from(self.loc != Where::Front);
+            while let Some(p) = node.as_ref().prev {
+                node = p;
+                applied += 1;
+            }
+            let mut mvs = vec![node.as_ref().mv.clone()];
+            while let Some(n) = node.as_ref().next {
+                node = n;
+                mvs.push(node.as_ref().mv.clone());
+            }
+            (mvs, applied)
+        }
+    }
+
+    /// Builds a history from moves, oldest first, where only the first `applied` of them are
+    /// applied and the rest can be redone. The inverse of [`History::moves`].
+    pub fn from_moves(mvs: impl IntoIterator<Item = Move<N>>, applied: usize) -> Self {
+        let mut hist = Self::new();
+        let mut len = 0;
+        for mv in mvs {
+            hist.update(mv);
+            len += 1;
+        }
+        for _ in applied..len {
+            hist.undo();
+        }
+        hist
+    }
 }
 
 impl<N: Clone + PartialEq> History<N> {
@@ -354,10 +391,10 @@ impl<N: Clone> MultiNode<N> {
     }
 
     fn new_onode(mvs: Vec<Move<N>>, prev: OMultiNode<N>, next: OMultiNode<N>) -> OMultiNode<N> {
-        Self::new(mvs, prev, next).to_onode()
+        Self::new(mvs, prev, next).into_onode()
     }
 
-    fn to_onode(self) -> OMultiNode<N> {
+    fn into_onode(self) -> OMultiNode<N> {
         NonNull::new(Box::into_raw(Box::new(self)))
     }
 }
@@ -421,7 +458,7 @@ impl<N: Clone> MultiHistory<N> {
         self.loc != Where::Back && self.curr.is_some()
     }
 
-    pub fn undo<'a>(&'a mut self) -> Option<&'a Vec<Move<N>>> {
+    pub fn undo(&mut self) -> Option<&Vec<Move<N>>> {
         if !self.can_undo() {
             return None;
         }
@@ -438,7 +475,7 @@ impl<N: Clone> MultiHistory<N> {
         }
     }
 
-    pub fn redo<'a>(&'a mut self) -> Option<&'a Vec<Move<N>>> {
+    pub fn redo(&mut self) -> Option<&Vec<Move<N>>> {
         if !self.can_redo() {
             return None;
         }
@@ -467,6 +504,42 @@ impl<N: Clone> MultiHistory<N> {
     pub fn clear(&mut self) {
         *self = Self::new()
     }
+
+    /// Returns every move set in the history, oldest first, along with how many of them are
+    /// currently applied (i.e., how many times undo can be called).
+    pub fn moves(&self) -> (Vec<Vec<Move<N>>>, usize) {
+        unsafe {
+            let Some(mut node) = self.curr else {
+                return (Vec::new(), 0);
+            };
+            let mut applied = usize::from(self.loc != Where::Front);
+            while let Some(p) = node.as_ref().prev {
+                node = p;
+                applied += 1;
+            }
+            let mut mvs = vec![node.as_ref().mvs.clone()];
+            while let Some(n) = node.as_ref().next {
+                node = n;
+                mvs.push(node.as_ref().mvs.clone());
+            }
+            (mvs, applied)
+        }
+    }
+
+    /// Builds a history from move sets, oldest first, where only the first `applied` of them are
+    /// applied and the rest can be redone. The inverse of [`MultiHistory::moves`].
+    pub fn from_moves(mvs: impl IntoIterator<Item = Vec<Move<N>>>, applied: usize) -> Self {
+        let mut hist = Self::new();
+        let mut len = 0;
+        for mv in mvs {
+            hist.update(mv);
+            len += 1;
+        }
+        for _ in applied..len {
+            hist.undo();
+        }
+        hist
+    }
 }
 
 impl<N: Clone + PartialEq> MultiHistory<N> {
@@ -757,6 +830,24 @@ mod test {
         assert!(!hist.same_as(&histc), "bad same as");
     }
 
+    #[test]
+    fn moves_round_trip() {
+        let moves = (0..5)
+            .map(|i| Move::new(i, i, (i as _, i as _)))
+            .collect::<Vec<_>>();
+        let mut hist = new_history(moves.clone());
+        assert_eq!(IHistory::new().moves(), (vec![], 0));
+        for applied in (0..=moves.len()).rev() {
+            assert_eq!(hist.moves(), (moves.clone(), applied), "bad moves");
+            assert_eq!(
+                IHistory::from_moves(moves.clone(), applied),
+                hist,
+                "bad from_moves"
+            );
+            hist.undo();
+        }
+    }
+
     fn new_history<I: IntoIterator<Item = IMove>>(iter: I) -> IHistory {
         let mut hist = IHistory::new();
         iter.into_iter().for_each(|i| hist.update(i));
@@ -851,6 +942,24 @@ mod multi_test {
         assert!(!hist.same_as(&histc), "bad same as");
     }
 
+    #[test]
+    fn moves_round_trip() {
+        let moves = (0..5)
+            .map(|i| vec![Move::new(i, i, (i as _, i as _)); 2])
+            .collect::<Vec<_>>();
+        let mut hist = new_history(moves.clone());
+        assert_eq!(IMultiHistory::new().moves(), (vec![], 0));
+        for applied in (0..=moves.len()).rev() {
+            assert_eq!(hist.moves(), (moves.clone(), applied), "bad moves");
+            assert_eq!(
+                IMultiHistory::from_moves(moves.clone(), applied),
+                hist,
+                "bad from_moves"
+            );
+            hist.undo();
+        }
+    }
+
     fn new_history<I: IntoIterator<Item = IMoveVec>>(iter: I) -> IMultiHistory {
         let mut hist = IMultiHistory::new();
         iter.into_iter().for_each(|i| hist.update(i));
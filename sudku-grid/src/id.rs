@@ -0,0 +1,211 @@
+use crate::{Difficulty, SudkuError, SudkuResult, SudokuGrid, Symmetry};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::fmt;
+use std::str::FromStr;
+
+/// The RNG used to generate puzzles from a seed. ChaCha8 is used rather than `StdRng` since its
+/// output won't change with new versions of `rand`, so shared IDs keep giving the same puzzles.
+pub fn seeded_rng(seed: u64) -> impl Rng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+/// A short ID that regenerates the exact same puzzle: the rank of the board, the difficulty asked
+/// for, the symmetry of the givens, and the seed for [`seeded_rng`]. It's written as the rank, the
+/// first letter of the difficulty (`X` for expert), a letter for the symmetry if there is one (see
+/// [`symmetry_letter`]), a dash, and the seed in base 36, like `3M-1Y2P0IJ32E8E7` or `3HR-2S`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PuzzleId {
+    pub rank: usize,
+    pub difficulty: Difficulty,
+    pub symmetry: Symmetry,
+    pub seed: u64,
+}
+
+impl PuzzleId {
+    pub fn new(rank: usize, difficulty: Difficulty, seed: u64) -> Self {
+        Self {
+            rank,
+            difficulty,
+            symmetry: Symmetry::None,
+            seed,
+        }
+    }
+
+    pub fn with_symmetry(self, symmetry: Symmetry) -> Self {
+        Self { symmetry, ..self }
+    }
+
+    /// An ID with a random seed, for starting a new puzzle that can be shared.
+    pub fn random(rank: usize, difficulty: Difficulty) -> Self {
+        Self::new(rank, difficulty, rand::thread_rng().gen())
+    }
+
+    /// Generates the puzzle and its solution (see [`SudokuGrid::generate_symmetric_with_rng`]).
+    /// Fails if the ID is for a different board size.
+    pub fn generate<G: SudokuGrid>(&self) -> SudkuResult<(G, G)> {
+        if self.rank != G::RANK {
+            return Err(SudkuError::WrongRank {
+                rank: self.rank,
+                expected: G::RANK,
+            });
+        }
+        let mut rng = seeded_rng(self.seed);
+        Ok(G::generate_symmetric_with_rng(
+            self.difficulty,
+            self.symmetry,
+            &mut rng,
+        ))
+    }
+}
+
+fn difficulty_letter(difficulty: Difficulty) -> char {
+    match difficulty {
+        Difficulty::Expert => 'X',
+        d => d.name().chars().next().expect("empty difficulty name"),
+    }
+}
+
+/// The letter written in a [`PuzzleId`] for each symmetry: `R` for 180° rotational, `Q` for a
+/// quarter turn, `H` and `V` for the mirrors and `D` for diagonal. [`Symmetry::None`] isn't
+/// written.
+pub fn symmetry_letter(symmetry: Symmetry) -> Option<char> {
+    match symmetry {
+        Symmetry::None => None,
+        Symmetry::Rotational180 => Some('R'),
+        Symmetry::Rotational90 => Some('Q'),
+        Symmetry::Horizontal => Some('H'),
+        Symmetry::Vertical => Some('V'),
+        Symmetry::Diagonal => Some('D'),
+    }
+}
+
+impl fmt::Display for PuzzleId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut digits = Vec::new();
+        let mut seed = self.seed;
+        loop {
+            digits.push(char::from_digit((seed % 36) as u32, 36).expect("bad base 36 digit"));
+            seed /= 36;
+            if seed == 0 {
+                break;
+            }
+        }
+        let seed = digits.into_iter().rev().collect::<String>();
+        write!(
+            f,
+            "{}{}{}-{}",
+            self.rank,
+            difficulty_letter(self.difficulty),
+            symmetry_letter(self.symmetry)
+                .map(String::from)
+                .unwrap_or_default(),
+            seed.to_uppercase()
+        )
+    }
+}
+
+impl FromStr for PuzzleId {
+    type Err = SudkuError;
+
+    /// Reads an ID written with [`fmt::Display`], ignoring case.
+    fn from_str(s: &str) -> SudkuResult<Self> {
+        let invalid = || SudkuError::InvalidPuzzleId(s.to_string());
+        let (head, seed) = s.trim().split_once('-').ok_or_else(invalid)?;
+        let letters = head.trim_start_matches(|c: char| c.is_ascii_digit());
+        let rank = head[..head.len() - letters.len()]
+            .parse()
+            .map_err(|_| invalid())?;
+        let mut letters = letters.chars();
+        let letter = letters.next().ok_or_else(invalid)?;
+        let difficulty = Difficulty::ALL
+            .into_iter()
+            .find(|&d| difficulty_letter(d).eq_ignore_ascii_case(&letter))
+            .ok_or_else(invalid)?;
+        let symmetry = match (letters.next(), letters.next()) {
+            (None, _) => Symmetry::None,
+            (Some(letter), None) => Symmetry::ALL
+                .into_iter()
+                .find(|&s| symmetry_letter(s).is_some_and(|l| l.eq_ignore_ascii_case(&letter)))
+                .ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+        let seed = u64::from_str_radix(seed, 36).map_err(|_| invalid())?;
+        Ok(Self::new(rank, difficulty, seed).with_symmetry(symmetry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid2x2, Grid3x3};
+
+    #[test]
+    fn display_parse() {
+        let id = PuzzleId::new(3, Difficulty::Expert, 123_456_789);
+        assert_eq!(id.to_string(), "3X-21I3V9");
+        assert_eq!("3x-21i3v9".parse::<PuzzleId>().unwrap(), id);
+        for seed in [0, 1, u64::MAX] {
+            let id = PuzzleId::new(4, Difficulty::Diabolical, seed);
+            assert_eq!(id.to_string().parse::<PuzzleId>().unwrap(), id);
+        }
+        for symmetry in Symmetry::ALL {
+            let id = PuzzleId::new(3, Difficulty::Hard, 100).with_symmetry(symmetry);
+            assert_eq!(id.to_string().parse::<PuzzleId>().unwrap(), id);
+        }
+        let id = PuzzleId::new(3, Difficulty::Hard, 100).with_symmetry(Symmetry::Horizontal);
+        assert_eq!(id.to_string(), "3HH-2S");
+        for bad in [
+            "", "3X", "3Q-12", "X-12", "3E-", "3E-!", "3EZ-12", "3ERR-12",
+        ] {
+            assert_eq!(
+                bad.parse::<PuzzleId>(),
+                Err(SudkuError::InvalidPuzzleId(bad.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn same_puzzle() {
+        let id = PuzzleId::random(3, Difficulty::Medium);
+        let (grid, solution) = id.generate::<Grid3x3>().unwrap();
+        let (again, again_solution) = id.generate::<Grid3x3>().unwrap();
+        assert!(grid == again && solution == again_solution);
+        assert_eq!(grid.difficulty(), Difficulty::Medium);
+        assert_eq!(grid.unique_solution().unwrap().nums(), solution.nums());
+
+        let other = PuzzleId {
+            seed: id.seed ^ 1,
+            ..id
+        };
+        assert!(other.generate::<Grid3x3>().unwrap().0 != grid);
+        assert_eq!(
+            id.generate::<Grid2x2>().err(),
+            Some(SudkuError::WrongRank {
+                rank: 3,
+                expected: 2
+            })
+        );
+    }
+
+    #[test]
+    fn seeded_apis() {
+        let mut grid = Grid3x3::generate_with_rng(&mut seeded_rng(7));
+        assert!(grid == Grid3x3::generate_with_rng(&mut seeded_rng(7)));
+        let mut other = grid.clone();
+        grid.randomize_with_rng(&mut seeded_rng(8));
+        other.randomize_with_rng(&mut seeded_rng(8));
+        assert!(grid == other);
+        grid.remove_nums_unique_with_rng(40, &mut seeded_rng(9));
+        other.remove_nums_unique_with_rng(40, &mut seeded_rng(9));
+        assert!(grid == other);
+
+        // The same seed has to give the same puzzle everywhere, or shared IDs would break
+        let (grid, _) = PuzzleId::new(2, Difficulty::Easy, 42)
+            .generate::<Grid2x2>()
+            .unwrap();
+        assert_eq!(grid.to_line().trim_end(), GOLDEN_2X2);
+    }
+
+    const GOLDEN_2X2: &str = ".23....4.....3..";
+}
@@ -1,23 +1,67 @@
 pub use rand;
 
+pub mod candidates;
+pub use candidates::*;
+
+pub mod collection;
+pub use collection::*;
+
+pub mod constraint;
+pub use constraint::*;
+
+pub mod error;
+pub use error::*;
+
+pub mod format;
+pub use format::*;
+
+pub mod grid;
+pub use grid::*;
+
+pub mod grid2;
+pub use grid2::*;
+
 pub mod grid3;
 pub use grid3::*;
 
 pub mod grid4;
 pub use grid4::*;
 
+pub mod grid5;
+pub use grid5::*;
+
+pub mod hint;
+pub use hint::*;
+
 pub mod history;
 pub use history::*;
 
+pub mod id;
+pub use id::*;
+
+pub mod logic;
+pub use logic::*;
+
+pub mod rules;
+pub use rules::*;
+
+pub mod samurai;
+pub use samurai::*;
+
+pub mod save;
+pub use save::*;
+
+#[cfg(feature = "serde")]
+mod serialize;
+
+mod solver;
+
+pub mod symmetry;
+pub use symmetry::*;
+
+pub mod transform;
+pub use transform::*;
+
 //pub mod multi_history;
 
 pub type Pos = (usize, usize);
-
-#[allow(dead_code)]
-#[derive(Clone, Copy, PartialEq, Eq, Default)]
-pub(crate) enum GridLayout {
-    #[default]
-    Row,
-    Col,
-    Box,
-}
@@ -0,0 +1,1072 @@
+use crate::{grid::num_symbol, Pos};
+use std::fmt;
+
+/// Human solving techniques, in order of difficulty.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    NakedPair,
+    HiddenPair,
+    Pointing,
+    BoxLineReduction,
+    NakedTriple,
+    HiddenTriple,
+    XWing,
+    Swordfish,
+    XYWing,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 11] = [
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::Pointing,
+        Technique::BoxLineReduction,
+        Technique::NakedTriple,
+        Technique::HiddenTriple,
+        Technique::XWing,
+        Technique::Swordfish,
+        Technique::XYWing,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Technique::NakedSingle => "Naked Single",
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::NakedPair => "Naked Pair",
+            Technique::HiddenPair => "Hidden Pair",
+            Technique::NakedTriple => "Naked Triple",
+            Technique::HiddenTriple => "Hidden Triple",
+            Technique::Pointing => "Pointing",
+            Technique::BoxLineReduction => "Box/Line Reduction",
+            Technique::XWing => "X-Wing",
+            Technique::Swordfish => "Swordfish",
+            Technique::XYWing => "XY-Wing",
+        }
+    }
+
+    /// The easiest difficulty of puzzle that can call for the technique.
+    pub fn difficulty(self) -> Difficulty {
+        match self {
+            Technique::NakedSingle | Technique::HiddenSingle => Difficulty::Easy,
+            Technique::NakedPair
+            | Technique::HiddenPair
+            | Technique::Pointing
+            | Technique::BoxLineReduction => Difficulty::Medium,
+            Technique::NakedTriple | Technique::HiddenTriple => Difficulty::Hard,
+            Technique::XWing | Technique::Swordfish | Technique::XYWing => Difficulty::Expert,
+        }
+    }
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How hard a puzzle is to solve by hand.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub enum Difficulty {
+    #[default]
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+    /// Can't be solved with the techniques known to the [`LogicSolver`].
+    Diabolical,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+        Difficulty::Diabolical,
+    ];
+
+    // Puzzles needing at least this many steps harder than singles are bumped up a difficulty.
+    const MANY_STEPS: usize = 6;
+
+    /// Rates a puzzle from the steps the [`LogicSolver`] took on it and whether they solved it.
+    /// The rating is that of the hardest technique used, bumped up one level if many of the steps
+    /// go beyond singles.
+    pub fn rate(steps: &[Step], solved: bool) -> Self {
+        if !solved {
+            return Difficulty::Diabolical;
+        }
+        let rating = steps
+            .iter()
+            .map(|step| step.technique.difficulty())
+            .max()
+            .unwrap_or_default();
+        let hard_steps = steps
+            .iter()
+            .filter(|step| step.technique.difficulty() > Difficulty::Easy)
+            .count();
+        if hard_steps >= Self::MANY_STEPS && rating < Difficulty::Expert {
+            Self::ALL[rating as usize + 1]
+        } else {
+            rating
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Expert => "Expert",
+            Difficulty::Diabolical => "Diabolical",
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A single deduction made by the [`LogicSolver`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Step {
+    pub technique: Technique,
+    /// The squares making up the pattern that allows the deduction.
+    pub cells: Vec<Pos>,
+    /// The numbers placed, if any.
+    pub placements: Vec<(Pos, u8)>,
+    /// The candidates removed, if any.
+    pub eliminations: Vec<(Pos, u8)>,
+}
+
+impl Step {
+    fn placement(technique: Technique, pos: Pos, n: u8) -> Self {
+        Self {
+            technique,
+            cells: vec![pos],
+            placements: vec![(pos, n)],
+            eliminations: Vec::new(),
+        }
+    }
+}
+
+/// Solves boards using only the techniques a person would, one [`Step`] at a time. Candidates
+/// are kept as masks with bit `n - 1` set if `n` is a candidate, the same layout as the note bits
+/// of the grid numbers.
+#[derive(Clone)]
+pub struct LogicSolver {
+    rank: usize,
+    size: usize,
+    // Row-major, with 0 being an empty square
+    cells: Vec<u8>,
+    // Candidates of the empty squares (0 for filled ones)
+    cands: Vec<u32>,
+    // Rows, then columns, then boxes
+    units: Vec<Vec<usize>>,
+}
+
+impl LogicSolver {
+    /// Creates a solver from the numbers of a board in row-major order (0 for empty squares),
+    /// computing the candidates of the empty squares from the numbers placed.
+    pub(crate) fn new(rank: usize, cells: &[u8]) -> Self {
+        let size = rank * rank;
+        let mut units = Vec::with_capacity(size * 3);
+        units.extend((0..size).map(|y| (0..size).map(|x| y * size + x).collect()));
+        units.extend((0..size).map(|x| (0..size).map(|y| y * size + x).collect()));
+        units.extend((0..size).map(|b| {
+            let (bx, by) = (b % rank * rank, b / rank * rank);
+            (0..size)
+                .map(|i| (by + i / rank) * size + bx + i % rank)
+                .collect()
+        }));
+        let mut solver = Self {
+            rank,
+            size,
+            cells: vec![0; size * size],
+            cands: vec![u32::MAX >> (32 - size); size * size],
+            units,
+        };
+        for (idx, &n) in cells.iter().enumerate() {
+            if n != 0 {
+                solver.place(idx, n);
+            }
+        }
+        solver
+    }
+
+    /// Same as `new`, but the candidates of empty squares are limited to the masks given for
+    /// them (a mask of 0 leaves the square's candidates alone).
+    pub(crate) fn with_candidates(rank: usize, cells: &[u8], cands: &[u32]) -> Self {
+        let mut solver = Self::new(rank, cells);
+        for (idx, &mask) in cands.iter().enumerate() {
+            if solver.cells[idx] == 0 && mask != 0 {
+                solver.cands[idx] &= mask;
+            }
+        }
+        solver
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    /// Returns the number in the square, 0 if it is empty.
+    pub fn num(&self, (x, y): Pos) -> u8 {
+        self.cells[y * self.size + x]
+    }
+
+    /// Returns the candidates of the square as a mask, 0 if the square is filled.
+    pub fn candidates(&self, (x, y): Pos) -> u32 {
+        self.cands[y * self.size + x]
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.cells.iter().all(|&n| n != 0)
+    }
+
+    /// Returns true if an empty square has no candidates left, meaning the board can't be solved
+    /// as it is.
+    pub fn is_broken(&self) -> bool {
+        self.cells
+            .iter()
+            .zip(&self.cands)
+            .any(|(&n, &cands)| n == 0 && cands == 0)
+    }
+
+    /// Finds the next step using the easiest technique possible. Returns None if no technique
+    /// makes progress.
+    pub fn next_step(&self) -> Option<Step> {
+        Technique::ALL
+            .into_iter()
+            .find_map(|technique| self.find(technique))
+    }
+
+    /// Looks for a step using only the given technique.
+    pub fn find(&self, technique: Technique) -> Option<Step> {
+        match technique {
+            Technique::NakedSingle => self.naked_single(),
+            Technique::HiddenSingle => self.hidden_single(),
+            Technique::NakedPair => self.naked_subset(2, technique),
+            Technique::HiddenPair => self.hidden_subset(2, technique),
+            Technique::NakedTriple => self.naked_subset(3, technique),
+            Technique::HiddenTriple => self.hidden_subset(3, technique),
+            Technique::Pointing => self.pointing(),
+            Technique::BoxLineReduction => self.box_line_reduction(),
+            Technique::XWing => self.fish(2, technique),
+            Technique::Swordfish => self.fish(3, technique),
+            Technique::XYWing => self.xy_wing(),
+        }
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        for &((x, y), n) in &step.placements {
+            self.place(y * self.size + x, n);
+        }
+        for &((x, y), n) in &step.eliminations {
+            self.cands[y * self.size + x] &= !(1 << (n - 1));
+        }
+    }
+
+    /// Applies steps until the board is solved or no technique makes progress, returning the
+    /// steps taken.
+    pub fn solve(&mut self) -> Vec<Step> {
+        let mut steps = Vec::new();
+        while !self.is_solved() && !self.is_broken() {
+            let Some(step) = self.next_step() else {
+                break;
+            };
+            self.apply(&step);
+            steps.push(step);
+        }
+        steps
+    }
+
+    /// Solves the board as far as possible and rates how hard it was.
+    pub fn rate(&mut self) -> Difficulty {
+        let steps = self.solve();
+        Difficulty::rate(&steps, self.is_solved())
+    }
+
+    /// Explains why the step, found on this solver's board, can be made. Squares are named like
+    /// `r3c4` for row 3, column 4, counting from 1.
+    pub fn explain(&self, step: &Step) -> String {
+        let cells = step
+            .cells
+            .iter()
+            .map(|&(x, y)| y * self.size + x)
+            .collect::<Vec<_>>();
+        let cell_names = list(step.cells.iter().map(|&pos| cell_name(pos)));
+        match step.technique {
+            Technique::NakedSingle => {
+                let (pos, n) = step.placements[0];
+                format!(
+                    "{} is the only number left that can go in {}",
+                    num_symbol(n),
+                    cell_name(pos)
+                )
+            }
+            Technique::HiddenSingle => {
+                let (pos, n) = step.placements[0];
+                let unit = self
+                    .units_of(cells[0])
+                    .into_iter()
+                    .find(|&unit| self.cells_with(unit, 1 << (n - 1)) == cells)
+                    .expect("missing hidden single unit");
+                format!(
+                    "{} can only go in {} within {}",
+                    num_symbol(n),
+                    cell_name(pos),
+                    self.unit_name(unit)
+                )
+            }
+            Technique::NakedPair | Technique::NakedTriple => {
+                let mask = cells.iter().fold(0, |mask, &idx| mask | self.cands[idx]);
+                let changed = step
+                    .eliminations
+                    .iter()
+                    .map(|&((x, y), _)| y * self.size + x);
+                let unit = self.unit_with(cells.iter().copied().chain(changed));
+                format!(
+                    "{cell_names} only have {} between them, so those numbers can be removed from \
+                     the rest of {}",
+                    mask_names(mask),
+                    self.unit_name(unit)
+                )
+            }
+            Technique::HiddenPair | Technique::HiddenTriple => {
+                let removed = step
+                    .eliminations
+                    .iter()
+                    .fold(0, |mask, &(_, n)| mask | 1 << (n - 1));
+                let mask = cells.iter().fold(0, |mask, &idx| mask | self.cands[idx]) & !removed;
+                let unit = (0..self.units.len())
+                    .find(|&unit| {
+                        cells.iter().all(|idx| self.units[unit].contains(idx))
+                            && self
+                                .digits()
+                                .filter(|&(_, bit)| mask & bit != 0)
+                                .all(|(_, bit)| {
+                                    self.cells_with(unit, bit)
+                                        .iter()
+                                        .all(|idx| cells.contains(idx))
+                                })
+                    })
+                    .expect("missing hidden subset unit");
+                format!(
+                    "{} only fit in {cell_names} within {}, so the other numbers can be removed \
+                     from those squares",
+                    mask_names(mask),
+                    self.unit_name(unit)
+                )
+            }
+            Technique::Pointing => {
+                let ((x, y), n) = step.eliminations[0];
+                let line = self.unit_with(cells.iter().copied().chain([y * self.size + x]));
+                format!(
+                    "Within {}, {} can only go in {}, so it can be removed from the rest of {}",
+                    self.unit_name(self.units_of(cells[0])[2]),
+                    num_symbol(n),
+                    self.unit_name(line),
+                    self.unit_name(line)
+                )
+            }
+            Technique::BoxLineReduction => {
+                let (_, n) = step.eliminations[0];
+                let line = self.unit_with(cells.iter().copied());
+                let bx = self.unit_name(self.units_of(cells[0])[2]);
+                format!(
+                    "Within {}, {} can only go in {bx}, so it can be removed from the rest of {bx}",
+                    self.unit_name(line),
+                    num_symbol(n)
+                )
+            }
+            Technique::XWing | Technique::Swordfish => {
+                let (_, n) = step.eliminations[0];
+                let mut rows = step.cells.iter().map(|&(_, y)| y + 1).collect::<Vec<_>>();
+                let mut cols = step.cells.iter().map(|&(x, _)| x + 1).collect::<Vec<_>>();
+                rows.sort_unstable();
+                rows.dedup();
+                cols.sort_unstable();
+                cols.dedup();
+                let (mut base, mut cover) = (("rows", rows), ("columns", cols));
+                // The candidates are removed from the cover lines, outside of the base ones
+                if step
+                    .eliminations
+                    .iter()
+                    .any(|&((_, y), _)| base.1.contains(&(y + 1)))
+                {
+                    std::mem::swap(&mut base, &mut cover);
+                }
+                format!(
+                    "Within {} {}, {} can only go in {} {}, so it can be removed from the rest of \
+                     those {}",
+                    base.0,
+                    list(base.1.into_iter()),
+                    num_symbol(n),
+                    cover.0,
+                    list(cover.1.into_iter()),
+                    cover.0
+                )
+            }
+            Technique::XYWing => {
+                let (_, z) = step.eliminations[0];
+                format!(
+                    "{} has to be {}, and either way one of {} is {}, so {} can be removed from \
+                     the squares that see both of them",
+                    cell_name(step.cells[0]),
+                    mask_names(self.cands[cells[0]]).replace(" and ", " or "),
+                    list(step.cells[1..].iter().map(|&pos| cell_name(pos))),
+                    num_symbol(z),
+                    num_symbol(z)
+                )
+            }
+        }
+    }
+
+    fn place(&mut self, idx: usize, n: u8) {
+        let bit = 1 << (n - 1);
+        self.cells[idx] = n;
+        self.cands[idx] = 0;
+        for unit in self.units_of(idx) {
+            for i in 0..self.size {
+                let peer = self.units[unit][i];
+                self.cands[peer] &= !bit;
+            }
+        }
+    }
+
+    fn pos(&self, idx: usize) -> Pos {
+        (idx % self.size, idx / self.size)
+    }
+
+    fn units_of(&self, idx: usize) -> [usize; 3] {
+        let (x, y) = self.pos(idx);
+        let b = y / self.rank * self.rank + x / self.rank;
+        [y, self.size + x, self.size * 2 + b]
+    }
+
+    fn sees(&self, a: usize, b: usize) -> bool {
+        let (ua, ub) = (self.units_of(a), self.units_of(b));
+        a != b && (ua[0] == ub[0] || ua[1] == ub[1] || ua[2] == ub[2])
+    }
+
+    fn unit_name(&self, unit: usize) -> String {
+        let (kind, i) = match unit / self.size {
+            0 => ("row", unit),
+            1 => ("column", unit - self.size),
+            _ => ("box", unit - self.size * 2),
+        };
+        format!("{kind} {}", i + 1)
+    }
+
+    // The first unit holding all of the squares
+    fn unit_with(&self, cells: impl Iterator<Item = usize> + Clone) -> usize {
+        (0..self.units.len())
+            .find(|&unit| cells.clone().all(|idx| self.units[unit].contains(&idx)))
+            .expect("squares not in one unit")
+    }
+
+    // The empty squares of the unit that have the candidate
+    fn cells_with(&self, unit: usize, bit: u32) -> Vec<usize> {
+        self.units[unit]
+            .iter()
+            .copied()
+            .filter(|&idx| self.cands[idx] & bit != 0)
+            .collect()
+    }
+
+    fn digits(&self) -> impl Iterator<Item = (u8, u32)> {
+        (1..=self.size as u8).map(|n| (n, 1 << (n - 1)))
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        (0..self.cells.len())
+            .find(|&idx| self.cells[idx] == 0 && self.cands[idx].count_ones() == 1)
+            .map(|idx| {
+                let n = self.cands[idx].trailing_zeros() as u8 + 1;
+                Step::placement(Technique::NakedSingle, self.pos(idx), n)
+            })
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        for unit in &self.units {
+            // Candidates seen at least once and at least twice in the unit
+            let (mut once, mut twice) = (0u32, 0u32);
+            for &idx in unit {
+                twice |= once & self.cands[idx];
+                once |= self.cands[idx];
+            }
+            let singles = once & !twice;
+            if singles == 0 {
+                continue;
+            }
+            let bit = singles & singles.wrapping_neg();
+            let idx = unit
+                .iter()
+                .copied()
+                .find(|&idx| self.cands[idx] & bit != 0)
+                .expect("missing hidden single square");
+            let n = bit.trailing_zeros() as u8 + 1;
+            return Some(Step::placement(Technique::HiddenSingle, self.pos(idx), n));
+        }
+        None
+    }
+
+    // k squares of a unit that only have k candidates between them
+    fn naked_subset(&self, k: usize, technique: Technique) -> Option<Step> {
+        for unit in &self.units {
+            let empties = unit
+                .iter()
+                .copied()
+                .filter(|&idx| {
+                    let count = self.cands[idx].count_ones() as usize;
+                    self.cells[idx] == 0 && (2..=k).contains(&count)
+                })
+                .collect::<Vec<_>>();
+            for combo in combinations(empties.len(), k) {
+                let cells = combo.iter().map(|&i| empties[i]).collect::<Vec<_>>();
+                let mask = cells.iter().fold(0, |mask, &idx| mask | self.cands[idx]);
+                if mask.count_ones() as usize != k {
+                    continue;
+                }
+                let others = unit.iter().copied().filter(|idx| !cells.contains(idx));
+                let eliminations = self.eliminations(others, mask);
+                if !eliminations.is_empty() {
+                    return Some(self.elimination_step(technique, &cells, eliminations));
+                }
+            }
+        }
+        None
+    }
+
+    // k candidates of a unit that only fit in k of its squares
+    fn hidden_subset(&self, k: usize, technique: Technique) -> Option<Step> {
+        for unit in 0..self.units.len() {
+            let digits = self
+                .digits()
+                .map(|(_, bit)| (bit, self.cells_with(unit, bit)))
+                .filter(|(_, cells)| (2..=k).contains(&cells.len()))
+                .collect::<Vec<_>>();
+            for combo in combinations(digits.len(), k) {
+                let mut cells = Vec::new();
+                let mut mask = 0;
+                for &i in &combo {
+                    mask |= digits[i].0;
+                    for &idx in &digits[i].1 {
+                        if !cells.contains(&idx) {
+                            cells.push(idx);
+                        }
+                    }
+                }
+                if cells.len() != k {
+                    continue;
+                }
+                cells.sort_unstable();
+                let eliminations = self.eliminations(cells.iter().copied(), !mask);
+                if !eliminations.is_empty() {
+                    return Some(self.elimination_step(technique, &cells, eliminations));
+                }
+            }
+        }
+        None
+    }
+
+    // A candidate confined to one row or column of a box can be removed from the rest of that
+    // row or column.
+    fn pointing(&self) -> Option<Step> {
+        for unit in self.size * 2..self.size * 3 {
+            for (_, bit) in self.digits() {
+                let cells = self.cells_with(unit, bit);
+                if cells.len() < 2 {
+                    continue;
+                }
+                for line in 0..2 {
+                    let line_unit = self.units_of(cells[0])[line];
+                    if cells
+                        .iter()
+                        .any(|&idx| self.units_of(idx)[line] != line_unit)
+                    {
+                        continue;
+                    }
+                    let others = self.units[line_unit]
+                        .iter()
+                        .copied()
+                        .filter(|&idx| self.units_of(idx)[2] != unit);
+                    let eliminations = self.eliminations(others, bit);
+                    if !eliminations.is_empty() {
+                        return Some(self.elimination_step(
+                            Technique::Pointing,
+                            &cells,
+                            eliminations,
+                        ));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // A candidate confined to one box within a row or column can be removed from the rest of the
+    // box.
+    fn box_line_reduction(&self) -> Option<Step> {
+        for unit in 0..self.size * 2 {
+            for (_, bit) in self.digits() {
+                let cells = self.cells_with(unit, bit);
+                if cells.len() < 2 {
+                    continue;
+                }
+                let box_unit = self.units_of(cells[0])[2];
+                if cells.iter().any(|&idx| self.units_of(idx)[2] != box_unit) {
+                    continue;
+                }
+                let others = self.units[box_unit]
+                    .iter()
+                    .copied()
+                    .filter(|idx| !self.units[unit].contains(idx));
+                let eliminations = self.eliminations(others, bit);
+                if !eliminations.is_empty() {
+                    return Some(self.elimination_step(
+                        Technique::BoxLineReduction,
+                        &cells,
+                        eliminations,
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    // X-Wing (k = 2) and Swordfish (k = 3): a candidate confined to the same k columns in k rows
+    // (or vice versa) can be removed from the rest of those columns.
+    fn fish(&self, k: usize, technique: Technique) -> Option<Step> {
+        for (_, bit) in self.digits() {
+            // Rows as the base lines, then columns
+            for base in [0, self.size] {
+                let cross = self.size - base;
+                let lines = (base..base + self.size)
+                    .map(|unit| (unit, self.cells_with(unit, bit)))
+                    .filter(|(_, cells)| (2..=k).contains(&cells.len()))
+                    .collect::<Vec<_>>();
+                for combo in combinations(lines.len(), k) {
+                    let mut cells = Vec::new();
+                    let mut covers = Vec::new();
+                    for &i in &combo {
+                        for &idx in &lines[i].1 {
+                            cells.push(idx);
+                            let cover = self.units_of(idx)[cross / self.size];
+                            if !covers.contains(&cover) {
+                                covers.push(cover);
+                            }
+                        }
+                    }
+                    if covers.len() != k {
+                        continue;
+                    }
+                    cells.sort_unstable();
+                    let base_units = combo.iter().map(|&i| lines[i].0).collect::<Vec<_>>();
+                    let others = covers
+                        .iter()
+                        .flat_map(|&cover| self.units[cover].iter().copied())
+                        .filter(|&idx| !base_units.contains(&self.units_of(idx)[base / self.size]));
+                    let eliminations = self.eliminations(others, bit);
+                    if !eliminations.is_empty() {
+                        return Some(self.elimination_step(technique, &cells, eliminations));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // A pivot with candidates XY that sees squares with XZ and YZ means Z can be removed from
+    // every square seeing both of those.
+    fn xy_wing(&self) -> Option<Step> {
+        let pairs = (0..self.cells.len())
+            .filter(|&idx| self.cands[idx].count_ones() == 2)
+            .collect::<Vec<_>>();
+        for &pivot in &pairs {
+            let pc = self.cands[pivot];
+            let wings = pairs
+                .iter()
+                .copied()
+                .filter(|&idx| {
+                    let common = self.cands[idx] & pc;
+                    self.sees(pivot, idx) && common.count_ones() == 1
+                })
+                .collect::<Vec<_>>();
+            for (i, &a) in wings.iter().enumerate() {
+                for &b in &wings[i + 1..] {
+                    let (ac, bc) = (self.cands[a], self.cands[b]);
+                    let z = ac & bc & !pc;
+                    // Each wing has to share a different candidate with the pivot
+                    if z.count_ones() != 1 || ac & pc == bc & pc {
+                        continue;
+                    }
+                    let others = (0..self.cells.len())
+                        .filter(|&idx| idx != pivot && self.sees(a, idx) && self.sees(b, idx));
+                    let eliminations = self.eliminations(others, z);
+                    if !eliminations.is_empty() {
+                        return Some(self.elimination_step(
+                            Technique::XYWing,
+                            &[pivot, a, b],
+                            eliminations,
+                        ));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // The candidates in the mask that can be removed from the squares
+    fn eliminations(&self, cells: impl Iterator<Item = usize>, mask: u32) -> Vec<(Pos, u8)> {
+        let mut elims = Vec::new();
+        for idx in cells {
+            let mut bits = self.cands[idx] & mask;
+            while bits != 0 {
+                let n = bits.trailing_zeros() as u8 + 1;
+                elims.push((self.pos(idx), n));
+                bits &= bits - 1;
+            }
+        }
+        elims.sort_unstable_by_key(|&((x, y), n)| (y, x, n));
+        elims.dedup();
+        elims
+    }
+
+    fn elimination_step(
+        &self,
+        technique: Technique,
+        cells: &[usize],
+        eliminations: Vec<(Pos, u8)>,
+    ) -> Step {
+        Step {
+            technique,
+            cells: cells.iter().map(|&idx| self.pos(idx)).collect(),
+            placements: Vec::new(),
+            eliminations,
+        }
+    }
+}
+
+fn cell_name((x, y): Pos) -> String {
+    format!("r{}c{}", y + 1, x + 1)
+}
+
+// The numbers in the mask, written out as a list
+fn mask_names(mask: u32) -> String {
+    list(
+        (0..32u8)
+            .filter(|i| mask & 1 << i != 0)
+            .map(|i| num_symbol(i + 1)),
+    )
+}
+
+// Joins the items as `a, b and c`
+fn list<T: fmt::Display>(items: impl Iterator<Item = T>) -> String {
+    let items = items.map(|item| item.to_string()).collect::<Vec<_>>();
+    match items.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
+// All of the ways to choose k of the indexes 0..n, in lexicographic order.
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    fn helper(start: usize, n: usize, k: usize, curr: &mut Vec<usize>, res: &mut Vec<Vec<usize>>) {
+        if curr.len() == k {
+            res.push(curr.clone());
+            return;
+        }
+        for i in start..n {
+            curr.push(i);
+            helper(i + 1, n, k, curr, res);
+            curr.pop();
+        }
+    }
+    let mut res = Vec::new();
+    helper(0, n, k, &mut Vec::with_capacity(k), &mut res);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid3x3, GridNum, SudokuGrid};
+
+    fn bits(nums: &[u8]) -> u32 {
+        nums.iter().fold(0, |mask, n| mask | 1 << (n - 1))
+    }
+
+    // An empty board with only the given candidates set, all others having every candidate
+    fn with_cands(cands: &[(Pos, &[u8])]) -> LogicSolver {
+        let mut masks = vec![0; 81];
+        for &((x, y), nums) in cands {
+            masks[y * 9 + x] = bits(nums);
+        }
+        LogicSolver::with_candidates(3, &[0; 81], &masks)
+    }
+
+    #[test]
+    fn naked_pair() {
+        let solver = with_cands(&[((0, 0), &[1, 2]), ((4, 0), &[1, 2])]);
+        let step = solver.find(Technique::NakedPair).expect("no naked pair");
+        assert_eq!(step.cells, vec![(0, 0), (4, 0)]);
+        assert!(step.eliminations.contains(&((1, 0), 1)));
+        assert!(step.eliminations.contains(&((8, 0), 2)));
+        assert!(!step.eliminations.iter().any(|&(pos, _)| pos == (0, 0)));
+        assert_eq!(
+            solver.explain(&step),
+            "r1c1 and r1c5 only have 1 and 2 between them, so those numbers can be removed from \
+             the rest of row 1"
+        );
+    }
+
+    #[test]
+    fn hidden_pair() {
+        // 1 and 2 can only go in r1c1 and r1c5
+        let mut masks = vec![0; 81];
+        for x in [1, 2, 3, 5, 6, 7, 8] {
+            masks[x] = bits(&[3, 4, 5, 6, 7, 8, 9]);
+        }
+        let solver = LogicSolver::with_candidates(3, &[0; 81], &masks);
+        let step = solver.find(Technique::HiddenPair).expect("no hidden pair");
+        assert_eq!(step.cells, vec![(0, 0), (4, 0)]);
+        assert_eq!(step.eliminations.len(), 14);
+        assert!(step
+            .eliminations
+            .iter()
+            .all(|&((x, y), n)| (x == 0 || x == 4) && y == 0 && n > 2));
+        assert_eq!(
+            solver.explain(&step),
+            "1 and 2 only fit in r1c1 and r1c5 within row 1, so the other numbers can be removed \
+             from those squares"
+        );
+    }
+
+    #[test]
+    fn naked_triple() {
+        let solver = with_cands(&[((0, 0), &[1, 2]), ((3, 0), &[2, 3]), ((6, 0), &[1, 3])]);
+        let step = solver
+            .find(Technique::NakedTriple)
+            .expect("no naked triple");
+        assert_eq!(step.cells, vec![(0, 0), (3, 0), (6, 0)]);
+        assert_eq!(step.eliminations.len(), 18);
+        assert!(step
+            .eliminations
+            .iter()
+            .all(|&((x, y), n)| x % 3 != 0 && y == 0 && n <= 3));
+        assert_eq!(
+            solver.explain(&step),
+            "r1c1, r1c4 and r1c7 only have 1, 2 and 3 between them, so those numbers can be \
+             removed from the rest of row 1"
+        );
+    }
+
+    #[test]
+    fn hidden_triple() {
+        // 1, 2 and 3 can only go in r1c1, r1c4 and r1c7
+        let mut masks = vec![0; 81];
+        for x in [1, 2, 4, 5, 7, 8] {
+            masks[x] = bits(&[4, 5, 6, 7, 8, 9]);
+        }
+        let solver = LogicSolver::with_candidates(3, &[0; 81], &masks);
+        let step = solver
+            .find(Technique::HiddenTriple)
+            .expect("no hidden triple");
+        assert_eq!(step.cells, vec![(0, 0), (3, 0), (6, 0)]);
+        assert_eq!(step.eliminations.len(), 18);
+        assert!(step
+            .eliminations
+            .iter()
+            .all(|&((x, y), n)| x % 3 == 0 && y == 0 && n > 3));
+        assert_eq!(
+            solver.explain(&step),
+            "1, 2 and 3 only fit in r1c1, r1c4 and r1c7 within row 1, so the other numbers can be \
+             removed from those squares"
+        );
+    }
+
+    #[test]
+    fn pointing() {
+        // 1 can only go in the top row of the first box
+        let mut masks = vec![0; 81];
+        for pos in [(0, 1), (1, 1), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            masks[pos.1 * 9 + pos.0] = bits(&[2, 3, 4, 5, 6, 7, 8, 9]);
+        }
+        let solver = LogicSolver::with_candidates(3, &[0; 81], &masks);
+        let step = solver.find(Technique::Pointing).expect("no pointing");
+        assert_eq!(step.cells, vec![(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(step.eliminations.len(), 6);
+        assert!(step
+            .eliminations
+            .iter()
+            .all(|&((_, y), n)| y == 0 && n == 1));
+        assert_eq!(
+            solver.explain(&step),
+            "Within box 1, 1 can only go in row 1, so it can be removed from the rest of row 1"
+        );
+    }
+
+    #[test]
+    fn box_line_reduction() {
+        // 1 can only go in the first box of the top row
+        let mut masks = vec![0; 81];
+        masks[3..9].fill(bits(&[2, 3, 4, 5, 6, 7, 8, 9]));
+        let solver = LogicSolver::with_candidates(3, &[0; 81], &masks);
+        let step = solver
+            .find(Technique::BoxLineReduction)
+            .expect("no box/line reduction");
+        assert_eq!(step.cells, vec![(0, 0), (1, 0), (2, 0)]);
+        assert_eq!(step.eliminations.len(), 6);
+        assert!(step
+            .eliminations
+            .iter()
+            .all(|&((x, y), n)| x < 3 && y > 0 && y < 3 && n == 1));
+        assert_eq!(
+            solver.explain(&step),
+            "Within row 1, 1 can only go in box 1, so it can be removed from the rest of box 1"
+        );
+    }
+
+    #[test]
+    fn x_wing() {
+        // 1 only fits in columns 1 and 7 of rows 0 and 4
+        let mut masks = vec![0; 81];
+        for y in [0, 4] {
+            for x in 0..9 {
+                if x != 1 && x != 7 {
+                    masks[y * 9 + x] = bits(&[2, 3, 4, 5, 6, 7, 8, 9]);
+                }
+            }
+        }
+        let solver = LogicSolver::with_candidates(3, &[0; 81], &masks);
+        let step = solver.find(Technique::XWing).expect("no x-wing");
+        assert_eq!(step.cells, vec![(1, 0), (7, 0), (1, 4), (7, 4)]);
+        assert_eq!(step.eliminations.len(), 14);
+        assert!(step
+            .eliminations
+            .iter()
+            .all(|&((x, y), n)| (x == 1 || x == 7) && y != 0 && y != 4 && n == 1));
+        assert_eq!(
+            solver.explain(&step),
+            "Within rows 1 and 5, 1 can only go in columns 2 and 8, so it can be removed from the \
+             rest of those columns"
+        );
+    }
+
+    #[test]
+    fn swordfish() {
+        // 1 only fits in columns 1, 4 and 7 of rows 0, 3 and 6
+        let mut masks = vec![0; 81];
+        for y in [0, 3, 6] {
+            for x in 0..9 {
+                if x % 3 != 1 {
+                    masks[y * 9 + x] = bits(&[2, 3, 4, 5, 6, 7, 8, 9]);
+                }
+            }
+        }
+        let solver = LogicSolver::with_candidates(3, &[0; 81], &masks);
+        assert!(solver.find(Technique::XWing).is_none());
+        let step = solver.find(Technique::Swordfish).expect("no swordfish");
+        assert_eq!(step.cells.len(), 9);
+        assert_eq!(step.eliminations.len(), 18);
+        assert!(step
+            .eliminations
+            .iter()
+            .all(|&((x, y), n)| x % 3 == 1 && y % 3 != 0 && n == 1));
+        assert_eq!(
+            solver.explain(&step),
+            "Within rows 1, 4 and 7, 1 can only go in columns 2, 5 and 8, so it can be removed \
+             from the rest of those columns"
+        );
+    }
+
+    #[test]
+    fn xy_wing() {
+        let solver = with_cands(&[((0, 0), &[1, 2]), ((4, 0), &[1, 3]), ((0, 4), &[2, 3])]);
+        let step = solver.find(Technique::XYWing).expect("no xy-wing");
+        assert_eq!(step.cells, vec![(0, 0), (4, 0), (0, 4)]);
+        assert_eq!(step.eliminations, vec![((4, 4), 3)]);
+        assert_eq!(
+            solver.explain(&step),
+            "r1c1 has to be 1 or 2, and either way one of r1c5 and r5c1 is 3, so 3 can be removed \
+             from the squares that see both of them"
+        );
+    }
+
+    #[test]
+    fn rate() {
+        let single = |technique| Step {
+            technique,
+            cells: Vec::new(),
+            placements: Vec::new(),
+            eliminations: Vec::new(),
+        };
+        let mut steps = vec![single(Technique::NakedSingle); 40];
+        assert_eq!(Difficulty::rate(&steps, true), Difficulty::Easy);
+        assert_eq!(Difficulty::rate(&steps, false), Difficulty::Diabolical);
+        steps.push(single(Technique::Pointing));
+        assert_eq!(Difficulty::rate(&steps, true), Difficulty::Medium);
+        steps.push(single(Technique::XWing));
+        assert_eq!(Difficulty::rate(&steps, true), Difficulty::Expert);
+        steps.pop();
+        steps.extend(vec![single(Technique::NakedPair); 5]);
+        assert_eq!(Difficulty::rate(&steps, true), Difficulty::Hard);
+    }
+
+    #[test]
+    fn pointing_before_triple() {
+        // A naked triple turns up before the first pointing step, but pointing is easier and all
+        // the puzzle needs
+        let grid = Grid3x3::from_line(
+            "...6.3...2....47...5.79.....3.....4.1...6.9..5..31...7....4.83.4.........79..5..6",
+        )
+        .unwrap();
+        let mut solver = grid.logic_solver();
+        let step = loop {
+            let step = solver.next_step().expect("stuck");
+            if step.technique.difficulty() > Difficulty::Easy {
+                break step;
+            }
+            solver.apply(&step);
+        };
+        assert_eq!(step.technique, Technique::Pointing);
+        assert!(solver.find(Technique::NakedTriple).is_some());
+        assert_eq!(grid.difficulty(), Difficulty::Medium);
+    }
+
+    #[test]
+    fn steps_match_solution() {
+        for _ in 0..5 {
+            let mut grid = Grid3x3::randomized();
+            grid.remove_nums_unique(60);
+            let mut solution = grid.clone();
+            assert!(solution.solve());
+
+            let (steps, solved) = grid.solve_logically();
+            for step in &steps {
+                for &(pos, n) in &step.placements {
+                    assert_eq!(solution[pos].num_or_zero(), n, "bad {}", step.technique);
+                }
+                for &(pos, n) in &step.eliminations {
+                    assert_ne!(solution[pos].num_or_zero(), n, "bad {}", step.technique);
+                }
+            }
+            if solved {
+                let placed = steps
+                    .iter()
+                    .map(|step| step.placements.len())
+                    .sum::<usize>();
+                let empty = (0..81).filter(|&i| grid[i / 9][i % 9].num_or_zero() == 0);
+                assert_eq!(placed, empty.count());
+            }
+        }
+    }
+}
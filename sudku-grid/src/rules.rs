@@ -0,0 +1,968 @@
+use crate::{
+    grid::positions,
+    solver::{classic_regions, Solver},
+    Arrow, Constraint, GridNum, Kropki, Move, Pos, SudkuError, SudkuResult, SudokuGrid, Thermo,
+};
+use rand::{seq::SliceRandom, Rng};
+use std::{fmt, rc::Rc};
+
+/// A group of squares in Killer Sudoku whose numbers have to add up to `sum`, without repeating
+/// any number.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cage {
+    pub sum: u32,
+    pub cells: Vec<Pos>,
+}
+
+impl Cage {
+    pub fn new(sum: u32, cells: Vec<Pos>) -> Self {
+        Self { sum, cells }
+    }
+
+    /// The square the sum is written in, which is the top left one.
+    pub fn label_pos(&self) -> Pos {
+        self.cells
+            .iter()
+            .copied()
+            .min_by_key(|&(x, y)| (y, x))
+            .expect("empty cage")
+    }
+}
+
+/// The rules a board is played with on top of the classic ones, where every row, column and box
+/// holds each number once. The default is no extra rules.
+///
+/// Boards don't know their rules, so the checks and solving here take the board.
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Rules {
+    /// Killer Sudoku cages, which can't overlap.
+    pub cages: Vec<Cage>,
+    /// Extra regions that also hold each number once, like the diagonals of Sudoku X. They have
+    /// one square for each number, and can overlap the others.
+    pub regions: Vec<Vec<Pos>>,
+    /// The box each square is in (numbered from 0), row by row, for the irregular boxes of Jigsaw
+    /// Sudoku. None for the usual square boxes.
+    pub boxes: Option<Vec<u8>>,
+    /// Thermometers, arrows and Kropki dots drawn over the board (see [`Constraint`]).
+    pub thermos: Vec<Thermo>,
+    pub arrows: Vec<Arrow>,
+    pub dots: Vec<Kropki>,
+}
+
+impl Rules {
+    pub fn killer(cages: Vec<Cage>) -> Self {
+        Self {
+            cages,
+            ..Self::default()
+        }
+    }
+
+    /// Sudoku X, where both of the long diagonals hold each number once too.
+    pub fn diagonal<G: SudokuGrid>() -> Self {
+        let last = G::SIZE - 1;
+        Self {
+            regions: vec![
+                (0..G::SIZE).map(|i| (i, i)).collect(),
+                (0..G::SIZE).map(|i| (last - i, i)).collect(),
+            ],
+            ..Self::default()
+        }
+    }
+
+    /// Windoku (or Hyper Sudoku), with extra boxes between the usual ones that hold each number
+    /// once too. They're a square in from the edges and a square apart, so a 9x9 board has four.
+    pub fn windoku<G: SudokuGrid>() -> Self {
+        let rank = G::RANK;
+        let starts = (0..rank - 1)
+            .map(|i| 1 + i * (rank + 1))
+            .collect::<Vec<_>>();
+        let regions = starts
+            .iter()
+            .flat_map(|&y| starts.iter().map(move |&x| (x, y)))
+            .map(|(x, y)| (0..G::SIZE).map(|i| (x + i % rank, y + i / rank)).collect())
+            .collect();
+        Self {
+            regions,
+            ..Self::default()
+        }
+    }
+
+    /// Jigsaw Sudoku, with the boxes laid out as given (see [`boxes`](Self::boxes)).
+    pub fn jigsaw(boxes: Vec<u8>) -> Self {
+        Self {
+            boxes: Some(boxes),
+            ..Self::default()
+        }
+    }
+
+    /// Returns true if there are no extra rules.
+    pub fn is_classic(&self) -> bool {
+        self.constraints().next().is_none() && self.regions.is_empty() && self.boxes.is_none()
+    }
+
+    /// The box the square is in, following the Jigsaw layout if there is one. The usual boxes
+    /// are numbered row by row.
+    pub fn box_of<G: SudokuGrid>(&self, (x, y): Pos) -> usize {
+        match &self.boxes {
+            Some(boxes) => boxes[y * G::SIZE + x] as usize,
+            None => y / G::RANK * G::RANK + x / G::RANK,
+        }
+    }
+
+    /// Like [`SudokuGrid::get_box_for`], the squares in the same box as the square (including
+    /// it), following the Jigsaw layout if there is one.
+    pub fn box_cells<G: SudokuGrid>(&self, pos: Pos) -> Vec<Pos> {
+        let b = self.box_of::<G>(pos);
+        positions::<G>()
+            .filter(|&p| self.box_of::<G>(p) == b)
+            .collect()
+    }
+
+    /// The other squares that can't hold the same number as the square, because they share a
+    /// region with it, each given once.
+    pub fn peers<G: SudokuGrid>(&self, pos: Pos) -> Vec<Pos> {
+        let mut peers = Vec::new();
+        for region in self.all_regions::<G>() {
+            if region.contains(&pos) {
+                for p in region {
+                    if p != pos && !peers.contains(&p) {
+                        peers.push(p);
+                    }
+                }
+            }
+        }
+        peers
+    }
+
+    /// Every constraint on the board: the cages, then the thermometers, arrows and Kropki dots.
+    pub fn constraints(&self) -> impl Iterator<Item = &dyn Constraint> {
+        let cages = self.cages.iter().map(|c| c as &dyn Constraint);
+        let thermos = self.thermos.iter().map(|c| c as &dyn Constraint);
+        let arrows = self.arrows.iter().map(|c| c as &dyn Constraint);
+        let dots = self.dots.iter().map(|c| c as &dyn Constraint);
+        cages.chain(thermos).chain(arrows).chain(dots)
+    }
+
+    pub fn cage_at(&self, pos: Pos) -> Option<&Cage> {
+        self.cages.iter().find(|cage| cage.cells.contains(&pos))
+    }
+
+    /// Returns true if the square is in one of the extra regions.
+    pub fn in_region(&self, pos: Pos) -> bool {
+        self.regions.iter().any(|region| region.contains(&pos))
+    }
+
+    /// Every region that has to hold each number once: the rows, then the columns, the boxes
+    /// (following the Jigsaw layout if there is one), and the extra regions.
+    pub fn all_regions<G: SudokuGrid>(&self) -> Vec<Vec<Pos>> {
+        let mut all = classic_regions(G::RANK)
+            .into_iter()
+            .map(|region| {
+                region
+                    .into_iter()
+                    .map(|i| (i % G::SIZE, i / G::SIZE))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        if let Some(boxes) = &self.boxes {
+            for (b, region) in all[2 * G::SIZE..].iter_mut().enumerate() {
+                *region = positions::<G>()
+                    .filter(|&(x, y)| boxes[y * G::SIZE + x] as usize == b)
+                    .collect();
+            }
+        }
+        all.extend(self.regions.iter().cloned());
+        all
+    }
+
+    /// Fails if a cage is empty, has squares off the board or in another cage, or has more squares
+    /// than there are numbers, if a region doesn't have one square on the board for each number,
+    /// if the Jigsaw boxes aren't each made of that many connected squares, or if a constraint
+    /// doesn't fit the board: thermometers and arrows need a path of squares next to each other
+    /// (diagonally too), and Kropki dots two squares side by side.
+    pub fn check<G: SudokuGrid>(&self) -> SudkuResult<()> {
+        let on_board = |&(x, y): &Pos| x < G::SIZE && y < G::SIZE;
+        let mut caged = vec![false; G::CELLS];
+        for (i, cage) in self.cages.iter().enumerate() {
+            if cage.cells.is_empty() || cage.cells.len() > G::SIZE {
+                return Err(SudkuError::InvalidCage(i));
+            }
+            for &(x, y) in &cage.cells {
+                if !on_board(&(x, y)) || caged[y * G::SIZE + x] {
+                    return Err(SudkuError::InvalidCage(i));
+                }
+                caged[y * G::SIZE + x] = true;
+            }
+        }
+        for (i, region) in self.regions.iter().enumerate() {
+            let distinct = region
+                .iter()
+                .enumerate()
+                .all(|(j, pos)| !region[..j].contains(pos));
+            if region.len() != G::SIZE || !distinct || !region.iter().all(on_board) {
+                return Err(SudkuError::InvalidRegion(i));
+            }
+        }
+        if let Some(boxes) = &self.boxes {
+            let mut sizes = vec![0; G::SIZE];
+            for &b in boxes {
+                match sizes.get_mut(b as usize) {
+                    Some(size) => *size += 1,
+                    None => return Err(SudkuError::InvalidBoxes),
+                }
+            }
+            if boxes.len() != G::CELLS
+                || sizes.iter().any(|&n| n != G::SIZE)
+                || !(0..G::SIZE).all(|b| is_connected::<G>(boxes, b as u8))
+            {
+                return Err(SudkuError::InvalidBoxes);
+            }
+        }
+        let is_path = |cells: &[Pos]| {
+            cells.windows(2).all(|w| {
+                let (dx, dy) = (w[0].0.abs_diff(w[1].0), w[0].1.abs_diff(w[1].1));
+                dx.max(dy) == 1
+            })
+        };
+        let thermos = self
+            .thermos
+            .iter()
+            .map(|t| t.cells.len() <= G::SIZE && is_path(&t.cells));
+        let arrows = self
+            .arrows
+            .iter()
+            .map(|a| a.cells.len() >= 2 && is_path(&a.cells));
+        let dots = self.dots.iter().map(|d| {
+            let [(ax, ay), (bx, by)] = d.cells;
+            ax.abs_diff(bx) + ay.abs_diff(by) == 1
+        });
+        let shapes = thermos.chain(arrows).chain(dots);
+        for (i, (constraint, shaped)) in self
+            .constraints()
+            .skip(self.cages.len())
+            .zip(shapes)
+            .enumerate()
+        {
+            let cells = constraint.cells();
+            let distinct = cells
+                .iter()
+                .enumerate()
+                .all(|(j, pos)| !cells[..j].contains(pos));
+            if !shaped || cells.is_empty() || !distinct || !cells.iter().all(on_board) {
+                return Err(SudkuError::InvalidConstraint(self.cages.len() + i));
+            }
+        }
+        Ok(())
+    }
+
+    /// Like [`SudokuGrid::pos_is_valid`] (with the Jigsaw boxes if there are any), but the number
+    /// also can't repeat in any extra region, and has to fit every constraint on the square: it
+    /// can't repeat in its cage or take the cage over its sum (or under it, for the cage's last
+    /// square), and so on.
+    pub fn pos_is_valid<G: SudokuGrid>(&self, grid: &G, pos: Pos, n: u8) -> bool {
+        if n == 0 {
+            return true;
+        }
+        let repeated = self.all_regions::<G>().iter().any(|region| {
+            region.contains(&pos)
+                && region
+                    .iter()
+                    .any(|&p| p != pos && grid[p].num_or_zero() == n)
+        });
+        !repeated
+            && self.constraints().all(|constraint| {
+                let cells = constraint.cells();
+                if !cells.contains(&pos) {
+                    return true;
+                }
+                let nums = cells
+                    .iter()
+                    .map(|&p| if p == pos { n } else { grid[p].num_or_zero() })
+                    .collect::<Vec<_>>();
+                constraint.fits(&nums, G::SIZE as u8)
+            })
+    }
+
+    /// Like [`SudokuGrid::is_valid`], but no number can repeat in an extra region either, and
+    /// every constraint has to be followed, like cages adding up. A broken constraint is pointed
+    /// out by its first square.
+    pub fn is_valid<G: SudokuGrid>(&self, grid: &G) -> Option<Pos> {
+        if let Some(pos) = positions::<G>().find(|&pos| grid[pos].num_or_zero() == 0) {
+            return Some(pos);
+        }
+        for region in self.all_regions::<G>() {
+            let mut seen = 0u64;
+            for pos in region {
+                let bit = 1 << grid[pos].num_or_zero();
+                if seen & bit != 0 {
+                    return Some(pos);
+                }
+                seen |= bit;
+            }
+        }
+        self.constraints()
+            .find(|constraint| {
+                let nums = constraint.cells().iter().map(|&p| grid[p].num_or_zero());
+                !constraint.fits(&nums.collect::<Vec<_>>(), G::SIZE as u8)
+            })
+            .map(|constraint| constraint.cells()[0])
+    }
+
+    /// Like [`SudokuGrid::place_num`], following these rules: the number has to be valid (see
+    /// [`pos_is_valid`](Self::pos_is_valid)), and it's taken out of the notes of its
+    /// [`peers`](Self::peers).
+    pub fn place_num<G: SudokuGrid>(
+        &self,
+        grid: &mut G,
+        pos: Pos,
+        num: G::Num,
+    ) -> Option<Vec<Move<G::Num>>> {
+        if self.is_classic() {
+            return grid.place_num(pos, num);
+        }
+        let n = num.num_or_zero();
+        if !self.pos_is_valid(grid, pos, n) {
+            return None;
+        }
+        let mut moves = vec![Move::new(grid[pos], num, pos)];
+        grid[pos] = num;
+        if n != 0 {
+            for peer in self.peers::<G>(pos) {
+                let old = grid[peer];
+                if old.has_note(n) == Some(true) {
+                    grid[peer] = old.with_toggle_note(n);
+                    moves.push(Move::new(old, grid[peer], peer));
+                }
+            }
+        }
+        Some(moves)
+    }
+
+    /// Like [`SudokuGrid::fill_notes`], noting the numbers each empty square can hold under these
+    /// rules.
+    pub fn fill_notes<G: SudokuGrid>(&self, grid: &mut G) -> Vec<Move<G::Num>> {
+        if self.is_classic() {
+            return grid.fill_notes();
+        }
+        let mut moves = Vec::new();
+        for pos in positions::<G>() {
+            let old = grid[pos];
+            if old.num_or_zero() != 0 {
+                continue;
+            }
+            let mut new = G::Num::new(0);
+            for n in 1..=G::SIZE as u8 {
+                if self.pos_is_valid(grid, pos, n) {
+                    new = if new.is_note() {
+                        new.with_note(n)
+                    } else {
+                        G::Num::new_note(n)
+                    };
+                }
+            }
+            if new != old {
+                grid[pos] = new;
+                moves.push(Move::new(old, new, pos));
+            }
+        }
+        moves
+    }
+
+    /// Like [`SudokuGrid::count_solutions`], following these rules.
+    pub fn count_solutions<G: SudokuGrid>(&self, grid: &G, limit: usize) -> usize {
+        self.solver(grid)
+            .map(|mut solver| solver.count(limit))
+            .unwrap_or(0)
+    }
+
+    pub fn has_unique_solution<G: SudokuGrid>(&self, grid: &G) -> bool {
+        self.count_solutions(grid, 2) == 1
+    }
+
+    // Same as `has_unique_solution`, but gives up and returns false once the search has placed
+    // `max_guesses` numbers.
+    pub(crate) fn has_unique_solution_bounded<G: SudokuGrid>(
+        &self,
+        grid: &G,
+        max_guesses: usize,
+    ) -> bool {
+        self.solver(grid)
+            .is_some_and(|mut solver| solver.count_bounded(2, max_guesses) == Some(1))
+    }
+
+    /// Like [`SudokuGrid::unique_solution`], following these rules.
+    pub fn unique_solution<G: SudokuGrid>(&self, grid: &G) -> Option<G> {
+        let mut solver = self.solver(grid)?;
+        if !solver.next_solution() {
+            return None;
+        }
+        let mut solution = G::empty();
+        solution.fill_from(solver.cells());
+        (!solver.next_solution()).then_some(solution)
+    }
+
+    /// Like [`SudokuGrid::solve`], following these rules.
+    pub fn solve<G: SudokuGrid>(&self, grid: &mut G) -> bool {
+        let Some(mut solver) = self.solver(grid) else {
+            return false;
+        };
+        if !solver.next_solution() {
+            return false;
+        }
+        grid.fill_from(solver.cells());
+        true
+    }
+
+    /// A random filled board following these rules, or None if they can't be followed. The cages
+    /// are left out, since they're made to fit a filled board.
+    pub fn random_solution<G: SudokuGrid>(&self, rng: &mut impl Rng) -> Option<G> {
+        // Some regions make the search run into long dead ends, so it starts over whenever it
+        // takes a while. Fresh starts usually do better than waiting longer, so each is only
+        // allowed a little more time than the last.
+        let mut max_guesses = G::CELLS * 4;
+        loop {
+            match self.random_solution_bounded(rng, max_guesses) {
+                Some(solution) => return solution,
+                None => max_guesses += max_guesses / 32,
+            }
+        }
+    }
+
+    // Same as `random_solution`, but gives up and returns None once the search has placed
+    // `max_guesses` numbers.
+    pub(crate) fn random_solution_bounded<G: SudokuGrid>(
+        &self,
+        rng: &mut impl Rng,
+        max_guesses: usize,
+    ) -> Option<Option<G>> {
+        let uncaged = Self {
+            cages: Vec::new(),
+            ..self.clone()
+        };
+        let Some(mut solver) = uncaged.solver(&G::empty()) else {
+            return Some(None);
+        };
+        let found = solver.next_random_solution_bounded(rng, max_guesses)?;
+        Some(found.then(|| {
+            let mut grid = G::empty();
+            grid.fill_from(solver.cells());
+            grid
+        }))
+    }
+
+    /// A random layout for Jigsaw Sudoku (see [`boxes`](Self::boxes)), made by trading squares
+    /// between the usual boxes while keeping each box in one piece. Some layouts have no
+    /// solutions, which [`random_solution`](Self::random_solution) can tell.
+    pub fn jigsaw_boxes<G: SudokuGrid>(rng: &mut impl Rng) -> Vec<u8> {
+        let mut boxes = positions::<G>()
+            .map(|(x, y)| (y / G::RANK * G::RANK + x / G::RANK) as u8)
+            .collect::<Vec<_>>();
+        let box_at = |boxes: &[u8], (x, y): Pos| boxes[y * G::SIZE + x];
+        let mut traded = 0;
+        while traded < G::CELLS {
+            // A square moves to the box next to it, which gives up one of its squares touching
+            // the first box in return
+            let a = (
+                rng.gen_range(0..G::SIZE as u32) as usize,
+                rng.gen_range(0..G::SIZE as u32) as usize,
+            );
+            let from = box_at(&boxes, a);
+            let next = neighbours::<G>(&[a])
+                .filter(|&p| box_at(&boxes, p) != from)
+                .collect::<Vec<_>>();
+            if next.is_empty() {
+                continue;
+            }
+            let to = box_at(&boxes, next[rng.gen_range(0..next.len() as u32) as usize]);
+            let back = positions::<G>()
+                .filter(|&p| {
+                    box_at(&boxes, p) == to
+                        && neighbours::<G>(&[p]).any(|q| q != a && box_at(&boxes, q) == from)
+                })
+                .collect::<Vec<_>>();
+            if back.is_empty() {
+                continue;
+            }
+            let b = back[rng.gen_range(0..back.len() as u32) as usize];
+            boxes[a.1 * G::SIZE + a.0] = to;
+            boxes[b.1 * G::SIZE + b.0] = from;
+            if is_connected::<G>(&boxes, from) && is_connected::<G>(&boxes, to) {
+                traded += 1;
+            } else {
+                boxes[a.1 * G::SIZE + a.0] = from;
+                boxes[b.1 * G::SIZE + b.0] = to;
+            }
+        }
+        boxes
+    }
+
+    /// Cuts a filled board into random cages of connected squares, mostly 2 to `rank + 2` squares
+    /// big, with the sums of its numbers.
+    pub fn killer_cages<G: SudokuGrid>(solution: &G, rng: &mut impl Rng) -> Vec<Cage> {
+        let size = G::SIZE;
+        let mut cage_of = vec![usize::MAX; G::CELLS];
+        let mut cells = Vec::<Vec<Pos>>::new();
+        let mut starts = positions::<G>().collect::<Vec<_>>();
+        starts.shuffle(rng);
+        for start in starts {
+            if cage_of[start.1 * size + start.0] != usize::MAX {
+                continue;
+            }
+            let target = rng.gen_range(2..=G::RANK as u32 + 2) as usize;
+            let mut cage = vec![start];
+            cage_of[start.1 * size + start.0] = cells.len();
+            while cage.len() < target {
+                let options = neighbours::<G>(&cage)
+                    .filter(|&(x, y)| cage_of[y * size + x] == usize::MAX)
+                    .filter(|&p| cage.iter().all(|&c| solution[c] != solution[p]))
+                    .collect::<Vec<_>>();
+                if options.is_empty() {
+                    break;
+                }
+                let (x, y) = options[rng.gen_range(0..options.len() as u32) as usize];
+                cage_of[y * size + x] = cells.len();
+                cage.push((x, y));
+            }
+            cells.push(cage);
+        }
+
+        // Squares left on their own would just be givens, so they join a cage next to them if
+        // one can take them.
+        for i in 0..cells.len() {
+            if cells[i].len() != 1 {
+                continue;
+            }
+            let pos = cells[i][0];
+            let join = neighbours::<G>(&cells[i])
+                .map(|(x, y)| cage_of[y * size + x])
+                .find(|&j| {
+                    j != i
+                        && cells[j].len() < size
+                        && cells[j].iter().all(|&c| solution[c] != solution[pos])
+                });
+            if let Some(j) = join {
+                cells[i].clear();
+                cells[j].push(pos);
+                cage_of[pos.1 * size + pos.0] = j;
+            }
+        }
+
+        cells
+            .into_iter()
+            .filter(|cells| !cells.is_empty())
+            .map(|cells| {
+                let sum = cells
+                    .iter()
+                    .map(|&p| solution[p].num_or_zero() as u32)
+                    .sum();
+                Cage::new(sum, cells)
+            })
+            .collect()
+    }
+
+    fn solver<G: SudokuGrid>(&self, grid: &G) -> Option<Solver> {
+        let idxs = |cells: &[Pos]| cells.iter().map(|&(x, y)| y * G::SIZE + x).collect();
+        let regions = self
+            .all_regions::<G>()
+            .iter()
+            .map(|region| idxs(region))
+            .collect::<Vec<_>>();
+        let cages = self
+            .cages
+            .iter()
+            .map(|cage| (idxs(&cage.cells), cage.sum))
+            .collect::<Vec<_>>();
+        // The cages are followed by the solver itself, and the other constraints by calling back
+        let thermos = self
+            .thermos
+            .iter()
+            .map(|c| Rc::new(c.clone()) as Rc<dyn Constraint>);
+        let arrows = self
+            .arrows
+            .iter()
+            .map(|c| Rc::new(c.clone()) as Rc<dyn Constraint>);
+        let dots = self
+            .dots
+            .iter()
+            .map(|c| Rc::new(c.clone()) as Rc<dyn Constraint>);
+        let constraints = thermos
+            .chain(arrows)
+            .chain(dots)
+            .map(|c| (idxs(c.cells()), c))
+            .collect::<Vec<_>>();
+        Solver::with_rules(G::RANK, &grid.nums(), &regions, &cages, &constraints)
+    }
+}
+
+/// The kinds of puzzles that can be generated, each with its own [`Rules`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum Variant {
+    #[default]
+    Classic,
+    /// Cages of squares with the sum of their numbers, see [`Rules::killer_cages`].
+    Killer,
+    /// See [`Rules::diagonal`].
+    Diagonal,
+    /// See [`Rules::windoku`].
+    Windoku,
+    /// Irregular boxes, see [`Rules::jigsaw_boxes`].
+    Jigsaw,
+    /// See [`Thermo::for_solution`].
+    Thermo,
+    /// See [`Arrow::for_solution`].
+    Arrow,
+    /// See [`Kropki::for_solution`].
+    Kropki,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 8] = [
+        Variant::Classic,
+        Variant::Killer,
+        Variant::Diagonal,
+        Variant::Windoku,
+        Variant::Jigsaw,
+        Variant::Thermo,
+        Variant::Arrow,
+        Variant::Kropki,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Classic => "Classic",
+            Variant::Killer => "Killer",
+            Variant::Diagonal => "Sudoku X",
+            Variant::Windoku => "Windoku",
+            Variant::Jigsaw => "Jigsaw",
+            Variant::Thermo => "Thermo",
+            Variant::Arrow => "Arrow",
+            Variant::Kropki => "Kropki",
+        }
+    }
+
+    /// The rank of the biggest boards worth generating for the variant, since bigger ones take
+    /// too long.
+    pub fn max_rank(self) -> usize {
+        match self {
+            Variant::Classic | Variant::Diagonal => 5,
+            _ => 3,
+        }
+    }
+
+    /// The rules for boards of the variant. Only Sudoku X and Windoku have any here, since the
+    /// others get theirs made randomly for each board (like Killer cages and Jigsaw boxes).
+    pub fn rules<G: SudokuGrid>(self) -> Rules {
+        match self {
+            Variant::Diagonal => Rules::diagonal::<G>(),
+            Variant::Windoku => Rules::windoku::<G>(),
+            _ => Rules::default(),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// Returns true if the squares of the box can all be reached from each other without leaving it.
+fn is_connected<G: SudokuGrid>(boxes: &[u8], b: u8) -> bool {
+    let cells = positions::<G>()
+        .filter(|&(x, y)| boxes[y * G::SIZE + x] == b)
+        .collect::<Vec<_>>();
+    let Some(&start) = cells.first() else {
+        return true;
+    };
+    let mut reached = vec![start];
+    while let Some(next) = {
+        let next = neighbours::<G>(&reached).find(|p| cells.contains(p));
+        next
+    } {
+        reached.push(next);
+    }
+    reached.len() == cells.len()
+}
+
+// The squares next to any of the cells (not diagonally) that aren't one of them, each once.
+fn neighbours<G: SudokuGrid>(cells: &[Pos]) -> impl Iterator<Item = Pos> + '_ {
+    let mut seen = Vec::new();
+    cells.iter().flat_map(move |&(x, y)| {
+        let mut next = Vec::new();
+        for (dx, dy) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+            if nx < 0 || ny < 0 || nx as usize >= G::SIZE || ny as usize >= G::SIZE {
+                continue;
+            }
+            let pos = (nx as usize, ny as usize);
+            if !cells.contains(&pos) && !seen.contains(&pos) {
+                seen.push(pos);
+                next.push(pos);
+            }
+        }
+        next
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{seeded_rng, Difficulty, Dot, Grid2x2, Grid3x3, Grid4x4, Num2x2, Symmetry};
+
+    #[test]
+    fn cages() {
+        let solution = Grid3x3::generate_with_rng(&mut seeded_rng(1));
+        let cages = Rules::killer_cages(&solution, &mut seeded_rng(2));
+        let rules = Rules::killer(cages);
+        assert!(rules.check::<Grid3x3>().is_ok());
+        assert_eq!(rules.cages.iter().map(|c| c.cells.len()).sum::<usize>(), 81);
+        assert!(rules.is_valid(&solution).is_none());
+
+        // Every cage adds up, and is connected
+        for cage in &rules.cages {
+            let mut reached = vec![cage.cells[0]];
+            while let Some(p) = {
+                let next = neighbours::<Grid3x3>(&reached).find(|p| cage.cells.contains(p));
+                next
+            } {
+                reached.push(p);
+            }
+            assert_eq!(reached.len(), cage.cells.len());
+        }
+
+        let mut swapped = solution.clone();
+        swapped.swap_nums(1, 2);
+        assert!(swapped.is_valid().is_none());
+        assert!(rules.is_valid(&swapped).is_some());
+
+        let overlap = Rules::killer(vec![
+            Cage::new(3, vec![(0, 0), (1, 0)]),
+            Cage::new(3, vec![(1, 0), (2, 0)]),
+        ]);
+        assert_eq!(overlap.check::<Grid3x3>(), Err(SudkuError::InvalidCage(1)));
+    }
+
+    #[test]
+    fn killer_rules() {
+        // 1 2 | 3 4
+        // 3 4 | 1 2
+        // ----+----
+        // 2 1 | 4 3
+        // 4 3 | 2 1
+        let rules = Rules::killer(vec![
+            Cage::new(3, vec![(0, 0), (1, 0)]),
+            Cage::new(7, vec![(2, 0), (3, 0)]),
+            Cage::new(3, vec![(2, 1), (3, 1)]),
+            Cage::new(5, vec![(0, 1), (0, 2)]),
+            Cage::new(5, vec![(1, 1), (1, 2)]),
+            Cage::new(7, vec![(2, 2), (3, 2)]),
+            Cage::new(7, vec![(0, 3), (1, 3)]),
+            Cage::new(3, vec![(2, 3), (3, 3)]),
+        ]);
+        assert_eq!(rules.count_solutions(&Grid2x2::empty(), 10), 8);
+        // The cages of 1 and 2 (and 3 and 4) can be swapped, so a few numbers are needed
+        let mut grid = Grid2x2::from_line("1.3...1.........").unwrap();
+        let solution = rules.unique_solution(&grid).expect("no unique solution");
+        assert_eq!(solution.to_line().trim_end(), "1234341221434321");
+        assert!(rules.solve(&mut grid) && grid == solution);
+
+        let mut grid = Grid2x2::empty();
+        assert!(rules.pos_is_valid(&grid, (0, 0), 2));
+        assert!(!rules.pos_is_valid(&grid, (0, 0), 3));
+        grid[(0, 0)] = GridNum::new(2);
+        assert!(!rules.pos_is_valid(&grid, (1, 0), 2));
+        assert!(!rules.pos_is_valid(&grid, (1, 0), 4));
+        assert!(rules.pos_is_valid(&grid, (1, 0), 1));
+    }
+
+    #[test]
+    fn generate_killer() {
+        let (grid, solution, rules) = Grid2x2::generate_killer_with_rng(&mut seeded_rng(5));
+        assert!(rules.check::<Grid2x2>().is_ok());
+        assert!(rules.is_valid(&solution).is_none());
+        assert!(rules.unique_solution(&grid).unwrap() == solution);
+        // Removing any of the numbers left would allow another solution
+        for pos in positions::<Grid2x2>().filter(|&p| grid[p].num_or_zero() != 0) {
+            let mut fewer = grid.clone();
+            fewer[pos] = GridNum::new(0);
+            assert!(!rules.has_unique_solution(&fewer));
+        }
+    }
+
+    #[test]
+    fn regions() {
+        let diagonal = Rules::diagonal::<Grid3x3>();
+        assert_eq!(diagonal.regions[1][..3], [(8, 0), (7, 1), (6, 2)]);
+        let windoku = Rules::windoku::<Grid3x3>();
+        assert_eq!(windoku.regions.len(), 4);
+        assert_eq!(windoku.regions[0][..4], [(1, 1), (2, 1), (3, 1), (1, 2)]);
+        assert_eq!(windoku.regions[3][8], (7, 7));
+        assert!(windoku.in_region((5, 5)) && !windoku.in_region((4, 4)));
+        assert_eq!(
+            Rules::windoku::<Grid2x2>().regions,
+            [[(1, 1), (2, 1), (1, 2), (2, 2)]]
+        );
+        assert!(diagonal.check::<Grid3x3>().is_ok() && windoku.check::<Grid3x3>().is_ok());
+        assert!(Rules::windoku::<Grid4x4>().check::<Grid4x4>().is_ok());
+        assert_eq!(
+            windoku.check::<Grid2x2>(),
+            Err(SudkuError::InvalidRegion(0))
+        );
+
+        let mut grid = Grid3x3::empty();
+        grid[(0, 0)] = GridNum::new(5);
+        assert!(grid.pos_is_valid((4, 4), 5));
+        assert!(!diagonal.pos_is_valid(&grid, (4, 4), 5));
+        assert!(windoku.pos_is_valid(&grid, (4, 4), 5));
+        assert!(!windoku.pos_is_valid(&grid, (0, 4), 5));
+    }
+
+    #[test]
+    fn generate_variants() {
+        for variant in [Variant::Diagonal, Variant::Windoku] {
+            let (grid, solution, rules) = Grid3x3::generate_variant_with_rng(
+                variant,
+                Difficulty::Medium,
+                Symmetry::Rotational180,
+                &mut seeded_rng(6),
+            );
+            assert!(rules == variant.rules::<Grid3x3>());
+            assert!(rules.is_valid(&solution).is_none());
+            assert!(Symmetry::Rotational180.matches(&grid.nums(), 9));
+            assert!(rules.unique_solution(&grid).unwrap() == solution);
+        }
+        // Swapping two numbers keeps the classic rules but breaks the diagonals
+        let rules = Rules::diagonal::<Grid2x2>();
+        let solution = rules
+            .random_solution::<Grid2x2>(&mut seeded_rng(7))
+            .unwrap();
+        assert!(rules.is_valid(&solution).is_none());
+        let mut swapped = solution.clone();
+        swapped.swap_nums(1, 2);
+        assert!(swapped.is_valid().is_none() && rules.is_valid(&swapped).is_none());
+        swapped.swap_rows(0, 1);
+        assert!(swapped.is_valid().is_none() && rules.is_valid(&swapped).is_some());
+    }
+
+    #[test]
+    fn jigsaw() {
+        // 0 0 | 0 1
+        // 0 2 | 1 1
+        // ----+----
+        // 2 2 | 3 1
+        // 2 3 | 3 3
+        let boxes = vec![0, 0, 0, 1, 0, 2, 1, 1, 2, 2, 3, 1, 2, 3, 3, 3];
+        let rules = Rules::jigsaw(boxes.clone());
+        assert!(rules.check::<Grid2x2>().is_ok());
+        assert_eq!(rules.box_of::<Grid2x2>((1, 1)), 2);
+        assert_eq!(
+            rules.box_cells::<Grid2x2>((0, 2)),
+            [(1, 1), (0, 2), (1, 2), (0, 3)]
+        );
+        assert_eq!(rules.peers::<Grid2x2>((0, 0)).len(), 6);
+
+        let mut grid = Grid2x2::empty();
+        grid[(0, 2)] = GridNum::new(1);
+        assert!(grid.pos_is_valid((1, 1), 1));
+        assert!(!rules.pos_is_valid(&grid, (1, 1), 1));
+        assert!(rules.pos_is_valid(&grid, (1, 0), 1));
+        // (0, 3) only shares the box with (1, 1)
+        grid[(0, 3)] = Num2x2::new_note(2).with_note(4);
+        assert!(rules
+            .place_num(&mut grid, (0, 1), GridNum::new(1))
+            .is_none());
+        let moves = rules.place_num(&mut grid, (1, 1), GridNum::new(2)).unwrap();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(grid[(0, 3)].has_note(2), Some(false));
+        rules.fill_notes(&mut grid);
+        assert_eq!(grid[(0, 3)].has_note(2), Some(false));
+        assert_eq!(grid[(0, 3)].has_note(3), Some(true));
+
+        let mut split = boxes.clone();
+        split.swap(0, 15);
+        assert_eq!(
+            Rules::jigsaw(split).check::<Grid2x2>(),
+            Err(SudkuError::InvalidBoxes)
+        );
+        assert_eq!(
+            Rules::jigsaw(boxes[1..].to_vec()).check::<Grid2x2>(),
+            Err(SudkuError::InvalidBoxes)
+        );
+        let layout = Rules::jigsaw(Rules::jigsaw_boxes::<Grid3x3>(&mut seeded_rng(8)));
+        assert!(layout.check::<Grid3x3>().is_ok());
+        assert!(positions::<Grid3x3>()
+            .any(|p| layout.box_of::<Grid3x3>(p) != Rules::default().box_of::<Grid3x3>(p)));
+    }
+
+    #[test]
+    fn generate_jigsaw() {
+        let (grid, solution, rules) = Grid3x3::generate_variant_with_rng(
+            Variant::Jigsaw,
+            Difficulty::Easy,
+            Symmetry::None,
+            &mut seeded_rng(9),
+        );
+        assert!(rules.boxes.is_some());
+        assert!(rules.check::<Grid3x3>().is_ok());
+        assert!(rules.is_valid(&solution).is_none());
+        assert!(rules.unique_solution(&grid).unwrap() == solution);
+    }
+
+    #[test]
+    fn constraints() {
+        // A thermometer along the top row only fits 1 2 3 4
+        let rules = Rules {
+            thermos: vec![Thermo::new(vec![(0, 0), (1, 0), (2, 0), (3, 0)])],
+            arrows: vec![Arrow::new(vec![(0, 2), (1, 3), (0, 3)])],
+            dots: vec![Kropki::new(Dot::Black, (3, 1), (3, 2))],
+            ..Rules::default()
+        };
+        assert!(rules.check::<Grid2x2>().is_ok() && !rules.is_classic());
+        assert_eq!(rules.constraints().count(), 3);
+        let mut grid = Grid2x2::empty();
+        assert!(rules.pos_is_valid(&grid, (1, 0), 2));
+        assert!(!rules.pos_is_valid(&grid, (1, 0), 3));
+        assert!(!rules.pos_is_valid(&grid, (0, 2), 1));
+        grid[(3, 1)] = GridNum::new(3);
+        assert!(!rules.pos_is_valid(&grid, (3, 2), 4));
+
+        assert_eq!(rules.count_solutions(&Grid2x2::empty(), 100), 2);
+        let solution = rules
+            .random_solution::<Grid2x2>(&mut seeded_rng(10))
+            .unwrap();
+        assert_eq!(solution.to_line()[..4], *"1234");
+        assert!(rules.is_valid(&solution).is_none());
+        let sum = solution[(1, 3)].num_or_zero() + solution[(0, 3)].num_or_zero();
+        assert_eq!(solution[(0, 2)].num(), Some(sum));
+
+        let mut bad = rules.clone();
+        bad.dots[0].cells[1] = (2, 2);
+        assert_eq!(
+            bad.check::<Grid2x2>(),
+            Err(SudkuError::InvalidConstraint(2))
+        );
+        bad.arrows[0].cells.truncate(1);
+        assert_eq!(
+            bad.check::<Grid2x2>(),
+            Err(SudkuError::InvalidConstraint(1))
+        );
+        bad.thermos[0].cells.push((3, 1));
+        assert_eq!(
+            bad.check::<Grid2x2>(),
+            Err(SudkuError::InvalidConstraint(0))
+        );
+
+        for variant in [Variant::Thermo, Variant::Arrow, Variant::Kropki] {
+            let (grid, solution, rules) = Grid3x3::generate_variant_with_rng(
+                variant,
+                Difficulty::Easy,
+                Symmetry::None,
+                &mut seeded_rng(11),
+            );
+            assert!(rules.constraints().next().is_some() && rules.check::<Grid3x3>().is_ok());
+            assert!(rules.is_valid(&solution).is_none());
+            assert!(rules.unique_solution(&grid).unwrap() == solution);
+        }
+    }
+}
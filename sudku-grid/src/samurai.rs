@@ -0,0 +1,474 @@
+use crate::{
+    candidates::peers,
+    grid::positions,
+    save::{parse_square, square_str},
+    solver::{classic_regions, Solver},
+    Grid3x3, GridNum, Hint, Move, Num3x3, Pos, Step, SudkuError, SudkuResult, SudokuGrid,
+};
+use rand::{seq::SliceRandom, Rng};
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+// How many numbers a search may place while filling or checking a board, like the limit for the
+// variants. Boards that go over it are started again or treated as ambiguous.
+const MAX_GUESSES: usize = 4000;
+
+/// Samurai Sudoku: five 9x9 boards, with the middle one sharing a corner box with each of the
+/// others. Together they cover 369 squares of a 21x21 board, and the squares between the corner
+/// boards aren't used. Shared squares are kept once, so a number placed in one is on both of its
+/// boards, and each board follows the rules of a [`Grid3x3`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Samurai {
+    // Row-major over the whole 21x21 board, with the unused squares left empty
+    cells: Vec<Num3x3>,
+}
+
+impl Samurai {
+    /// The width and height of the whole board.
+    pub const SIZE: usize = 21;
+    /// How many squares are on the five boards.
+    pub const CELLS: usize = 369;
+    /// The top left squares of the five boards: top left, top right, middle, bottom left, and
+    /// bottom right.
+    pub const OFFSETS: [Pos; 5] = [(0, 0), (12, 0), (6, 6), (0, 12), (12, 12)];
+    /// Names for the five boards, in the order of [`OFFSETS`](Self::OFFSETS).
+    pub const NAMES: [&'static str; 5] = [
+        "top left",
+        "top right",
+        "middle",
+        "bottom left",
+        "bottom right",
+    ];
+
+    pub fn empty() -> Self {
+        Self {
+            cells: vec![Num3x3::default(); Self::SIZE * Self::SIZE],
+        }
+    }
+
+    /// Returns true if the square is on one of the five boards.
+    pub fn contains(pos: Pos) -> bool {
+        Self::grids_of(pos).next().is_some()
+    }
+
+    /// The boards the square is on, by their index in [`OFFSETS`](Self::OFFSETS). Squares in the
+    /// shared boxes are on two.
+    pub fn grids_of((x, y): Pos) -> impl Iterator<Item = usize> {
+        (0..5).filter(move |&i| {
+            let (ox, oy) = Self::OFFSETS[i];
+            (ox..ox + 9).contains(&x) && (oy..oy + 9).contains(&y)
+        })
+    }
+
+    /// Every square on the five boards, row by row.
+    pub fn positions() -> impl Iterator<Item = Pos> {
+        (0..Self::SIZE)
+            .flat_map(|y| (0..Self::SIZE).map(move |x| (x, y)))
+            .filter(|&pos| Self::contains(pos))
+    }
+
+    /// The square on the whole board for a square of one of the five.
+    pub fn board_pos(grid: usize, (x, y): Pos) -> Pos {
+        let (ox, oy) = Self::OFFSETS[grid];
+        (ox + x, oy + y)
+    }
+
+    /// One of the five boards, by its index in [`OFFSETS`](Self::OFFSETS).
+    pub fn grid(&self, i: usize) -> Grid3x3 {
+        let mut grid = Grid3x3::empty();
+        for pos in positions::<Grid3x3>() {
+            grid[pos] = self[Self::board_pos(i, pos)];
+        }
+        grid
+    }
+
+    /// Replaces one of the five boards, which also changes the boxes it shares.
+    pub fn set_grid(&mut self, i: usize, grid: &Grid3x3) {
+        for pos in positions::<Grid3x3>() {
+            self[Self::board_pos(i, pos)] = grid[pos];
+        }
+    }
+
+    /// Like [`SudokuGrid::pos_is_valid`], on every board the square is on. Nothing can go in the
+    /// squares between the boards.
+    pub fn pos_is_valid(&self, pos: Pos, n: u8) -> bool {
+        Self::contains(pos)
+            && Self::grids_of(pos).all(|i| {
+                let (ox, oy) = Self::OFFSETS[i];
+                self.grid(i).pos_is_valid((pos.0 - ox, pos.1 - oy), n)
+            })
+    }
+
+    /// Like [`SudokuGrid::is_valid`], returning the first square that's empty or breaks the rules
+    /// of one of the boards.
+    pub fn is_valid(&self) -> Option<Pos> {
+        (0..5).find_map(|i| {
+            let pos = self.grid(i).is_valid()?;
+            Some(Self::board_pos(i, pos))
+        })
+    }
+
+    /// Like [`SudokuGrid::place_num`], taking the number out of the notes of the squares that
+    /// share a row, column, or box with the square on any of its boards. Returns None for the
+    /// squares between the boards.
+    pub fn place_num(&mut self, pos: Pos, num: Num3x3) -> Option<Vec<Move<Num3x3>>> {
+        let n = num.num_or_zero();
+        if !self.pos_is_valid(pos, n) {
+            return None;
+        }
+        let mut moves = vec![Move::new(self[pos], num, pos)];
+        self[pos] = num;
+        if n != 0 {
+            for i in Self::grids_of(pos).collect::<Vec<_>>() {
+                let (ox, oy) = Self::OFFSETS[i];
+                for peer in peers(3, (pos.0 - ox, pos.1 - oy)) {
+                    let peer = Self::board_pos(i, peer);
+                    let old = self[peer];
+                    if old.has_note(n) == Some(true) {
+                        self[peer] = old.with_toggle_note(n);
+                        moves.push(Move::new(old, self[peer], peer));
+                    }
+                }
+            }
+        }
+        Some(moves)
+    }
+
+    /// Like [`SudokuGrid::fill_notes`], noting the numbers each empty square can hold on all of its
+    /// boards.
+    pub fn fill_notes(&mut self) -> Vec<Move<Num3x3>> {
+        let grids = (0..5).map(|i| self.grid(i)).collect::<Vec<_>>();
+        let mut moves = Vec::new();
+        for pos in Self::positions() {
+            let old = self[pos];
+            if old.num_or_zero() != 0 {
+                continue;
+            }
+            let mut new = Num3x3::new(0);
+            for n in 1..=9 {
+                let fits = Self::grids_of(pos).all(|i| {
+                    let (ox, oy) = Self::OFFSETS[i];
+                    grids[i].pos_is_valid((pos.0 - ox, pos.1 - oy), n)
+                });
+                if fits {
+                    new = if new.is_note() {
+                        new.with_note(n)
+                    } else {
+                        Num3x3::new_note(n)
+                    };
+                }
+            }
+            if new != old {
+                self[pos] = new;
+                moves.push(Move::new(old, new, pos));
+            }
+        }
+        moves
+    }
+
+    /// Finds the next step a person could take on one of the boards, like [`SudokuGrid::hint`].
+    /// The step's squares are on the whole board, and the reason names the board it's on.
+    pub fn hint(&self) -> Option<Hint> {
+        (0..5).find_map(|i| {
+            let hint = self.grid(i).hint()?;
+            let to_board = |pos| Self::board_pos(i, pos);
+            let step = Step {
+                technique: hint.step.technique,
+                cells: hint.step.cells.into_iter().map(to_board).collect(),
+                placements: hint
+                    .step
+                    .placements
+                    .into_iter()
+                    .map(|(p, n)| (to_board(p), n))
+                    .collect(),
+                eliminations: hint
+                    .step
+                    .eliminations
+                    .into_iter()
+                    .map(|(p, n)| (to_board(p), n))
+                    .collect(),
+            };
+            let reason = format!("On the {} board: {}", Self::NAMES[i], hint.reason);
+            Some(Hint { step, reason })
+        })
+    }
+
+    /// Like [`SudokuGrid::apply_step`], for a step from [`hint`](Self::hint).
+    pub fn apply_step(&mut self, step: &Step) -> Vec<Move<Num3x3>> {
+        let mut moves = Vec::new();
+        for &(pos, n) in &step.placements {
+            if let Some(mvs) = self.place_num(pos, Num3x3::new(n)) {
+                moves.extend(mvs);
+            }
+        }
+        for &(pos, n) in &step.eliminations {
+            let old = self[pos];
+            if old.has_note(n) == Some(true) {
+                self[pos].set_toggle_note(n);
+                moves.push(Move::new(old, self[pos], pos));
+            }
+        }
+        moves
+    }
+
+    /// Sets every number on the board as given.
+    pub fn set_given(&mut self) {
+        for num in &mut self.cells {
+            if num.num_or_zero() != 0 {
+                num.set_given();
+            }
+        }
+    }
+
+    /// The board with only its given numbers.
+    pub fn givens(&self) -> Self {
+        let mut givens = Self::empty();
+        for pos in Self::positions().filter(|&pos| self[pos].is_given()) {
+            givens[pos] = self[pos];
+        }
+        givens
+    }
+
+    /// The squares holding a number other than the one in the solution.
+    pub fn mistakes(&self, solution: &Self) -> Vec<Pos> {
+        Self::positions()
+            .filter(|&pos| {
+                let n = self[pos].num_or_zero();
+                n != 0 && n != solution[pos].num_or_zero()
+            })
+            .collect()
+    }
+
+    /// Counts the solutions of the board, stopping once `limit` have been found.
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.solver().map_or(0, |mut solver| solver.count(limit))
+    }
+
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    /// Solves the board, returning false (and leaving it as it was) if it has no solution. The
+    /// numbers already on the board are kept as they are.
+    pub fn solve(&mut self) -> bool {
+        let Some(mut solver) = self.solver() else {
+            return false;
+        };
+        if !solver.next_solution() {
+            return false;
+        }
+        self.fill_from(solver.cells());
+        true
+    }
+
+    /// Generates a puzzle along with its solution. As many numbers are removed, in pairs across
+    /// the middle of the board, as can be while the puzzle can still quickly be shown to have one
+    /// solution.
+    pub fn generate() -> (Self, Self) {
+        Self::generate_with_rng(&mut rand::thread_rng())
+    }
+
+    pub fn generate_with_rng(rng: &mut impl Rng) -> (Self, Self) {
+        let solution = loop {
+            let mut solver = Self::empty().solver().expect("empty board can't be solved");
+            if let Some(true) = solver.next_random_solution_bounded(rng, MAX_GUESSES) {
+                let mut solution = Self::empty();
+                solution.fill_from(solver.cells());
+                break solution;
+            }
+        };
+        let mut grid = solution.clone();
+        let mut pairs = Self::positions()
+            .filter(|&(x, y)| (y, x) <= (Self::SIZE - 1 - y, Self::SIZE - 1 - x))
+            .collect::<Vec<_>>();
+        pairs.shuffle(rng);
+        for (x, y) in pairs {
+            let pair = [(x, y), (Self::SIZE - 1 - x, Self::SIZE - 1 - y)];
+            for pos in pair {
+                grid[pos] = Num3x3::new(0);
+            }
+            let unique = grid
+                .solver()
+                .and_then(|mut solver| solver.count_bounded(2, MAX_GUESSES))
+                == Some(1);
+            if !unique {
+                for pos in pair {
+                    grid[pos] = solution[pos];
+                }
+            }
+        }
+        (grid, solution)
+    }
+
+    // Solves over the squares of the five boards only, in the order of `positions`, with the rows,
+    // columns, and boxes of every board as regions.
+    fn solver(&self) -> Option<Solver> {
+        let mut index = vec![usize::MAX; Self::SIZE * Self::SIZE];
+        for (i, (x, y)) in Self::positions().enumerate() {
+            index[y * Self::SIZE + x] = i;
+        }
+        let regions = (0..5)
+            .flat_map(|i| {
+                classic_regions(3).into_iter().map(move |region| {
+                    region
+                        .into_iter()
+                        .map(|idx| Self::board_pos(i, (idx % 9, idx / 9)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .map(|region| {
+                region
+                    .into_iter()
+                    .map(|(x, y)| index[y * Self::SIZE + x])
+                    .collect()
+            })
+            .collect::<Vec<_>>();
+        let nums = Self::positions()
+            .map(|pos| self[pos].num_or_zero())
+            .collect::<Vec<_>>();
+        Solver::with_rules(3, &nums, &regions, &[], &[])
+    }
+
+    // Fills in the squares from numbers in the order of `positions`, keeping the givens.
+    fn fill_from(&mut self, nums: &[u8]) {
+        for (pos, &n) in Self::positions().zip(nums) {
+            if self[pos].num_or_zero() != n {
+                self[pos] = Num3x3::new(n);
+            }
+        }
+    }
+
+    /// Reads a board written with [`Display`](fmt::Display): 21 lines of 21 squares each,
+    /// separated by spaces. A square is written `.` when empty, as its number, as `!` and its
+    /// number when given, or as its notes in brackets, and the unused squares as `-`.
+    pub fn parse(s: &str) -> SudkuResult<Self> {
+        let invalid = |line, reason| SudkuError::InvalidSave { line, reason };
+        let mut board = Self::empty();
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty());
+        let mut last = 0;
+        for y in 0..Self::SIZE {
+            let (i, line) = lines.next().ok_or(invalid(last + 1, "missing row"))?;
+            let mut squares = line.split_whitespace();
+            for x in 0..Self::SIZE {
+                let square = squares.next().ok_or(invalid(i, "missing square"))?;
+                if !Self::contains((x, y)) {
+                    if square != "-" {
+                        return Err(invalid(i, "square between the boards"));
+                    }
+                    continue;
+                }
+                board[(x, y)] = parse_square::<Grid3x3>(square).ok_or(invalid(i, "bad square"))?;
+            }
+            if squares.next().is_some() {
+                return Err(invalid(i, "extra square"));
+            }
+            last = i;
+        }
+        match lines.next() {
+            Some((i, _)) => Err(invalid(i, "extra row")),
+            None => Ok(board),
+        }
+    }
+}
+
+impl Default for Samurai {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl Index<Pos> for Samurai {
+    type Output = Num3x3;
+
+    fn index(&self, (x, y): Pos) -> &Num3x3 {
+        &self.cells[y * Self::SIZE + x]
+    }
+}
+
+impl IndexMut<Pos> for Samurai {
+    fn index_mut(&mut self, (x, y): Pos) -> &mut Num3x3 {
+        &mut self.cells[y * Self::SIZE + x]
+    }
+}
+
+impl fmt::Display for Samurai {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..Self::SIZE {
+            let squares = (0..Self::SIZE).map(|x| {
+                if Self::contains((x, y)) {
+                    square_str(self[(x, y)])
+                } else {
+                    "-".into()
+                }
+            });
+            writeln!(f, "{}", squares.collect::<Vec<_>>().join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seeded_rng;
+
+    #[test]
+    fn layout() {
+        assert_eq!(Samurai::positions().count(), Samurai::CELLS);
+        assert_eq!(Samurai::grids_of((7, 7)).collect::<Vec<_>>(), [0, 2]);
+        assert_eq!(Samurai::grids_of((10, 3)).count(), 0);
+        assert_eq!(Samurai::grids_of((10, 10)).collect::<Vec<_>>(), [2]);
+        assert!(!Samurai::contains((9, 0)) && Samurai::contains((12, 8)));
+
+        // The shared box is the bottom right of one board and the top left of the other
+        let mut board = Samurai::empty();
+        let mut grid = Grid3x3::empty();
+        grid[(8, 8)] = Num3x3::new(5);
+        board.set_grid(0, &grid);
+        assert_eq!(board.grid(2)[(2, 2)].num(), Some(5));
+        assert!(!board.pos_is_valid((8, 11), 5) && !board.pos_is_valid((8, 0), 5));
+        assert!(board.pos_is_valid((20, 8), 5));
+
+        // Notes are cleared on the board below the shared box too
+        board[(6, 20)] = Num3x3::new_note(5).with_note(6);
+        let moves = board.place_num((6, 13), Num3x3::new(5)).unwrap();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(board[(6, 20)].has_note(5), Some(false));
+        assert_eq!(board[(6, 20)].has_note(6), Some(true));
+
+        // The squares between the boards stay empty, so the board still reads back
+        assert!(!board.pos_is_valid((10, 3), 1) && !board.pos_is_valid((21, 0), 1));
+        assert!(board.place_num((10, 3), Num3x3::new(1)).is_none());
+        assert!(board.place_num((3, 10), Num3x3::new(0)).is_none());
+        assert!(Samurai::parse(&board.to_string()) == Ok(board));
+    }
+
+    #[test]
+    fn generate() {
+        let (mut grid, solution) = Samurai::generate_with_rng(&mut seeded_rng(12));
+        assert!(solution.is_valid().is_none());
+        assert!(grid.mistakes(&solution).is_empty());
+        assert!(grid.has_unique_solution());
+        grid.set_given();
+        let text = grid.to_string();
+        assert!(Samurai::parse(&text) == Ok(grid.clone()));
+        assert_eq!(
+            Samurai::parse(&text.replacen('-', ".", 1)).err(),
+            Some(SudkuError::InvalidSave {
+                line: 1,
+                reason: "square between the boards"
+            })
+        );
+
+        let hint = grid.hint().expect("no hint");
+        assert!(hint.reason.starts_with("On the "));
+        let moves = grid.apply_step(&hint.step);
+        assert!(!moves.is_empty() && grid.mistakes(&solution).is_empty());
+        assert!(grid.solve() && grid.mistakes(&solution).is_empty());
+        assert!(grid.is_valid().is_none());
+    }
+}
@@ -0,0 +1,683 @@
+use crate::{
+    grid::{num_symbol, positions, symbol_num},
+    Arrow, Cage, Dot, GridNum, History, Kropki, Move, MultiHistory, Pos, Rules, SudkuError,
+    SudkuResult, SudokuGrid, Thermo,
+};
+use std::fmt::{self, Write};
+use std::time::Duration;
+
+/// The version written by [`GameSave`]. Saves with a newer version are rejected when parsing.
+pub const SAVE_VERSION: u32 = 1;
+
+const SAVE_HEADER: &str = "sudku-save";
+
+/// Everything needed to pick a game back up: the board (givens, numbers, and notes), the rules it's
+/// played with, the undo/redo history, the time played, and any settings the frontend wants to keep
+/// with the game.
+///
+/// Saves are written as text with [`fmt::Display`] and read back with [`GameSave::parse`]. The
+/// first line is `sudku-save <version>`, followed by one `<key> <value>` line per field:
+///
+/// ```text
+/// sudku-save 1
+/// rank 2
+/// givens 1..4.4..........
+/// values .2..............
+/// notes 2,0:3 3,1:12
+/// solution 1234341221434321
+/// cage 3 0,0 1,0
+/// elapsed-ms 61250
+/// setting difficulty 1
+/// cursor 2
+/// move 1,0:.>2
+/// move 2,0:.>[3]
+/// ```
+///
+/// `givens` and `values` list every square row by row, with `.` for squares that aren't given or
+/// don't hold a number the player entered. `notes` lists the squares holding notes as `x,y:notes`.
+/// `solution` is the solved board, also row by row, and is left out if it isn't known.
+/// Each `cage` line is a Killer Sudoku cage, made of its sum and its squares as `x,y`, and each
+/// `region` line is an extra region (see [`Rules::regions`]), made of its squares. `boxes` is the
+/// Jigsaw layout (see [`Rules::boxes`]), row by row with the boxes numbered from 1. Each `thermo`
+/// and `arrow` line is the squares of a thermometer from its bulb or an arrow from its circle, and
+/// each `kropki` line is a `white` or `black` dot and its two squares. Each `setting` line is a key
+/// and its value, with `\\` written for a backslash, `\s` for a space, `\n` for a newline, `\t` for
+/// a tab, and `\u{...}` with the hex code for any other whitespace, so both can hold anything.
+/// Each `move` line is one history entry, made of `x,y:old>new` changes where a square is written
+/// as `.`, a number, `!` and a number for a given, or its notes in brackets. `cursor` is how many
+/// of the entries are applied; the rest can be redone. Unknown keys are skipped, so fields can be
+/// added without bumping the version.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GameSave<G: SudokuGrid> {
+    pub grid: G,
+    /// The solution of the puzzle, used to point out mistakes.
+    pub solution: Option<G>,
+    pub rules: Rules,
+    /// History entries, oldest first. Each entry holds the moves undone and redone together.
+    pub history: Vec<Vec<Move<G::Num>>>,
+    /// How many of the history entries are applied.
+    pub cursor: usize,
+    pub elapsed: Duration,
+    /// Key/value pairs, in the order they were set.
+    pub settings: Vec<(String, String)>,
+}
+
+impl<G: SudokuGrid> GameSave<G> {
+    /// A save of the board with no history, time, or settings.
+    pub fn new(grid: G) -> Self {
+        Self {
+            grid,
+            solution: None,
+            rules: Rules::default(),
+            history: Vec::new(),
+            cursor: 0,
+            elapsed: Duration::ZERO,
+            settings: Vec::new(),
+        }
+    }
+
+    /// Migrates a board saved with [`SudokuGrid::encode`], which is how games were saved before
+    /// this format. Returns None if the encoding is too short.
+    pub fn from_legacy(encoded: impl AsRef<[u8]>) -> Option<Self> {
+        G::from_encoded(encoded).map(Self::new)
+    }
+
+    /// Reads a save written with [`fmt::Display`]. Fails if the text isn't a save, is from a
+    /// newer version, is for a different board size, or holds anything invalid for the board.
+    pub fn parse(s: &str) -> SudkuResult<Self> {
+        let invalid = |line, reason| SudkuError::InvalidSave { line, reason };
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.trim()))
+            .filter(|(_, l)| !l.is_empty());
+        let (first, header) = lines.next().ok_or(invalid(1, "empty"))?;
+        let version = header
+            .strip_prefix(SAVE_HEADER)
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .ok_or(invalid(first, "missing header"))?;
+        if version == 0 || version > SAVE_VERSION {
+            return Err(invalid(first, "unsupported version"));
+        }
+
+        let mut save = Self::new(G::empty());
+        let (mut givens, mut values) = (None, None);
+        let mut notes = Vec::new();
+        let (mut rank, mut cursor_line) = (None, first);
+        for (i, line) in lines {
+            let (key, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let value = rest.trim();
+            let squares = || parse_squares::<G>(value).ok_or(invalid(i, "bad squares"));
+            let cells = || parse_cells::<G>(value).ok_or(invalid(i, "bad squares"));
+            match key {
+                "rank" => {
+                    let r = value.parse().map_err(|_| invalid(i, "bad rank"))?;
+                    if r != G::RANK {
+                        return Err(SudkuError::WrongRank {
+                            rank: r,
+                            expected: G::RANK,
+                        });
+                    }
+                    rank = Some(r);
+                }
+                "givens" => givens = Some(squares()?),
+                "values" => values = Some((i, squares()?)),
+                "notes" => {
+                    for s in value.split_whitespace() {
+                        let (pos, ns) = s
+                            .split_once(':')
+                            .and_then(|(pos, ns)| {
+                                Some((parse_pos::<G>(pos)?, parse_notes::<G>(ns)?))
+                            })
+                            .ok_or(invalid(i, "bad notes"))?;
+                        notes.push((i, pos, ns));
+                    }
+                }
+                "solution" => {
+                    let mut solution = G::empty();
+                    for (pos, n) in positions::<G>().zip(squares()?) {
+                        solution[pos] = G::Num::new(n);
+                    }
+                    save.solution = Some(solution);
+                }
+                "cage" => {
+                    let cage = value.split_once(' ').and_then(|(sum, cells)| {
+                        Some(Cage::new(sum.parse().ok()?, parse_cells::<G>(cells)?))
+                    });
+                    save.rules.cages.push(cage.ok_or(invalid(i, "bad cage"))?);
+                }
+                "region" => save.rules.regions.push(cells()?),
+                "thermo" => save.rules.thermos.push(Thermo::new(cells()?)),
+                "arrow" => save.rules.arrows.push(Arrow::new(cells()?)),
+                "kropki" => {
+                    let dot = value.split_once(' ').and_then(|(dot, cells)| {
+                        let dot = match dot {
+                            "white" => Dot::White,
+                            "black" => Dot::Black,
+                            _ => return None,
+                        };
+                        let [a, b] = parse_cells::<G>(cells)?[..] else {
+                            return None;
+                        };
+                        Some(Kropki::new(dot, a, b))
+                    });
+                    save.rules
+                        .dots
+                        .push(dot.ok_or(invalid(i, "bad kropki dot"))?);
+                }
+                "boxes" => {
+                    let boxes = value
+                        .chars()
+                        .map(|c| parse_num::<G>(c).filter(|&n| n != 0).map(|n| n - 1))
+                        .collect::<Option<_>>()
+                        .ok_or(invalid(i, "bad boxes"))?;
+                    save.rules.boxes = Some(boxes);
+                }
+                "elapsed-ms" => {
+                    let ms = value.parse().map_err(|_| invalid(i, "bad time"))?;
+                    save.elapsed = Duration::from_millis(ms);
+                }
+                "setting" => {
+                    // Not trimmed, since an empty key leaves a space before the value
+                    let (k, v) = rest.split_once(' ').unwrap_or((rest, ""));
+                    let (k, v) = unescape(k)
+                        .zip(unescape(v))
+                        .ok_or(invalid(i, "bad setting"))?;
+                    save.set_setting(k, v);
+                }
+                "cursor" => {
+                    save.cursor = value.parse().map_err(|_| invalid(i, "bad cursor"))?;
+                    cursor_line = i;
+                }
+                "move" => save.history.push(
+                    value
+                        .split_whitespace()
+                        .map(parse_move::<G>)
+                        .collect::<Option<_>>()
+                        .ok_or(invalid(i, "bad move"))?,
+                ),
+                _ => (),
+            }
+        }
+        if rank.is_none() {
+            return Err(invalid(first, "missing rank"));
+        }
+        if save.cursor > save.history.len() {
+            return Err(invalid(cursor_line, "cursor past the history"));
+        }
+        save.rules.check::<G>()?;
+
+        let givens = givens.ok_or(invalid(first, "missing givens"))?;
+        let (values_line, values) = values.ok_or(invalid(first, "missing values"))?;
+        for ((pos, g), v) in positions::<G>().zip(givens).zip(values) {
+            save.grid[pos] = match (g, v) {
+                (0, 0) => G::Num::new(0),
+                (g, 0) => {
+                    let mut num = G::Num::new(g);
+                    num.set_given();
+                    num
+                }
+                (0, v) => G::Num::new(v),
+                _ => return Err(invalid(values_line, "value on a given square")),
+            };
+        }
+        for (i, pos, ns) in notes {
+            if save.grid[pos] != G::Num::new(0) {
+                return Err(invalid(i, "notes on a filled square"));
+            }
+            save.grid[pos] = ns;
+        }
+        Ok(save)
+    }
+
+    /// The history as used by frontends that undo a single move at a time.
+    pub fn history(&self) -> History<G::Num> {
+        let before = self.history[..self.cursor].iter().map(Vec::len).sum();
+        History::from_moves(self.history.iter().flatten().copied(), before)
+    }
+
+    pub fn set_history(&mut self, hist: &History<G::Num>) {
+        let (mvs, cursor) = hist.moves();
+        self.history = mvs.into_iter().map(|mv| vec![mv]).collect();
+        self.cursor = cursor;
+    }
+
+    pub fn multi_history(&self) -> MultiHistory<G::Num> {
+        MultiHistory::from_moves(self.history.iter().cloned(), self.cursor)
+    }
+
+    pub fn set_multi_history(&mut self, hist: &MultiHistory<G::Num>) {
+        (self.history, self.cursor) = hist.moves();
+    }
+
+    pub fn setting(&self, key: &str) -> Option<&str> {
+        self.settings
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets the value for the key, replacing any old value.
+    pub fn set_setting(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        let (key, value) = (key.into(), value.into());
+        match self.settings.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, v)) => *v = value,
+            None => self.settings.push((key, value)),
+        }
+    }
+}
+
+impl<G: SudokuGrid> fmt::Display for GameSave<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{SAVE_HEADER} {SAVE_VERSION}")?;
+        writeln!(f, "rank {}", G::RANK)?;
+
+        let (mut givens, mut values) = (String::new(), String::new());
+        let mut notes = Vec::new();
+        for pos in positions::<G>() {
+            let num = self.grid[pos];
+            let c = match num.num_or_zero() {
+                0 => '.',
+                n => num_symbol(n),
+            };
+            let (g, v) = if num.is_given() { (c, '.') } else { ('.', c) };
+            givens.push(g);
+            values.push(v);
+            if num.is_note() {
+                notes.push(format!("{},{}:{}", pos.0, pos.1, notes_str(num)));
+            }
+        }
+        writeln!(f, "givens {givens}")?;
+        writeln!(f, "values {values}")?;
+        writeln!(f, "notes {}", notes.join(" "))?;
+        if let Some(solution) = &self.solution {
+            let nums = positions::<G>().map(|pos| match solution[pos].num_or_zero() {
+                0 => '.',
+                n => num_symbol(n),
+            });
+            writeln!(f, "solution {}", nums.collect::<String>())?;
+        }
+        for cage in &self.rules.cages {
+            let mut line = format!("cage {}", cage.sum);
+            for (x, y) in &cage.cells {
+                write!(line, " {x},{y}")?;
+            }
+            writeln!(f, "{line}")?;
+        }
+        for region in &self.rules.regions {
+            let mut line = String::from("region");
+            for (x, y) in region {
+                write!(line, " {x},{y}")?;
+            }
+            writeln!(f, "{line}")?;
+        }
+        for (key, cells) in self
+            .rules
+            .thermos
+            .iter()
+            .map(|t| ("thermo", &t.cells[..]))
+            .chain(self.rules.arrows.iter().map(|a| ("arrow", &a.cells[..])))
+        {
+            let mut line = String::from(key);
+            for (x, y) in cells {
+                write!(line, " {x},{y}")?;
+            }
+            writeln!(f, "{line}")?;
+        }
+        for dot in &self.rules.dots {
+            let [(ax, ay), (bx, by)] = dot.cells;
+            let kind = match dot.dot {
+                Dot::White => "white",
+                Dot::Black => "black",
+            };
+            writeln!(f, "kropki {kind} {ax},{ay} {bx},{by}")?;
+        }
+        if let Some(boxes) = &self.rules.boxes {
+            let boxes = boxes.iter().map(|&b| num_symbol(b + 1));
+            writeln!(f, "boxes {}", boxes.collect::<String>())?;
+        }
+
+        writeln!(f, "elapsed-ms {}", self.elapsed.as_millis())?;
+        for (k, v) in &self.settings {
+            writeln!(f, "setting {} {}", escape(k), escape(v))?;
+        }
+
+        writeln!(f, "cursor {}", self.cursor)?;
+        for mvs in &self.history {
+            let mut line = String::from("move");
+            for mv in mvs {
+                let (x, y) = mv.pos;
+                write!(
+                    line,
+                    " {x},{y}:{}>{}",
+                    square_str(mv.old),
+                    square_str(mv.new)
+                )?;
+            }
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+fn notes_str<N: GridNum>(num: N) -> String {
+    (1..=N::MAX)
+        .filter(|&n| num.has_note(n) == Some(true))
+        .map(num_symbol)
+        .collect()
+}
+
+// Escapes the backslashes and whitespace in a setting so it's one word that can be put back
+// exactly with `unescape`.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ' ' => escaped.push_str("\\s"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_whitespace() => {
+                // Writing to a `String` can't fail
+                let _ = write!(escaped, "\\u{{{:x}}}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(s: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            's' => ' ',
+            'n' => '\n',
+            't' => '\t',
+            'u' => {
+                let rest = chars.as_str().strip_prefix('{')?;
+                let (hex, rest) = rest.split_once('}')?;
+                chars = rest.chars();
+                char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+            }
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+pub(crate) fn square_str<N: GridNum>(num: N) -> String {
+    if num.is_note() {
+        format!("[{}]", notes_str(num))
+    } else {
+        match num.num_or_zero() {
+            0 => ".".into(),
+            n if num.is_given() => format!("!{}", num_symbol(n)),
+            n => num_symbol(n).into(),
+        }
+    }
+}
+
+fn parse_cells<G: SudokuGrid>(s: &str) -> Option<Vec<Pos>> {
+    s.split_whitespace().map(parse_pos::<G>).collect()
+}
+
+fn parse_num<G: SudokuGrid>(c: char) -> Option<u8> {
+    symbol_num(c).filter(|&n| n as usize <= G::SIZE)
+}
+
+fn parse_squares<G: SudokuGrid>(s: &str) -> Option<Vec<u8>> {
+    let nums = s
+        .chars()
+        .map(|c| if c == '.' { Some(0) } else { parse_num::<G>(c) })
+        .collect::<Option<Vec<_>>>()?;
+    (nums.len() == G::CELLS).then_some(nums)
+}
+
+fn parse_notes<G: SudokuGrid>(s: &str) -> Option<G::Num> {
+    let mut num = G::Num::from_bits(G::Num::NOTE_BIT);
+    for c in s.chars() {
+        num = num.with_note(parse_num::<G>(c)?);
+    }
+    Some(num)
+}
+
+fn parse_pos<G: SudokuGrid>(s: &str) -> Option<Pos> {
+    let (x, y) = s.split_once(',')?;
+    let pos = (x.parse().ok()?, y.parse().ok()?);
+    (pos.0 < G::SIZE && pos.1 < G::SIZE).then_some(pos)
+}
+
+pub(crate) fn parse_square<G: SudokuGrid>(s: &str) -> Option<G::Num> {
+    if s == "." {
+        Some(G::Num::new(0))
+    } else if let Some(ns) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        parse_notes::<G>(ns)
+    } else if let Some(n) = s.strip_prefix('!') {
+        let mut num = parse_square::<G>(n).filter(|n| n.num_or_zero() != 0)?;
+        num.set_given();
+        Some(num)
+    } else {
+        let mut chars = s.chars();
+        let n = parse_num::<G>(chars.next()?)?;
+        chars.next().is_none().then(|| G::Num::new(n))
+    }
+}
+
+fn parse_move<G: SudokuGrid>(s: &str) -> Option<Move<G::Num>> {
+    let (pos, change) = s.split_once(':')?;
+    let (old, new) = change.split_once('>')?;
+    Some(Move::new(
+        parse_square::<G>(old)?,
+        parse_square::<G>(new)?,
+        parse_pos::<G>(pos)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Grid2x2, Grid3x3, Grid4x4};
+
+    #[test]
+    fn save_parse() {
+        let mut grid = Grid3x3::generate();
+        grid.remove_nums_unique(50);
+        grid.set_given();
+        let mut save = GameSave::new(grid.clone());
+        let empty = positions::<Grid3x3>()
+            .filter(|&pos| grid[pos].num_or_zero() == 0)
+            .take(3)
+            .collect::<Vec<_>>();
+
+        let mut hist = MultiHistory::new();
+        let mut place = |grid: &mut Grid3x3, pos, num| {
+            hist.update(vec![Move::new(grid[pos], num, pos)]);
+            grid[pos] = num;
+        };
+        place(&mut grid, empty[0], GridNum::new(1));
+        place(&mut grid, empty[1], GridNum::new_note(4));
+        let toggled = grid[empty[1]].with_toggle_note(9);
+        place(&mut grid, empty[1], toggled);
+        place(&mut grid, empty[2], GridNum::new(7));
+        hist.undo();
+        grid[empty[2]] = GridNum::new(0);
+
+        save.grid = grid;
+        save.solution = save.grid.givens().unique_solution();
+        assert!(save.solution.is_some());
+        save.set_multi_history(&hist);
+        save.rules = Rules::killer(vec![
+            Cage::new(10, vec![(0, 0), (1, 0), (0, 1)]),
+            Cage::new(9, vec![(8, 8)]),
+        ]);
+        save.rules.regions = Rules::diagonal::<Grid3x3>().regions;
+        save.rules.thermos = vec![Thermo::new(vec![(4, 4), (5, 5), (6, 5)])];
+        save.rules.arrows = vec![Arrow::new(vec![(0, 8), (1, 8)])];
+        save.rules.dots = vec![Kropki::new(Dot::Black, (3, 3), (3, 4))];
+        save.elapsed = Duration::from_millis(61_250);
+        save.set_setting("difficulty", "2");
+        save.set_setting("theme", "dark mode");
+        save.set_setting("difficulty", "3");
+
+        let text = save.to_string();
+        assert!(text.starts_with("sudku-save 1\nrank 3\n"), "bad header");
+        let parsed = GameSave::<Grid3x3>::parse(&text).expect("bad parse");
+        assert!(parsed == save, "bad round trip");
+        assert_eq!(parsed.setting("difficulty"), Some("3"));
+        assert_eq!(parsed.setting("theme"), Some("dark mode"));
+        assert!(
+            text.contains("setting theme dark\\smode\n"),
+            "bad setting escape"
+        );
+        assert!(parsed.multi_history() == hist, "bad history");
+        assert_eq!(parsed.history().moves().1, 3, "bad single history cursor");
+
+        // Other ranks and newer versions aren't read, but unknown keys are skipped
+        let error = |line, reason| Some(SudkuError::InvalidSave { line, reason });
+        assert_eq!(
+            GameSave::<Grid4x4>::parse(&text).err(),
+            Some(SudkuError::WrongRank {
+                rank: 3,
+                expected: 4
+            })
+        );
+        assert_eq!(
+            GameSave::<Grid3x3>::parse(&text.replace("save 1", "save 2")).err(),
+            error(1, "unsupported version")
+        );
+        assert!(GameSave::<Grid3x3>::parse(&format!("{text}future-key 1\n")).is_ok());
+        let cursor_line = text.lines().position(|l| l == "cursor 3").unwrap() + 1;
+        assert_eq!(
+            GameSave::<Grid3x3>::parse(&text.replace("cursor 3", "cursor 5")).err(),
+            error(cursor_line, "cursor past the history")
+        );
+    }
+
+    #[test]
+    fn parse_example() {
+        let text = "sudku-save 1
+            rank 2
+            givens 1..4.4..........
+            values .2..............
+            notes 2,0:3 3,1:12
+            solution 1234341221434321
+            cage 3 0,0 1,0
+            elapsed-ms 61250
+            setting difficulty 1
+            cursor 2
+            move 1,0:.>2
+            move 2,0:.>[3]";
+        let save = GameSave::<Grid2x2>::parse(text).expect("bad parse");
+        assert!(save.grid[(0, 0)].is_given());
+        assert_eq!(save.grid[(1, 0)].num(), Some(2));
+        assert!(!save.grid[(1, 0)].is_given());
+        assert_eq!(
+            save.grid[(3, 1)].notes(),
+            Some(vec![true, true, false, false])
+        );
+        assert_eq!(save.cursor, 2);
+        assert_eq!(
+            save.history[1],
+            vec![Move::new(GridNum::new(0), GridNum::new_note(3), (2, 0))]
+        );
+        assert_eq!(save.elapsed, Duration::from_millis(61_250));
+        let solution = save.solution.as_ref().expect("missing solution");
+        assert_eq!(save.grid.mistakes(solution), Vec::new());
+        assert_eq!(save.rules.cages, [Cage::new(3, vec![(0, 0), (1, 0)])]);
+        assert!(save.rules.is_valid(solution).is_none());
+
+        // Squares can't be both given and entered, and notes can only go in empty squares
+        let parse = |text: &str| GameSave::<Grid2x2>::parse(text).err();
+        let error = |line, reason| Some(SudkuError::InvalidSave { line, reason });
+        assert_eq!(
+            parse(&text.replace("values .2", "values 2.")),
+            error(4, "value on a given square")
+        );
+        assert_eq!(
+            parse(&text.replace("2,0:3", "1,0:3")),
+            error(5, "notes on a filled square")
+        );
+        assert_eq!(
+            parse(&text.replace("1..4", "1..5")),
+            error(3, "bad squares")
+        );
+        // Cages can't overlap or go off the board
+        assert_eq!(
+            parse(&text.replace("1,0\n", "1,0 0,0\n")),
+            Some(SudkuError::InvalidCage(0))
+        );
+        assert_eq!(
+            parse(&text.replace("cage 3 0,0", "cage 3 4,0")),
+            error(7, "bad cage")
+        );
+        let region = format!("{text}\nregion 0,0 1,1 2,2 3,3");
+        let save = GameSave::<Grid2x2>::parse(&region).expect("bad region parse");
+        assert!(save.rules.regions == Rules::diagonal::<Grid2x2>().regions[..1]);
+        assert_eq!(
+            parse(&region.replace(" 3,3", "")),
+            Some(SudkuError::InvalidRegion(0))
+        );
+        let jigsaw = format!("{text}\nboxes 1112132233423444");
+        let save = GameSave::<Grid2x2>::parse(&jigsaw).expect("bad boxes parse");
+        assert_eq!(save.rules.box_of::<Grid2x2>((1, 1)), 2);
+        assert_eq!(
+            parse(&jigsaw.replace("1112", "1114")),
+            Some(SudkuError::InvalidBoxes)
+        );
+        assert_eq!(
+            parse(&jigsaw.replace("1112", "1115")),
+            error(13, "bad boxes")
+        );
+        // Kropki dots go between two squares side by side
+        let kropki = format!("{text}\nkropki white 0,1 0,2");
+        let save = GameSave::<Grid2x2>::parse(&kropki).expect("bad kropki parse");
+        assert_eq!(save.rules.dots, [Kropki::new(Dot::White, (0, 1), (0, 2))]);
+        assert_eq!(
+            parse(&kropki.replace("0,2", "1,2")),
+            Some(SudkuError::InvalidConstraint(1))
+        );
+        assert_eq!(
+            parse(&kropki.replace("white", "grey")),
+            error(13, "bad kropki dot")
+        );
+    }
+
+    #[test]
+    fn settings() {
+        let mut save = GameSave::new(Grid2x2::empty());
+        save.set_setting("padded", "  padded ");
+        save.set_setting("lines", "first\nsecond\r\n\tthird\\s");
+        save.set_setting("odd key\u{a0}", "");
+        save.set_setting("", "no key");
+        let parsed = GameSave::<Grid2x2>::parse(&save.to_string()).expect("bad parse");
+        assert!(parsed == save, "bad round trip");
+        assert_eq!(parsed.setting("padded"), Some("  padded "));
+        assert_eq!(parsed.setting("lines"), Some("first\nsecond\r\n\tthird\\s"));
+
+        // Values written before settings were escaped still read back
+        let text = save
+            .to_string()
+            .replace("setting padded", "setting old dark mode\nsetting x");
+        let parsed = GameSave::<Grid2x2>::parse(&text).expect("bad old parse");
+        assert_eq!(parsed.setting("old"), Some("dark mode"));
+        assert!(GameSave::<Grid2x2>::parse(&format!("{text}setting bad \\q\n")).is_err());
+    }
+
+    #[test]
+    fn legacy() {
+        let mut grid = Grid3x3::generate();
+        grid.remove_nums_unique(40);
+        grid.set_given();
+        let save = GameSave::<Grid3x3>::from_legacy(grid.encode()).expect("bad legacy encoding");
+        assert!(save.grid == grid);
+        assert!(save.history.is_empty() && save.elapsed == Duration::ZERO);
+        assert!(GameSave::<Grid3x3>::from_legacy([0; 10]).is_none());
+    }
+}
@@ -0,0 +1,502 @@
+use crate::{
+    grid::{num_symbol, positions, symbol_num},
+    Arrow, Cage, GameSave, Grid2x2, Grid3x3, Grid4x4, Grid5x5, GridNum, History, Kropki, Move,
+    MultiHistory, Num2x2, Num3x3, Num4x4, Num5x5, Pos, Rules, SudokuGrid, Thermo,
+};
+use serde::{
+    de::{self, Deserializer},
+    ser::{SerializeMap, Serializer},
+    Deserialize, Serialize,
+};
+use std::time::Duration;
+
+// Squares are written as their number (0 when empty), `{"given": n}` for givens, or
+// `{"notes": [..]}` for notes.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SquareRepr {
+    Num(u8),
+    Given { given: u8 },
+    Notes { notes: Vec<u8> },
+}
+
+fn square_repr<N: GridNum>(num: N) -> SquareRepr {
+    if num.is_note() {
+        SquareRepr::Notes {
+            notes: note_list(num),
+        }
+    } else if num.is_given() {
+        SquareRepr::Given {
+            given: num.num_or_zero(),
+        }
+    } else {
+        SquareRepr::Num(num.num_or_zero())
+    }
+}
+
+fn square_from_repr<N: GridNum, E: de::Error>(repr: SquareRepr) -> Result<N, E> {
+    let check = |n: u8| {
+        if n > N::MAX {
+            Err(E::custom(format!("number {n} is above {}", N::MAX)))
+        } else {
+            Ok(n)
+        }
+    };
+    match repr {
+        SquareRepr::Num(n) => Ok(N::new(check(n)?)),
+        SquareRepr::Given { given: 0 } => Err(E::custom("given square is empty")),
+        SquareRepr::Given { given } => {
+            let mut num = N::new(check(given)?);
+            num.set_given();
+            Ok(num)
+        }
+        SquareRepr::Notes { notes } => notes_from_list(&notes),
+    }
+}
+
+fn note_list<N: GridNum>(num: N) -> Vec<u8> {
+    (1..=N::MAX)
+        .filter(|&n| num.has_note(n) == Some(true))
+        .collect()
+}
+
+fn notes_from_list<N: GridNum, E: de::Error>(notes: &[u8]) -> Result<N, E> {
+    let mut num = N::from_bits(N::NOTE_BIT);
+    for &n in notes {
+        if n == 0 || n > N::MAX {
+            return Err(E::custom(format!(
+                "note {n} isn't between 1 and {}",
+                N::MAX
+            )));
+        }
+        num = num.with_note(n);
+    }
+    Ok(num)
+}
+
+// Grids are written as strings of their numbers row by row, with `.` for empty squares, along
+// with the same for just the givens and a list of the squares holding notes.
+#[derive(Serialize, Deserialize)]
+struct GridRepr {
+    cells: String,
+    #[serde(default)]
+    givens: Option<String>,
+    #[serde(default)]
+    notes: Vec<NotesRepr>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct NotesRepr {
+    pos: Pos,
+    notes: Vec<u8>,
+}
+
+fn grid_repr<G: SudokuGrid>(grid: &G) -> GridRepr {
+    let (mut cells, mut givens) = (String::new(), String::new());
+    let mut notes = Vec::new();
+    for pos in positions::<G>() {
+        let num = grid[pos];
+        let c = match num.num_or_zero() {
+            0 => '.',
+            n => num_symbol(n),
+        };
+        cells.push(c);
+        givens.push(if num.is_given() { c } else { '.' });
+        if num.is_note() {
+            notes.push(NotesRepr {
+                pos,
+                notes: note_list(num),
+            });
+        }
+    }
+    GridRepr {
+        cells,
+        givens: Some(givens),
+        notes,
+    }
+}
+
+fn grid_from_repr<G: SudokuGrid, E: de::Error>(repr: GridRepr) -> Result<G, E> {
+    let parse = |s: &str, what| {
+        let nums = s
+            .chars()
+            .map(|c| match c {
+                '.' | '0' => Some(0),
+                c => symbol_num(c).filter(|&n| n as usize <= G::SIZE),
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| E::custom(format!("invalid symbol in {what}")))?;
+        if nums.len() == G::CELLS {
+            Ok(nums)
+        } else {
+            Err(E::custom(format!(
+                "{what} has {} squares instead of {}",
+                nums.len(),
+                G::CELLS
+            )))
+        }
+    };
+    let cells = parse(&repr.cells, "cells")?;
+    let givens = match &repr.givens {
+        Some(givens) => parse(givens, "givens")?,
+        None => vec![0; G::CELLS],
+    };
+
+    let mut grid = G::empty();
+    for ((pos, n), g) in positions::<G>().zip(cells).zip(givens) {
+        grid[pos] = G::Num::new(n);
+        if g != 0 {
+            if g != n {
+                return Err(E::custom(format!(
+                    "given at {pos:?} doesn't match the cells"
+                )));
+            }
+            grid[pos].set_given();
+        }
+    }
+    for NotesRepr { pos, notes } in repr.notes {
+        if pos.0 >= G::SIZE || pos.1 >= G::SIZE {
+            return Err(E::custom(format!("notes at {pos:?} are off the board")));
+        }
+        if grid[pos].num_or_zero() != 0 {
+            return Err(E::custom(format!("notes at {pos:?} are on a number")));
+        }
+        grid[pos] = notes_from_list(&notes)?;
+    }
+    Ok(grid)
+}
+
+macro_rules! impl_serde {
+    ($($num:ty, $grid:ty);* $(;)?) => {
+        $(
+            impl Serialize for $num {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    square_repr(*self).serialize(serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $num {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    square_from_repr(SquareRepr::deserialize(deserializer)?)
+                }
+            }
+
+            impl Serialize for $grid {
+                fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    grid_repr(self).serialize(serializer)
+                }
+            }
+
+            impl<'de> Deserialize<'de> for $grid {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    grid_from_repr(GridRepr::deserialize(deserializer)?)
+                }
+            }
+        )*
+    };
+}
+
+impl_serde!(
+    Num2x2, Grid2x2;
+    Num3x3, Grid3x3;
+    Num4x4, Grid4x4;
+    Num5x5, Grid5x5;
+);
+
+// Histories are written as all of their moves, oldest first, and how many of them are applied.
+#[derive(Serialize, Deserialize)]
+struct HistoryRepr<L> {
+    moves: L,
+    cursor: usize,
+}
+
+impl<N: Clone + Serialize> Serialize for History<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (moves, cursor) = self.moves();
+        HistoryRepr { moves, cursor }.serialize(serializer)
+    }
+}
+
+impl<'de, N: Clone + Deserialize<'de>> Deserialize<'de> for History<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let HistoryRepr { moves, cursor } = HistoryRepr::<Vec<Move<N>>>::deserialize(deserializer)?;
+        if cursor > moves.len() {
+            return Err(de::Error::custom("history cursor is past the last move"));
+        }
+        Ok(History::from_moves(moves, cursor))
+    }
+}
+
+impl<N: Clone + Serialize> Serialize for MultiHistory<N> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (moves, cursor) = self.moves();
+        HistoryRepr { moves, cursor }.serialize(serializer)
+    }
+}
+
+impl<'de, N: Clone + Deserialize<'de>> Deserialize<'de> for MultiHistory<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let HistoryRepr { moves, cursor } =
+            HistoryRepr::<Vec<Vec<Move<N>>>>::deserialize(deserializer)?;
+        if cursor > moves.len() {
+            return Err(de::Error::custom("history cursor is past the last move"));
+        }
+        Ok(MultiHistory::from_moves(moves, cursor))
+    }
+}
+
+// Saves are written like a `MultiHistory` next to the grid, with the settings as an object. The
+// solution is left out when it isn't known, and the rules are left out when there are none.
+#[derive(Serialize, Deserialize)]
+struct GameSaveRepr<G, H> {
+    grid: G,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solution: Option<G>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cages: Vec<Cage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    regions: Vec<Vec<Pos>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    boxes: Option<Vec<u8>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    thermos: Vec<Thermo>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    arrows: Vec<Arrow>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dots: Vec<Kropki>,
+    history: HistoryRepr<H>,
+    elapsed_ms: u64,
+    #[serde(with = "settings_map")]
+    settings: Vec<(String, String)>,
+}
+
+impl<G> Serialize for GameSave<G>
+where
+    G: SudokuGrid + Serialize,
+    G::Num: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameSaveRepr {
+            grid: &self.grid,
+            solution: self.solution.as_ref(),
+            cages: self.rules.cages.clone(),
+            regions: self.rules.regions.clone(),
+            boxes: self.rules.boxes.clone(),
+            thermos: self.rules.thermos.clone(),
+            arrows: self.rules.arrows.clone(),
+            dots: self.rules.dots.clone(),
+            history: HistoryRepr {
+                moves: &self.history,
+                cursor: self.cursor,
+            },
+            elapsed_ms: self.elapsed.as_millis() as u64,
+            settings: self.settings.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, G> Deserialize<'de> for GameSave<G>
+where
+    G: SudokuGrid + Deserialize<'de>,
+    G::Num: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = GameSaveRepr::<G, Vec<Vec<Move<G::Num>>>>::deserialize(deserializer)?;
+        if repr.history.cursor > repr.history.moves.len() {
+            return Err(de::Error::custom("history cursor is past the last move"));
+        }
+        let on_board = |mv: &Move<G::Num>| mv.pos.0 < G::SIZE && mv.pos.1 < G::SIZE;
+        if !repr.history.moves.iter().flatten().all(on_board) {
+            return Err(de::Error::custom("history has a move off the board"));
+        }
+        let rules = Rules {
+            cages: repr.cages,
+            regions: repr.regions,
+            boxes: repr.boxes,
+            thermos: repr.thermos,
+            arrows: repr.arrows,
+            dots: repr.dots,
+        };
+        rules.check::<G>().map_err(de::Error::custom)?;
+        Ok(GameSave {
+            grid: repr.grid,
+            solution: repr.solution,
+            rules,
+            history: repr.history.moves,
+            cursor: repr.history.cursor,
+            elapsed: Duration::from_millis(repr.elapsed_ms),
+            settings: repr.settings,
+        })
+    }
+}
+
+// Settings keep their order in a `Vec`, but read better as an object.
+mod settings_map {
+    use super::*;
+    use serde::de::{MapAccess, Visitor};
+    use std::fmt;
+
+    pub fn serialize<S: Serializer>(
+        settings: &[(String, String)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(settings.len()))?;
+        for (k, v) in settings {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, String)>, D::Error> {
+        struct SettingsVisitor;
+
+        impl<'de> Visitor<'de> for SettingsVisitor {
+            type Value = Vec<(String, String)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a map of setting names to values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut settings = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    settings.push(entry);
+                }
+                Ok(settings)
+            }
+        }
+
+        deserializer.deserialize_map(SettingsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{seeded_rng, Dot, History3x3, MultiHistory3x3};
+    use serde_json::json;
+
+    #[test]
+    fn num_json() {
+        let num = Num3x3::new(5);
+        assert_eq!(serde_json::to_value(num).unwrap(), json!(5));
+        let mut given = num;
+        given.set_given();
+        assert_eq!(serde_json::to_value(given).unwrap(), json!({ "given": 5 }));
+        let notes = Num3x3::new_note(2).with_note(7);
+        assert_eq!(
+            serde_json::to_value(notes).unwrap(),
+            json!({ "notes": [2, 7] })
+        );
+
+        for num in [Num3x3::new(0), num, given, notes] {
+            let value = serde_json::to_value(num).unwrap();
+            assert_eq!(serde_json::from_value::<Num3x3>(value).unwrap(), num);
+        }
+        assert!(serde_json::from_value::<Num3x3>(json!(10)).is_err());
+        assert!(serde_json::from_value::<Num3x3>(json!({ "notes": [0] })).is_err());
+        assert!(serde_json::from_value::<Num4x4>(json!({ "notes": [16] })).is_ok());
+    }
+
+    #[test]
+    fn grid_json() {
+        let mut grid = Grid2x2::base();
+        grid[(0, 0)].set_given();
+        grid[(1, 0)] = Num2x2::new_note(3).with_note(4);
+        let value = serde_json::to_value(&grid).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "cells": "1.34341221434321",
+                "givens": "1...............",
+                "notes": [{ "pos": [1, 0], "notes": [3, 4] }],
+            })
+        );
+        assert!(serde_json::from_value::<Grid2x2>(value).unwrap() == grid);
+
+        // Only the cells are needed
+        let cells = json!({ "cells": "1234341221434321" });
+        assert!(serde_json::from_value::<Grid2x2>(cells).unwrap() == Grid2x2::base());
+        let bad = [
+            json!({ "cells": "123" }),
+            json!({ "cells": "1234341221434325" }),
+            json!({ "cells": "1234341221434321", "givens": "2..............." }),
+            json!({ "cells": "1234341221434321", "notes": [{ "pos": [0, 0], "notes": [1] }] }),
+        ];
+        for value in bad {
+            assert!(serde_json::from_value::<Grid2x2>(value).is_err());
+        }
+
+        let mut grid = Grid5x5::base();
+        grid.remove_nums(100);
+        let json = serde_json::to_string(&grid).unwrap();
+        assert!(serde_json::from_str::<Grid5x5>(&json).unwrap() == grid);
+    }
+
+    #[test]
+    fn history_json() {
+        let mut hist = History::new();
+        for i in 1..=3 {
+            hist.update(Move::new(Num3x3::new(0), Num3x3::new(i), (i as usize, 0)));
+        }
+        hist.undo();
+        let value = serde_json::to_value(&hist).unwrap();
+        assert_eq!(value["cursor"], json!(2));
+        assert_eq!(
+            value["moves"][0],
+            json!({ "old": 0, "new": 1, "pos": [1, 0] })
+        );
+        assert_eq!(serde_json::from_value::<History3x3>(value).unwrap(), hist);
+
+        let mut multi = MultiHistory::new();
+        multi.update(vec![Move::new(Num3x3::new(0), Num3x3::new(1), (0, 0)); 2]);
+        let value = serde_json::to_value(&multi).unwrap();
+        assert_eq!(
+            serde_json::from_value::<MultiHistory3x3>(value).unwrap(),
+            multi
+        );
+        let bad = json!({ "moves": [], "cursor": 1 });
+        assert!(serde_json::from_value::<MultiHistory3x3>(bad).is_err());
+    }
+
+    #[test]
+    fn save_json() {
+        let mut grid = Grid3x3::generate();
+        grid.remove_nums_unique(40);
+        grid.set_given();
+        let mut save = GameSave::new(grid);
+        save.history = vec![vec![Move::new(Num3x3::new(0), Num3x3::new_note(4), (0, 0))]];
+        save.cursor = 1;
+        save.elapsed = Duration::from_millis(90_500);
+        save.set_setting("difficulty", "2");
+
+        let json = serde_json::to_string(&save).unwrap();
+        let value = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(value["elapsed_ms"], json!(90_500));
+        assert_eq!(value["settings"], json!({ "difficulty": "2" }));
+        assert!(value.get("solution").is_none() && value.get("cages").is_none());
+        assert!(value.get("boxes").is_none());
+        assert!(serde_json::from_str::<GameSave<Grid3x3>>(&json).unwrap() == save);
+
+        save.solution = save.grid.unique_solution();
+        save.rules.cages = vec![Cage::new(4, vec![(0, 0), (0, 1)])];
+        save.rules.regions = Rules::windoku::<Grid3x3>().regions;
+        save.rules.boxes = Some(Rules::jigsaw_boxes::<Grid3x3>(&mut seeded_rng(3)));
+        save.rules.thermos = vec![Thermo::new(vec![(2, 2), (3, 3)])];
+        save.rules.arrows = vec![Arrow::new(vec![(8, 8), (8, 7), (7, 6)])];
+        save.rules.dots = vec![Kropki::new(Dot::White, (5, 5), (6, 5))];
+        let json = serde_json::to_string(&save).unwrap();
+        assert!(serde_json::from_str::<GameSave<Grid3x3>>(&json).unwrap() == save);
+
+        let json = json.replace("[0,1]", "[0,9]");
+        assert!(serde_json::from_str::<GameSave<Grid3x3>>(&json).is_err());
+
+        // Moves off the board would panic on undo or redo
+        save.history = vec![vec![Move::new(Num3x3::new(0), Num3x3::new(1), (99, 0))]];
+        let json = serde_json::to_string(&save).unwrap();
+        assert!(serde_json::from_str::<GameSave<Grid3x3>>(&json).is_err());
+    }
+}
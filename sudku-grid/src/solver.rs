@@ -0,0 +1,477 @@
+use crate::Constraint;
+use rand::{Rng, RngCore};
+use std::rc::Rc;
+
+// Bitmask based solver shared by all of the grid sizes. Each region (a row, column, or box, or any
+// other group of squares that has to hold every number once) keeps a mask of the numbers already
+// placed in it (bit `n - 1` is set if `n` is used), so the candidates for a square are found with a
+// few ORs instead of rescanning the board. The search always branches on the most constrained
+// square, preferring squares and numbers that are forced (naked and hidden singles). Killer cages
+// are followed too, keeping track of the numbers used in each and what they add up to so far. Any
+// other constraint is asked about each candidate in its squares.
+#[derive(Clone)]
+pub(crate) struct Solver {
+    full: u32,
+    // Row-major, with 0 being an empty square
+    cells: Vec<u8>,
+    // The squares of each region, and the numbers placed in it
+    regions: Vec<Vec<usize>>,
+    masks: Vec<u32>,
+    // The regions each square is in are `region_of[region_start[idx]..region_start[idx + 1]]`
+    region_start: Vec<usize>,
+    region_of: Vec<usize>,
+    cages: Vec<Cage>,
+    // The index of the cage each square is in, or `NO_CAGE`
+    cage_of: Vec<usize>,
+    // The other constraints with their squares, and the ones each square is in, found like the
+    // regions are
+    constraints: Vec<(Vec<usize>, Rc<dyn Constraint>)>,
+    constraint_start: Vec<usize>,
+    constraint_of: Vec<usize>,
+    stack: Vec<Frame>,
+    started: bool,
+    done: bool,
+    // Numbers placed by the search, and how many it may place before giving up
+    guesses: usize,
+    max_guesses: usize,
+}
+
+const NO_CAGE: usize = usize::MAX;
+
+#[derive(Clone)]
+struct Cage {
+    sum: u32,
+    len: usize,
+    // The numbers placed in the cage, what they add up to, and how many there are
+    used: u32,
+    total: u32,
+    filled: usize,
+    // The numbers that can still go in the cage's empty squares
+    cands: u32,
+}
+
+impl Cage {
+    fn update(&mut self, full: u32) {
+        self.cands = if self.total > self.sum {
+            0
+        } else {
+            combinations(
+                full & !self.used,
+                self.len - self.filled,
+                self.sum - self.total,
+            )
+        };
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Frame {
+    idx: usize,
+    // The numbers not yet tried in the square
+    remaining: u32,
+}
+
+impl Solver {
+    /// Creates a solver for a board of the given rank from its numbers in row-major order (0 for
+    /// empty squares). Returns None if any of the numbers conflict.
+    pub(crate) fn new(rank: usize, cells: &[u8]) -> Option<Self> {
+        Self::with_rules(rank, cells, &classic_regions(rank), &[], &[])
+    }
+
+    /// Same as `new`, but with the regions (given as their squares' indexes) that have to hold
+    /// every number once instead of the rows, columns, and boxes, so those have to be included if
+    /// they're wanted. The squares don't have to make up a square board then, as long as each is
+    /// in some region. The numbers in each cage (given as its squares' indexes and its sum) also
+    /// have to add up to the sum without repeating. The cages can't overlap. Every other
+    /// constraint (given with its squares' indexes) has to fit too.
+    pub(crate) fn with_rules(
+        rank: usize,
+        cells: &[u8],
+        regions: &[Vec<usize>],
+        cages: &[(Vec<usize>, u32)],
+        constraints: &[(Vec<usize>, Rc<dyn Constraint>)],
+    ) -> Option<Self> {
+        let size = rank * rank;
+        debug_assert!(regions.iter().all(|region| region.len() == size));
+        let (region_start, region_of) = index_lists(cells.len(), regions);
+        let constraint_cells = constraints
+            .iter()
+            .map(|(idxs, _)| idxs.clone())
+            .collect::<Vec<_>>();
+        let (constraint_start, constraint_of) = index_lists(cells.len(), &constraint_cells);
+        let mut solver = Self {
+            full: u32::MAX >> (32 - size),
+            cells: vec![0; cells.len()],
+            regions: regions.to_vec(),
+            masks: vec![0; regions.len()],
+            region_start,
+            region_of,
+            cages: Vec::with_capacity(cages.len()),
+            cage_of: vec![NO_CAGE; cells.len()],
+            constraints: constraints.to_vec(),
+            constraint_start,
+            constraint_of,
+            stack: Vec::new(),
+            started: false,
+            done: false,
+            guesses: 0,
+            max_guesses: usize::MAX,
+        };
+        for (i, (idxs, sum)) in cages.iter().enumerate() {
+            for &idx in idxs {
+                if solver.cage_of[idx] != NO_CAGE {
+                    return None;
+                }
+                solver.cage_of[idx] = i;
+            }
+            let mut cage = Cage {
+                sum: *sum,
+                len: idxs.len(),
+                used: 0,
+                total: 0,
+                filled: 0,
+                cands: 0,
+            };
+            cage.update(solver.full);
+            solver.cages.push(cage);
+        }
+        for (idx, &n) in cells.iter().enumerate() {
+            if n == 0 {
+                continue;
+            }
+            if solver.candidates(idx) & (1 << (n - 1)) == 0 {
+                return None;
+            }
+            solver.place(idx, n);
+        }
+        Some(solver)
+    }
+
+    pub(crate) fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    /// Finds the next solution, returning false once there are none left.
+    pub(crate) fn next_solution(&mut self) -> bool {
+        self.search(None)
+    }
+
+    /// Same as `next_solution`, but the numbers for each square are tried in a random order.
+    pub(crate) fn next_random_solution<R: Rng>(&mut self, rng: &mut R) -> bool {
+        self.search(Some(rng))
+    }
+
+    /// Same as `next_random_solution`, but gives up and returns None once the search has placed
+    /// `max_guesses` numbers without finishing.
+    pub(crate) fn next_random_solution_bounded<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        max_guesses: usize,
+    ) -> Option<bool> {
+        self.max_guesses = max_guesses;
+        let found = self.next_random_solution(rng);
+        (found || self.guesses < max_guesses).then_some(found)
+    }
+
+    fn search(&mut self, mut rng: Option<&mut dyn RngCore>) -> bool {
+        if self.done {
+            return false;
+        }
+        // Move on from the previous solution
+        if self.started && !self.advance(rng.as_deref_mut()) {
+            self.done = true;
+            return false;
+        }
+        self.started = true;
+        loop {
+            let Some((idx, remaining)) = self.most_constrained() else {
+                return true;
+            };
+            if remaining != 0 {
+                self.stack.push(Frame { idx, remaining });
+            }
+            if !self.advance(rng.as_deref_mut()) {
+                self.done = true;
+                return false;
+            }
+        }
+    }
+
+    /// Counts the solutions, stopping once `limit` have been found.
+    pub(crate) fn count(&mut self, limit: usize) -> usize {
+        let mut count = 0;
+        while count < limit && self.next_solution() {
+            count += 1;
+        }
+        count
+    }
+
+    /// Same as `count`, but gives up and returns None once the search has placed `max_guesses`
+    /// numbers without finishing.
+    pub(crate) fn count_bounded(&mut self, limit: usize, max_guesses: usize) -> Option<usize> {
+        self.max_guesses = max_guesses;
+        let count = self.count(limit);
+        (self.guesses < max_guesses || count == limit).then_some(count)
+    }
+
+    // Places the next untried number of the most recent square that has one, undoing squares that
+    // have run out. Returns false if the search is exhausted.
+    fn advance(&mut self, mut rng: Option<&mut (dyn RngCore + '_)>) -> bool {
+        while let Some(frame) = self.stack.last_mut() {
+            let Frame { idx, remaining } = *frame;
+            if remaining == 0 {
+                self.stack.pop();
+                self.unplace(idx);
+                continue;
+            }
+            if self.guesses == self.max_guesses {
+                return false;
+            }
+            self.guesses += 1;
+            let bit = match rng.as_deref_mut() {
+                Some(rng) => nth_bit(remaining, rng.gen_range(0..remaining.count_ones())),
+                None => remaining & remaining.wrapping_neg(),
+            };
+            frame.remaining &= !bit;
+            self.unplace(idx);
+            self.place(idx, bit.trailing_zeros() as u8 + 1);
+            return true;
+        }
+        false
+    }
+
+    // Returns the empty square to branch on along with the numbers to try in it, or None if the
+    // board is filled. A mask of 0 means the board can't be solved as it is.
+    fn most_constrained(&self) -> Option<(usize, u32)> {
+        let mut best: Option<(usize, u32)> = None;
+        let mut best_count = u32::MAX;
+        for idx in 0..self.cells.len() {
+            if self.cells[idx] != 0 {
+                continue;
+            }
+            let cands = self.candidates(idx);
+            let count = cands.count_ones();
+            if count <= 1 {
+                return Some((idx, cands));
+            }
+            if count < best_count {
+                best = Some((idx, cands));
+                best_count = count;
+            }
+        }
+        best?;
+        // Look for a number that only fits in one square of a region
+        for (region, &mask) in self.regions.iter().zip(&self.masks) {
+            let (mut once, mut twice) = (0u32, 0u32);
+            for &idx in region {
+                if self.cells[idx] == 0 {
+                    let cands = self.candidates(idx);
+                    twice |= once & cands;
+                    once |= cands;
+                }
+            }
+            let missing = self.full & !mask;
+            if missing & !once != 0 {
+                // A number has nowhere to go
+                return Some((region[0], 0));
+            }
+            let singles = once & !twice;
+            if singles != 0 {
+                let bit = singles & singles.wrapping_neg();
+                let idx = region
+                    .iter()
+                    .copied()
+                    .find(|&idx| self.cells[idx] == 0 && self.candidates(idx) & bit != 0)
+                    .expect("missing hidden single square");
+                return Some((idx, bit));
+            }
+        }
+        best
+    }
+
+    #[inline(always)]
+    fn regions_of(&self, idx: usize) -> &[usize] {
+        &self.region_of[self.region_start[idx]..self.region_start[idx + 1]]
+    }
+
+    fn candidates(&self, idx: usize) -> u32 {
+        let used = self
+            .regions_of(idx)
+            .iter()
+            .fold(0, |used, &region| used | self.masks[region]);
+        let mut cands = self.full & !used;
+        if let Some(cage) = self.cages.get(self.cage_of[idx]) {
+            cands &= cage.cands;
+        }
+        for &c in &self.constraint_of[self.constraint_start[idx]..self.constraint_start[idx + 1]] {
+            let (idxs, constraint) = &self.constraints[c];
+            let mut nums = idxs.iter().map(|&i| self.cells[i]).collect::<Vec<_>>();
+            let at = idxs
+                .iter()
+                .position(|&i| i == idx)
+                .expect("square not in constraint");
+            let mut bits = cands;
+            while bits != 0 {
+                let bit = bits & bits.wrapping_neg();
+                bits &= bits - 1;
+                nums[at] = bit.trailing_zeros() as u8 + 1;
+                if !constraint.fits(&nums, self.full.count_ones() as u8) {
+                    cands &= !bit;
+                }
+            }
+        }
+        cands
+    }
+
+    fn place(&mut self, idx: usize, n: u8) {
+        let bit = 1 << (n - 1);
+        self.cells[idx] = n;
+        for i in self.region_start[idx]..self.region_start[idx + 1] {
+            self.masks[self.region_of[i]] |= bit;
+        }
+        if let Some(cage) = self.cages.get_mut(self.cage_of[idx]) {
+            cage.used |= bit;
+            cage.total += n as u32;
+            cage.filled += 1;
+            cage.update(self.full);
+        }
+    }
+
+    fn unplace(&mut self, idx: usize) {
+        let n = self.cells[idx];
+        if n == 0 {
+            return;
+        }
+        let bit = !(1 << (n - 1));
+        self.cells[idx] = 0;
+        for i in self.region_start[idx]..self.region_start[idx + 1] {
+            self.masks[self.region_of[i]] &= bit;
+        }
+        if let Some(cage) = self.cages.get_mut(self.cage_of[idx]) {
+            cage.used &= bit;
+            cage.total -= n as u32;
+            cage.filled -= 1;
+            cage.update(self.full);
+        }
+    }
+}
+
+// For each square, the indexes of the groups it's in, flattened: the groups of square `idx` are
+// `of[start[idx]..start[idx + 1]]`.
+fn index_lists(cells: usize, groups: &[Vec<usize>]) -> (Vec<usize>, Vec<usize>) {
+    let mut lists = vec![Vec::new(); cells];
+    for (i, group) in groups.iter().enumerate() {
+        for &idx in group {
+            lists[idx].push(i);
+        }
+    }
+    let mut start = vec![0];
+    for list in &lists {
+        start.push(start.last().unwrap() + list.len());
+    }
+    (start, lists.concat())
+}
+
+/// The rows, columns, and boxes of a board of the given rank, each as its squares' indexes in
+/// row-major order.
+pub(crate) fn classic_regions(rank: usize) -> Vec<Vec<usize>> {
+    let size = rank * rank;
+    let rows = (0..size).map(|y| (0..size).map(|x| y * size + x).collect());
+    let cols = (0..size).map(|x| (0..size).map(|y| y * size + x).collect());
+    let boxes = (0..size).map(|b| {
+        let (bx, by) = (b % rank * rank, b / rank * rank);
+        (0..size)
+            .map(|i| (by + i / rank) * size + bx + i % rank)
+            .collect()
+    });
+    rows.chain(cols).chain(boxes).collect()
+}
+
+// The numbers from the mask (bit `n - 1` for `n`) that are part of some set of `count` different
+// ones from it adding up to `sum`.
+fn combinations(mask: u32, count: usize, sum: u32) -> u32 {
+    if count == 0 || count > mask.count_ones() as usize {
+        return 0;
+    }
+    // No set can be made if even the smallest or biggest numbers miss the sum
+    let (mut low, mut high, mut bits) = (0, 0, mask);
+    for _ in 0..count {
+        low += bits.trailing_zeros() + 1;
+        bits &= bits - 1;
+    }
+    bits = mask;
+    for _ in 0..count {
+        let top = 31 - bits.leading_zeros();
+        high += top + 1;
+        bits &= !(1 << top);
+    }
+    if sum < low || sum > high {
+        return 0;
+    }
+    // Tries each number as the biggest of the set, then finds the rest from those below it
+    let top = 31 - mask.leading_zeros();
+    let (n, below) = (top + 1, mask & !(1 << top));
+    let mut found = combinations(below, count, sum);
+    if n == sum && count == 1 {
+        found |= 1 << top;
+    } else if n < sum && count > 1 {
+        let rest = combinations(below, count - 1, sum - n);
+        if rest != 0 {
+            found |= rest | 1 << top;
+        }
+    }
+    found
+}
+
+// Returns the nth (0-indexed) set bit of the mask.
+fn nth_bit(mut mask: u32, n: u32) -> u32 {
+    for _ in 0..n {
+        mask &= mask - 1;
+    }
+    mask & mask.wrapping_neg()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solve_count() {
+        // "AI Escargot"
+        let puzzle =
+            "1....7.9..3..2...8..96..5....53..9...1..8...26....4...3......1..4......7..7...3..";
+        let cells = puzzle
+            .bytes()
+            .map(|b| if b == b'.' { 0 } else { b - b'0' })
+            .collect::<Vec<_>>();
+        let mut solver = Solver::new(3, &cells).expect("conflicting puzzle");
+        assert!(solver.next_solution());
+        let solution = solver.cells().to_vec();
+        for (c, s) in cells.iter().zip(&solution) {
+            assert!(*c == 0 || c == s);
+        }
+        assert!(!solver.next_solution());
+        assert_eq!(Solver::new(3, &cells).unwrap().count(10), 1);
+
+        assert_eq!(Solver::new(2, &[0; 16]).unwrap().count(usize::MAX), 288);
+        let mut solver = Solver::new(2, &[0; 16]).unwrap();
+        assert_eq!(solver.count_bounded(usize::MAX, 10), None);
+        let mut solver = Solver::new(2, &[0; 16]).unwrap();
+        assert_eq!(solver.count_bounded(usize::MAX, 10_000), Some(288));
+
+        let mut conflict = cells.clone();
+        conflict[1] = 1;
+        assert!(Solver::new(3, &conflict).is_none());
+    }
+
+    #[test]
+    fn random_fill() {
+        let mut rng = rand::thread_rng();
+        for rank in 2..=5 {
+            let size = rank * rank;
+            let mut solver = Solver::new(rank, &vec![0; size * size]).unwrap();
+            assert!(solver.next_random_solution(&mut rng));
+            assert!(solver.cells().iter().all(|&n| n != 0));
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::Pos;
+use std::fmt;
+
+/// The pattern the givens of a generated puzzle keep, like in most published puzzles. Numbers are
+/// removed a whole [`group`](Self::group) at a time, so the empty squares keep it too.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum Symmetry {
+    /// Squares are removed one at a time, anywhere.
+    #[default]
+    None,
+    /// The same after turning the board half way around.
+    Rotational180,
+    /// The same after turning the board a quarter of the way around.
+    Rotational90,
+    /// The top half mirrors the bottom half.
+    Horizontal,
+    /// The left half mirrors the right half.
+    Vertical,
+    /// Mirrored across the diagonal from the top left to the bottom right.
+    Diagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 6] = [
+        Symmetry::None,
+        Symmetry::Rotational180,
+        Symmetry::Rotational90,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Diagonal,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Symmetry::None => "None",
+            Symmetry::Rotational180 => "Rotational (180°)",
+            Symmetry::Rotational90 => "Rotational (90°)",
+            Symmetry::Horizontal => "Horizontal Mirror",
+            Symmetry::Vertical => "Vertical Mirror",
+            Symmetry::Diagonal => "Diagonal",
+        }
+    }
+
+    /// The squares on a board `size` squares across that have to be filled or empty along with
+    /// `pos` (including `pos` itself, which is first), each once.
+    pub fn group(self, pos: Pos, size: usize) -> Vec<Pos> {
+        let (x, y) = pos;
+        let last = size - 1;
+        let mut group = vec![pos];
+        match self {
+            Symmetry::None => {}
+            Symmetry::Rotational180 => group.push((last - x, last - y)),
+            Symmetry::Rotational90 => {
+                group.extend([(last - y, x), (last - x, last - y), (y, last - x)])
+            }
+            Symmetry::Horizontal => group.push((x, last - y)),
+            Symmetry::Vertical => group.push((last - x, y)),
+            Symmetry::Diagonal => group.push((y, x)),
+        }
+        // Squares on the axis or in the center map to themselves
+        let mut seen = Vec::with_capacity(group.len());
+        group.retain(|&pos| {
+            let new = !seen.contains(&pos);
+            seen.push(pos);
+            new
+        });
+        group
+    }
+
+    /// Returns true if the empty and filled squares of a board with `nums` (in row-major order)
+    /// follow this pattern.
+    pub fn matches(self, nums: &[u8], size: usize) -> bool {
+        (0..nums.len()).all(|i| {
+            let filled = nums[i] != 0;
+            self.group((i % size, i / size), size)
+                .into_iter()
+                .all(|(x, y)| (nums[y * size + x] != 0) == filled)
+        })
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups() {
+        assert_eq!(Symmetry::None.group((1, 2), 9), [(1, 2)]);
+        assert_eq!(Symmetry::Rotational180.group((1, 2), 9), [(1, 2), (7, 6)]);
+        assert_eq!(
+            Symmetry::Rotational90.group((1, 2), 9),
+            [(1, 2), (6, 1), (7, 6), (2, 7)]
+        );
+        assert_eq!(Symmetry::Horizontal.group((1, 2), 9), [(1, 2), (1, 6)]);
+        assert_eq!(Symmetry::Vertical.group((1, 2), 16), [(1, 2), (14, 2)]);
+        assert_eq!(Symmetry::Diagonal.group((1, 2), 16), [(1, 2), (2, 1)]);
+        for symmetry in Symmetry::ALL {
+            assert_eq!(symmetry.group((4, 4), 9), [(4, 4)]);
+        }
+        assert_eq!(Symmetry::Diagonal.group((3, 3), 16), [(3, 3)]);
+        assert_eq!(Symmetry::Horizontal.group((0, 4), 9), [(0, 4)]);
+    }
+}
@@ -0,0 +1,489 @@
+use crate::{grid::two_distinct, SudkuError, SudkuResult, SudokuGrid};
+use rand::Rng;
+use std::cmp::Ordering;
+
+/// A change to a board that keeps a valid board valid, and a puzzle's solution unique. Rows and
+/// columns are numbered from 0, as are bands (rows of boxes) and stacks (columns of boxes), while
+/// numbers start at 1.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Transform {
+    /// Swaps two rows in the same band.
+    SwapRows(usize, usize),
+    /// Swaps two columns in the same stack.
+    SwapCols(usize, usize),
+    SwapBands(usize, usize),
+    SwapStacks(usize, usize),
+    /// Reflects on the diagonal from the top left to the bottom right.
+    Transpose,
+    /// Reflects on the diagonal from the top right to the bottom left.
+    AntiTranspose,
+    /// Rotates clockwise.
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Flips top to bottom.
+    ReflectX,
+    /// Flips left to right.
+    ReflectY,
+    /// Swaps every occurrence of the two numbers, including in notes.
+    SwapNums(u8, u8),
+}
+
+impl Transform {
+    /// Panics if the transform doesn't fit the board, see [`try_apply`](Self::try_apply).
+    pub fn apply<G: SudokuGrid>(self, grid: &mut G) {
+        self.try_apply(grid).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Applies the transform, failing without changing the board if it doesn't fit it: rows or
+    /// columns off the board or in different bands or stacks, bands or stacks off the board, or
+    /// numbers above its size.
+    pub fn try_apply<G: SudokuGrid>(self, grid: &mut G) -> SudkuResult<()> {
+        self.check(G::RANK)?;
+        match self {
+            Transform::SwapRows(a, b) => grid.swap_rows(a, b),
+            Transform::SwapCols(a, b) => grid.swap_cols(a, b),
+            Transform::SwapBands(a, b) => grid.swap_bands(a, b),
+            Transform::SwapStacks(a, b) => grid.swap_stacks(a, b),
+            Transform::Transpose => grid.transpose(),
+            Transform::AntiTranspose => grid.anti_transpose(),
+            Transform::Rotate90 => grid.rotate_90(),
+            Transform::Rotate180 => grid.rotate_180(),
+            Transform::Rotate270 => grid.rotate_270(),
+            Transform::ReflectX => grid.reflect_x(),
+            Transform::ReflectY => grid.reflect_y(),
+            Transform::SwapNums(a, b) => grid.swap_nums(a, b),
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the transform doesn't fit a board of the rank.
+    pub fn check(self, rank: usize) -> SudkuResult<()> {
+        let size = rank * rank;
+        let fits = match self {
+            Transform::SwapRows(a, b) | Transform::SwapCols(a, b) => {
+                a < size && b < size && a / rank == b / rank
+            }
+            Transform::SwapBands(a, b) | Transform::SwapStacks(a, b) => a < rank && b < rank,
+            Transform::SwapNums(a, b) => {
+                (1..=size as u8).contains(&a) && (1..=size as u8).contains(&b)
+            }
+            _ => true,
+        };
+        if fits {
+            Ok(())
+        } else {
+            Err(SudkuError::InvalidTransform(self))
+        }
+    }
+
+    /// The transform that undoes this one.
+    pub fn inverse(self) -> Self {
+        match self {
+            Transform::Rotate90 => Transform::Rotate270,
+            Transform::Rotate270 => Transform::Rotate90,
+            t => t,
+        }
+    }
+}
+
+/// Transforms applied one after the other, built up with [`then`](Self::then).
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Transforms(Vec<Transform>);
+
+impl Transforms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a transform to apply after the others.
+    pub fn then(mut self, transform: Transform) -> Self {
+        self.0.push(transform);
+        self
+    }
+
+    pub fn push(&mut self, transform: Transform) {
+        self.0.push(transform);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Transform> + '_ {
+        self.0.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The transforms that undo these ones.
+    pub fn inverse(&self) -> Self {
+        self.0.iter().rev().map(|t| t.inverse()).collect()
+    }
+
+    /// Panics if any of the transforms doesn't fit the board, see [`try_apply`](Self::try_apply).
+    pub fn apply<G: SudokuGrid>(&self, grid: &mut G) {
+        self.try_apply(grid).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Applies every transform in order, failing without changing the board if any of them
+    /// doesn't fit it.
+    pub fn try_apply<G: SudokuGrid>(&self, grid: &mut G) -> SudkuResult<()> {
+        for t in &self.0 {
+            t.check(G::RANK)?;
+        }
+        for t in &self.0 {
+            t.try_apply(grid)?;
+        }
+        Ok(())
+    }
+
+    /// A random shuffle for a board of the rank: a few hundred swaps of rows and columns within
+    /// their bands, of whole bands and stacks, rotations and reflections, then as many swaps of
+    /// numbers. This is what [`SudokuGrid::randomize`] applies.
+    pub fn random(rank: usize) -> Self {
+        Self::random_with_rng(rank, &mut rand::thread_rng())
+    }
+
+    pub fn random_with_rng(rank: usize, rng: &mut impl Rng) -> Self {
+        let num_transforms = rng.gen_range(100..=1000);
+        let mut transforms = Self(Vec::with_capacity(num_transforms * 2));
+        for _ in 0..num_transforms {
+            transforms.push(match rng.gen_range(0..11) {
+                t @ 0..=1 => {
+                    let band = rng.gen_range(0..rank as u32) as usize * rank;
+                    let (a, b) = two_distinct(rng, rank);
+                    if t == 0 {
+                        Transform::SwapRows(band + a, band + b)
+                    } else {
+                        Transform::SwapCols(band + a, band + b)
+                    }
+                }
+                t @ 2..=3 => {
+                    let (a, b) = two_distinct(rng, rank);
+                    if t == 2 {
+                        Transform::SwapBands(a, b)
+                    } else {
+                        Transform::SwapStacks(a, b)
+                    }
+                }
+                4 => Transform::Transpose,
+                5 => Transform::AntiTranspose,
+                6 => Transform::Rotate90,
+                7 => Transform::Rotate180,
+                8 => Transform::Rotate270,
+                9 => Transform::ReflectX,
+                10 => Transform::ReflectY,
+                _ => unreachable!(),
+            });
+        }
+        for _ in 0..num_transforms {
+            let (a, b) = two_distinct(rng, rank * rank);
+            transforms.push(Transform::SwapNums(a as u8 + 1, b as u8 + 1));
+        }
+        transforms
+    }
+}
+
+impl FromIterator<Transform> for Transforms {
+    fn from_iter<I: IntoIterator<Item = Transform>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl Extend<Transform> for Transforms {
+    fn extend<I: IntoIterator<Item = Transform>>(&mut self, iter: I) {
+        self.0.extend(iter)
+    }
+}
+
+/// Finds the representative of every board the numbers (row-major, 0 for empty squares) can be
+/// transformed into, see [`SudokuGrid::canonical`].
+pub(crate) fn canonical_nums(nums: &[u8], rank: usize) -> Vec<u8> {
+    let size = rank * rank;
+    let mut search = Search {
+        rank,
+        cells: vec![0; nums.len()],
+        best: None,
+    };
+    for transposed in [false, true] {
+        for (i, cell) in search.cells.iter_mut().enumerate() {
+            let (x, y) = (i % size, i / size);
+            *cell = if transposed {
+                nums[x * size + y]
+            } else {
+                nums[y * size + x]
+            };
+        }
+        // The order of the columns within each stack is worked out as the rows are chosen, but
+        // the order of the stacks is tried every way.
+        for stacks in permutations(rank) {
+            let groups = stacks
+                .iter()
+                .map(|s| (s * rank..(s + 1) * rank).collect())
+                .collect::<Vec<_>>();
+            search.rows(
+                &mut Vec::new(),
+                &groups,
+                &mut vec![0; size + 1],
+                1,
+                &mut Vec::new(),
+            );
+        }
+    }
+    search.best.expect("no order searched")
+}
+
+// The search for the smallest board. Rows are added one at a time, trying each row that can come
+// next and only keeping on with those that come out smallest. Numbers are relabeled in the order
+// they first appear, and the columns are kept in groups that are in order with each other but
+// could still go in any order within the group, which get split up as rows tell them apart.
+struct Search {
+    rank: usize,
+    // The numbers, row-major
+    cells: Vec<u8>,
+    best: Option<Vec<u8>>,
+}
+
+impl Search {
+    // `labels` maps the old numbers to their new ones (0 for those not seen yet), and `next` is
+    // the next unused label.
+    fn rows(
+        &mut self,
+        order: &mut Vec<usize>,
+        groups: &[Vec<usize>],
+        labels: &mut [u8],
+        next: u8,
+        out: &mut Vec<u8>,
+    ) {
+        let size = self.rank * self.rank;
+        if order.len() == size {
+            if self.best.as_ref().is_none_or(|best| *out < *best) {
+                self.best = Some(out.clone());
+            }
+            return;
+        }
+        // A row starting a new band can be from any unused band, otherwise it has to be from the
+        // band already started. Within a band, rows that are the same can be swapped without
+        // changing anything, so only one of them is tried.
+        let candidates = if order.len().is_multiple_of(self.rank) {
+            (0..size)
+                .filter(|&y| order.iter().all(|&o| o / self.rank != y / self.rank))
+                .collect::<Vec<_>>()
+        } else {
+            let band = order[order.len() - 1] / self.rank;
+            let mut rows = Vec::<usize>::new();
+            for y in band * self.rank..(band + 1) * self.rank {
+                if !order.contains(&y) && rows.iter().all(|&r| self.row(r) != self.row(y)) {
+                    rows.push(y);
+                }
+            }
+            rows
+        };
+        let mut smallest: Option<Vec<u8>> = None;
+        let mut ties = Vec::new();
+        for y in candidates {
+            let row = self.relabeled(y, groups, labels, next);
+            match smallest.as_ref().map(|s| row.cmp(s)) {
+                Some(Ordering::Greater) => {}
+                Some(Ordering::Equal) => ties.push(y),
+                _ => {
+                    smallest = Some(row);
+                    ties = vec![y];
+                }
+            }
+        }
+        let smallest = smallest.expect("no rows left");
+        if let Some(best) = &self.best {
+            // Nothing from here can beat the best board found so far
+            if out.iter().chain(&smallest).gt(&best[..out.len() + size]) {
+                return;
+            }
+        }
+        out.extend_from_slice(&smallest);
+        for y in ties {
+            order.push(y);
+            self.split(y, groups, 0, &mut Vec::new(), labels, next, order, out);
+            order.pop();
+        }
+        out.truncate(out.len() - size);
+    }
+
+    fn row(&self, y: usize) -> &[u8] {
+        let size = self.rank * self.rank;
+        &self.cells[y * size..(y + 1) * size]
+    }
+
+    // The row as it comes out with the columns of each group put in their best order: empty
+    // squares first, then numbers already seen by their labels, then new ones.
+    fn relabeled(&self, y: usize, groups: &[Vec<usize>], labels: &[u8], mut next: u8) -> Vec<u8> {
+        let row = self.row(y);
+        let mut out = Vec::with_capacity(row.len());
+        for group in groups {
+            let start = out.len();
+            let mut new = 0;
+            for &x in group {
+                match row[x] {
+                    0 => out.push(0),
+                    n if labels[n as usize] != 0 => out.push(labels[n as usize]),
+                    _ => new += 1,
+                }
+            }
+            out[start..].sort_unstable();
+            out.extend(next..next + new);
+            next += new;
+        }
+        out
+    }
+
+    // Splits the groups from the `i`th on by the numbers in row `y`, the way
+    // [`relabeled`](Self::relabeled) orders them, then carries on with the next row. New numbers
+    // in the same group come out the same whichever goes first, so each order is tried.
+    #[allow(clippy::too_many_arguments)]
+    fn split(
+        &mut self,
+        y: usize,
+        groups: &[Vec<usize>],
+        i: usize,
+        split: &mut Vec<Vec<usize>>,
+        labels: &mut [u8],
+        next: u8,
+        order: &mut Vec<usize>,
+        out: &mut Vec<u8>,
+    ) {
+        let Some(group) = groups.get(i) else {
+            self.rows(order, split, labels, next, out);
+            return;
+        };
+        let row = self.row(y).to_vec();
+        let n = |x: usize| row[x];
+        let start = split.len();
+        let empty = group
+            .iter()
+            .copied()
+            .filter(|&x| n(x) == 0)
+            .collect::<Vec<_>>();
+        if !empty.is_empty() {
+            split.push(empty);
+        }
+        let mut seen = group
+            .iter()
+            .copied()
+            .filter(|&x| n(x) != 0 && labels[n(x) as usize] != 0)
+            .collect::<Vec<_>>();
+        seen.sort_unstable_by_key(|&x| labels[n(x) as usize]);
+        split.extend(seen.into_iter().map(|x| vec![x]));
+        let new = group
+            .iter()
+            .copied()
+            .filter(|&x| n(x) != 0 && labels[n(x) as usize] == 0)
+            .collect::<Vec<_>>();
+        for perm in permutations(new.len()) {
+            let before = split.len();
+            for (label, &p) in (next..).zip(&perm) {
+                labels[n(new[p]) as usize] = label;
+                split.push(vec![new[p]]);
+            }
+            let after = next + new.len() as u8;
+            self.split(y, groups, i + 1, split, labels, after, order, out);
+            for &x in &new {
+                labels[n(x) as usize] = 0;
+            }
+            split.truncate(before);
+        }
+        split.truncate(start);
+    }
+}
+
+// Every order of 0..n.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+    let mut perms = Vec::new();
+    for perm in permutations(n - 1) {
+        for i in 0..n {
+            let mut perm = perm.clone();
+            perm.insert(i, n - 1);
+            perms.push(perm);
+        }
+    }
+    perms
+}
+
+/// A 64 bit FNV-1a hash of the bytes, which unlike the standard library's hashers is guaranteed
+/// to stay the same between versions and platforms.
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Difficulty, Grid2x2, Grid3x3, Grid4x4};
+
+    #[test]
+    fn transforms() {
+        let (grid, _) = Grid3x3::generate_with_solution(Difficulty::Easy);
+        let transforms = Transforms::new()
+            .then(Transform::SwapRows(3, 5))
+            .then(Transform::Rotate90)
+            .then(Transform::SwapStacks(0, 2))
+            .then(Transform::SwapNums(1, 9));
+        let mut moved = grid.clone();
+        transforms.apply(&mut moved);
+        assert!(moved != grid);
+        transforms.inverse().apply(&mut moved);
+        assert!(moved == grid);
+
+        let random = Transforms::random(3);
+        random.apply(&mut moved);
+        random.inverse().apply(&mut moved);
+        assert!(moved == grid);
+
+        for bad in [
+            Transform::SwapRows(2, 3),
+            Transform::SwapCols(0, 9),
+            Transform::SwapBands(0, 3),
+            Transform::SwapNums(0, 1),
+        ] {
+            let transforms = Transforms::new().then(Transform::Transpose).then(bad);
+            assert_eq!(
+                transforms.try_apply(&mut moved),
+                Err(SudkuError::InvalidTransform(bad))
+            );
+            assert!(moved == grid);
+        }
+    }
+
+    #[test]
+    fn canonical() {
+        let (grid, _) = Grid3x3::generate_with_solution(Difficulty::Medium);
+        let canonical = grid.canonical();
+        assert!(canonical.canonical() == canonical);
+        let mut moved = grid.clone();
+        moved.randomize();
+        assert!(moved.canonical() == canonical);
+        assert!(moved.is_equivalent(&grid));
+        assert_eq!(moved.canonical_hash(), grid.canonical_hash());
+
+        let (other, _) = Grid3x3::generate_with_solution(Difficulty::Medium);
+        assert!(!other.is_equivalent(&grid));
+
+        let mut grid = Grid4x4::generate();
+        grid.remove_nums(150);
+        let mut moved = grid.clone();
+        moved.randomize();
+        assert!(moved.is_equivalent(&grid));
+
+        // The numbers are in different bands and stacks, which no transform changes, so the first
+        // can't be put any later than the end of the second row
+        let grid = Grid2x2::from_line("....3..........1").unwrap();
+        assert_eq!(grid.canonical().to_line().trim_end(), ".......1.....2..");
+        assert_eq!(stable_hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
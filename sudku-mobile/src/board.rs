@@ -0,0 +1,629 @@
+#![allow(non_snake_case)]
+
+use crate::*;
+use sudku_grid::{
+    Dot, GridNum, Hint, Move, MultiHistory as History, Num3x3, Pos, Rules, Samurai, SudokuGrid,
+};
+
+#[component]
+pub fn Board<G>(
+    grid: RwSignal<G>, counts: RwSignal<Counts>, history: RwSignal<History<G::Num>>,
+    hint: RwSignal<Option<Hint>>, solution: RwSignal<Option<G>>, mistakes: RwSignal<u32>,
+    mark_mistakes: Signal<bool>, rules: RwSignal<Rules>,
+) -> impl IntoView
+where
+    G: SudokuGrid + 'static,
+{
+    let notes_active = use_context::<RwSignal<bool>>().expect("missing notes_active context");
+    let focused_cell = use_context::<RwSignal<CellInfo>>().expect("missing focused_cell context");
+
+    let (completed, set_completed) = create_signal(false);
+    create_effect(move |_| {
+        if rules.with(|rules| grid.with(|grid| rules.is_valid(grid))).is_none() {
+            focused_cell.set(CellInfo::default());
+            set_completed(true);
+        } else {
+            set_completed(false);
+        }
+    });
+
+    let rank = G::RANK;
+    // The number buttons are split into rows of at most 9
+    let rows = G::SIZE.div_ceil(9);
+    let per_row = G::SIZE.div_ceil(rows);
+
+    view! {
+        <div
+            id=format!("grid{rank}")
+            class:jigsaw=move || rules.with(|rules| rules.boxes.is_some())
+            style:background-color=move || if completed() { "#4fff55" } else { "" }
+        >
+        {
+            move || {
+                (0..G::SIZE)
+                    .map(|i| view! {
+                        <BoardBox
+                            grid=grid counts=counts history=history hint=hint
+                            solution=solution mistakes=mistakes mark_mistakes=mark_mistakes
+                            rules=rules start=(i % rank * rank, i / rank * rank)
+                        />
+                    })
+                    .collect_view()
+            }
+        }
+        <ConstraintOverlay rules=rules size=G::SIZE/>
+        </div>
+        <div id=format!("numbers{rank}-div") class="numbers-div">
+            {
+                (0..rows).map(|row| {
+                    view! {
+                        <div>
+                        {
+                            (row * per_row + 1..=G::SIZE.min((row + 1) * per_row)).map(|n| {
+                                let n = n as u8;
+                                view! {
+                                    <div
+                                        //style:color=move || if notes_active.get() { "gray" } else { "blue" }
+                                        style:color=move || {
+                                            if notes_active.get() {
+                                                let cell_info = focused_cell.get();
+                                                if cell_info.node.get().is_none() {
+                                                    return "gray";
+                                                };
+                                                if grid.with(|grid| grid[cell_info.pos].has_note(n))
+                                                    .unwrap_or(false) {
+                                                    //"blue"
+                                                    "aqua"
+                                                } else {
+                                                    "gray"
+                                                }
+                                            } else {
+                                                if counts.with(|counts| counts[n as usize - 1] < G::SIZE) {
+                                                    "blue"
+                                                } else {
+                                                    "gray"
+                                                }
+                                            }
+                                        }
+                                        on:click=move |_| {
+                                            let cell_info = focused_cell.get();
+                                            if grid.with(|grid| grid[cell_info.pos].is_given()) {
+                                                return;
+                                            }
+                                            let Some(cell) = cell_info.node.get() else {
+                                                // TODO?
+                                                return;
+                                            };
+                                            grid.update(|grid| {
+                                                let num = if notes_active.get() {
+                                                    grid[cell_info.pos].with_toggle_note(n)
+                                                } else if grid[cell_info.pos].num_or_zero() == n {
+                                                    G::Num::new(0)
+                                                } else if !rules.with(|rules| {
+                                                    rules.pos_is_valid(grid, cell_info.pos, n)
+                                                }) {
+                                                    return;
+                                                } else {
+                                                    G::Num::new(n)
+                                                };
+                                                counts.update(|counts| {
+                                                    if let Some(mvs) = place_num_counted(
+                                                        rules, grid, counts, cell_info.pos, num,
+                                                    ) {
+                                                        count_mistake(
+                                                            solution, mistakes, cell_info.pos, num,
+                                                        );
+                                                        history.update(|hist| hist.update(mvs));
+                                                    }
+                                                });
+                                            });
+                                            cell.focus().expect("error focusing cell");
+                                        }
+                                        >
+                                        {num_to_str(n)}
+                                        </div>
+                                }
+                            }).collect_view()
+                        }
+                        </div>
+                    }
+                }).collect_view()
+            }
+        </div>
+    }
+}
+
+// Thermometers, arrows and Kropki dots are drawn over the board in an SVG measured in squares
+#[component]
+fn ConstraintOverlay(rules: RwSignal<Rules>, size: usize) -> impl IntoView {
+    let center = |(x, y): Pos| (x as f32 + 0.5, y as f32 + 0.5);
+    let unit = |(dx, dy): (f32, f32)| (dx / dx.hypot(dy), dy / dx.hypot(dy));
+    let points = |points: &[(f32, f32)]| {
+        points.iter().map(|(x, y)| format!("{x},{y}")).collect::<Vec<_>>().join(" ")
+    };
+    let overlay = move || rules.with(|rules| {
+        let thermos = rules.thermos.iter().map(|thermo| {
+            let (bx, by) = center(thermo.cells[0]);
+            let line = thermo.cells.iter().copied().map(center).collect::<Vec<_>>();
+            view! {
+                <g class="thermo">
+                    <circle cx=bx.to_string() cy=by.to_string() r="0.3"/>
+                    <polyline points=points(&line)/>
+                </g>
+            }
+        }).collect_view();
+        let arrows = rules.arrows.iter().map(|arrow| {
+            // The line starts at the edge of the circle, and ends in a head
+            let (cx, cy) = center(arrow.circle());
+            let mut line = arrow.path().iter().copied().map(center).collect::<Vec<_>>();
+            let (dx, dy) = unit((line[0].0 - cx, line[0].1 - cy));
+            line.insert(0, (cx + 0.4 * dx, cy + 0.4 * dy));
+            let ((px, py), (lx, ly)) = (line[line.len() - 2], line[line.len() - 1]);
+            let (dx, dy) = unit((lx - px, ly - py));
+            let head = [
+                (lx - 0.25 * dx - 0.2 * dy, ly - 0.25 * dy + 0.2 * dx),
+                (lx, ly),
+                (lx - 0.25 * dx + 0.2 * dy, ly - 0.25 * dy - 0.2 * dx),
+            ];
+            view! {
+                <g class="arrow">
+                    <circle cx=cx.to_string() cy=cy.to_string() r="0.4"/>
+                    <polyline points=points(&line)/>
+                    <polyline points=points(&head)/>
+                </g>
+            }
+        }).collect_view();
+        let dots = rules.dots.iter().map(|dot| {
+            let [(ax, ay), (bx, by)] = dot.cells.map(center);
+            let class = match dot.dot {
+                Dot::White => "kropki kropki-white",
+                Dot::Black => "kropki kropki-black",
+            };
+            let (x, y) = ((ax + bx) / 2.0, (ay + by) / 2.0);
+            view! { <circle class=class cx=x.to_string() cy=y.to_string() r="0.13"/> }
+        }).collect_view();
+        view! { {thermos} {arrows} {dots} }
+    });
+    view! {
+        <svg class="overlay" viewBox=format!("0 0 {size} {size}")>{overlay}</svg>
+    }
+}
+
+#[component]
+fn BoardBox<G>(
+    grid: RwSignal<G>, counts: RwSignal<Counts>, history: RwSignal<History<G::Num>>,
+    hint: RwSignal<Option<Hint>>, solution: RwSignal<Option<G>>, mistakes: RwSignal<u32>,
+    mark_mistakes: Signal<bool>, rules: RwSignal<Rules>, start: Pos,
+) -> impl IntoView
+where
+    G: SudokuGrid + 'static,
+{
+    let (col, row) = start;
+    let rank = G::RANK;
+    view! {
+        <div class=format!("grid{rank}-box")>
+        {(0..G::SIZE)
+            .map(|i| view! {
+                <BoardCell
+                    grid=grid counts=counts history=history hint=hint
+                    solution=solution mistakes=mistakes mark_mistakes=mark_mistakes
+                    rules=rules pos=(col + (i % rank), row + (i / rank))
+                />
+            })
+            .collect_view()}
+        </div>
+    }
+}
+
+#[component]
+fn BoardCell<G>(
+    grid: RwSignal<G>, counts: RwSignal<Counts>, history: RwSignal<History<G::Num>>,
+    hint: RwSignal<Option<Hint>>, solution: RwSignal<Option<G>>, mistakes: RwSignal<u32>,
+    mark_mistakes: Signal<bool>, rules: RwSignal<Rules>, pos: Pos,
+) -> impl IntoView
+where
+    G: SudokuGrid + 'static,
+{
+    let num = grid.with_untracked(|grid| grid[pos]);
+    let given = num.is_given();
+
+    let focused_cell = use_context::<RwSignal<CellInfo>>().expect("missing focused_cell context");
+
+    let notes_active = use_context::<RwSignal<bool>>().expect("missing notes_active context");
+
+    let node_ref = create_node_ref();
+    let cell_info = CellInfo::new(node_ref, pos);
+
+    let display_cell = move || grid.with(|grid| {
+        if let Some(notes) = grid[pos].notes() {
+            notes.into_iter()
+                .enumerate()
+                .map(|(i, b)| view! {
+                    <div class=format!("grid{}-note-div", G::RANK)>
+                        {if b { num_to_str(i as u8 + 1) } else { " " }}
+                    </div>
+                })
+                .collect_view()
+        } else {
+            num_to_str(grid[pos].num_or_zero()).into_view()
+        }
+    });
+    // A Killer cage is outlined inside its squares, on the sides that aren't next to another
+    // square of the cage, and its sum is shown in its top left square
+    let display_cage = move || rules.with(|rules| {
+        let cage = rules.cage_at(pos)?;
+        let (x, y) = pos;
+        let mut class = String::from("cage");
+        for (side, next) in [
+            ("top", y.checked_sub(1).map(|y| (x, y))),
+            ("right", Some((x + 1, y))),
+            ("bottom", Some((x, y + 1))),
+            ("left", x.checked_sub(1).map(|x| (x, y))),
+        ] {
+            if !next.is_some_and(|next| cage.cells.contains(&next)) {
+                class.push_str(" cage-");
+                class.push_str(side);
+            }
+        }
+        let sum = (cage.label_pos() == pos)
+            .then(|| view! { <span class="cage-sum">{cage.sum}</span> });
+        Some(view! { <div class=class>{sum}</div> })
+    });
+    // The boxes of a Jigsaw layout can't be drawn as blocks of squares, so each square draws
+    // the sides it shares with another box, or the edge of the board
+    let display_box_edge = move || rules.with(|rules| {
+        rules.boxes.as_ref()?;
+        let (x, y) = pos;
+        let b = rules.box_of::<G>(pos);
+        let mut class = String::from("box-edge");
+        for (side, next) in [
+            ("top", y.checked_sub(1).map(|y| (x, y))),
+            ("right", (x + 1 < G::SIZE).then_some((x + 1, y))),
+            ("bottom", (y + 1 < G::SIZE).then_some((x, y + 1))),
+            ("left", x.checked_sub(1).map(|x| (x, y))),
+        ] {
+            if !next.is_some_and(|next| rules.box_of::<G>(next) == b) {
+                class.push_str(" box-");
+                class.push_str(side);
+            }
+        }
+        Some(view! { <div class=class></div> })
+    });
+    view! {
+    //<div tabindex={if !given { "0" } else { "" }}
+    <div tabindex="0"
+        node_ref=node_ref
+        class:secondary-focus=move|| {
+            let fc = focused_cell.get();
+            if fc.node.get().is_none() {
+                return false;
+            } else if fc.pos == cell_info.pos {
+                return given;
+            }
+            let (this_num, other_num) = grid.with(|grid| (grid[cell_info.pos], grid[fc.pos]));
+            let (tn, on) = (this_num.num_or_zero(), other_num.num_or_zero());
+            on != 0 && (tn == on || this_num.has_note(on).unwrap_or(false))
+        }
+        class=format!("grid{}-cell", G::RANK)
+        class:note-cell=move || grid.with(|grid| grid[pos].is_note())
+        // The squares making up the hint's pattern, and the ones it changes
+        class:hint-cell=move || hint.with(|hint| {
+            hint.as_ref().is_some_and(|hint| hint.step.cells.contains(&pos))
+        })
+        class:hint-target=move || hint.with(|hint| {
+            hint.as_ref().is_some_and(|hint| hint.targets().contains(&pos))
+        })
+        class:mistake=move || mark_mistakes.get() && solution.with(|solution| {
+            solution.as_ref().is_some_and(|solution| grid.with(|grid| {
+                let n = grid[pos].num_or_zero();
+                n != 0 && n != solution[pos].num_or_zero()
+            }))
+        })
+        class:focused-cell=move || {
+            let fc = focused_cell.get();
+            !given && fc.node.get().is_some() && fc.pos == cell_info.pos
+        }
+        on:click=move |_| {
+            node_ref.get().expect("missing node_ref").focus().expect("error focusing");
+        }
+        on:focusin=move |_| focused_cell.set(cell_info)
+        on:keydown=move |ev| {
+            if given {
+                return;
+            }
+            let mut key = ev.key();
+            let val = if key == "Backspace" || key == "Delete" {
+                0
+            } else if key == "Escape" || key == "Tab" {
+                return;
+            } else {
+                key.make_ascii_lowercase();
+                let val = match key.as_bytes() {
+                    &[c @ b'0'..=b'9'] => c - b'0',
+                    &[c @ b'a'..=b'z'] => c - b'a' + 10,
+                    _ => u8::MAX,
+                };
+                if val as usize > G::SIZE {
+                    ev.prevent_default();
+                    return;
+                }
+                val
+            };
+            grid.update(|grid| {
+                let num = if val == 0 || val == grid[pos].num_or_zero() {
+                    G::Num::new(0)
+                } else if notes_active.get() {
+                    grid[pos].with_toggle_note(val)
+                } else if !rules.with(|rules| rules.pos_is_valid(grid, pos, val)) {
+                    ev.prevent_default();
+                    return;
+                } else {
+                    G::Num::new(val)
+                };
+                counts.update(|counts| {
+                    if let Some(mvs) = place_num_counted(rules, grid, counts, cell_info.pos, num) {
+                        count_mistake(solution, mistakes, cell_info.pos, num);
+                        history.update(|hist| hist.update(mvs));
+                    }
+                });
+            });
+            ev.prevent_default();
+        }
+        class:given=given
+        class:cage-cell=move || rules.with(|rules| rules.cage_at(pos).is_some())
+        // The squares of extra regions, like the diagonals of Sudoku X, are shaded
+        class:region-cell=move || rules.with(|rules| rules.in_region(pos))
+        class:jigsaw-cell=move || rules.with(|rules| rules.boxes.is_some())
+    >
+        {display_box_edge}
+        {display_cage}
+        {display_cell}
+    </div>
+    }
+}
+
+// Places the number like `Rules::place_num`, keeping the counts up to date. Returns the moves
+// made, or None if the number can't go there.
+fn place_num_counted<G: SudokuGrid>(
+    rules: RwSignal<Rules>, grid: &mut G, counts: &mut Counts, pos: Pos, num: G::Num,
+) -> Option<Vec<Move<G::Num>>> {
+    let mvs = rules.with(|rules| rules.place_num(grid, pos, num))?;
+    let (old, new) = (mvs[0].old.num_or_zero() as usize, mvs[0].new.num_or_zero() as usize);
+    if old != 0 {
+        counts[old - 1] -= 1;
+    }
+    if new != 0 {
+        counts[new - 1] += 1;
+    }
+    Some(mvs)
+}
+
+// Counts the number as a mistake if it isn't the one in the solution. Notes are never mistakes.
+fn count_mistake<G: SudokuGrid>(
+    solution: RwSignal<Option<G>>, mistakes: RwSignal<u32>, pos: Pos, num: G::Num,
+) {
+    let n = num.num_or_zero();
+    let wrong = solution.with_untracked(|solution| {
+        solution.as_ref().is_some_and(|solution| solution[pos].num_or_zero() != n)
+    });
+    if n != 0 && wrong {
+        mistakes.update(|mistakes| *mistakes += 1);
+    }
+}
+
+/// A Samurai board, drawn as the whole 21x21 board with the squares between the corner boards
+/// left blank. Clicking the focused square zooms the board in or out, and the zoomed board can be
+/// scrolled.
+#[component]
+pub fn SamuraiBoard(
+    grid: RwSignal<Samurai>, history: RwSignal<History<Num3x3>>, hint: RwSignal<Option<Hint>>,
+    solution: RwSignal<Option<Samurai>>, mistakes: RwSignal<u32>, mark_mistakes: Signal<bool>,
+) -> impl IntoView {
+    let notes_active = use_context::<RwSignal<bool>>().expect("missing notes_active context");
+    let focused_cell = use_context::<RwSignal<CellInfo>>().expect("missing focused_cell context");
+
+    let (completed, set_completed) = create_signal(false);
+    create_effect(move |_| {
+        if grid.with(Samurai::is_valid).is_none() {
+            focused_cell.set(CellInfo::default());
+            set_completed(true);
+        } else {
+            set_completed(false);
+        }
+    });
+
+    view! {
+        <div id="samurai-scroll">
+            <div
+                id="samurai"
+                class:zoomed=move || focused_cell.with(|fc| fc.zoomed)
+                style:background-color=move || if completed() { "#4fff55" } else { "" }
+            >
+            {
+                (0..Samurai::SIZE)
+                    .flat_map(|y| (0..Samurai::SIZE).map(move |x| (x, y)))
+                    .map(|pos| if Samurai::contains(pos) {
+                        view! {
+                            <SamuraiCell
+                                grid=grid history=history hint=hint
+                                solution=solution mistakes=mistakes mark_mistakes=mark_mistakes
+                                pos=pos
+                            />
+                        }.into_view()
+                    } else {
+                        view! { <div class="samurai-gap"></div> }.into_view()
+                    })
+                    .collect_view()
+            }
+            </div>
+        </div>
+        <div id="numbers3-div" class="numbers-div">
+            <div>
+            {
+                (1..=9).map(|n| view! {
+                    <div
+                        style:color=move || {
+                            // Each number is in 41 of the squares once the board is filled
+                            let placed = grid.with(|grid| {
+                                Samurai::positions()
+                                    .filter(|&pos| grid[pos].num() == Some(n))
+                                    .count()
+                            });
+                            if notes_active.get() || placed == Samurai::CELLS / 9 {
+                                "gray"
+                            } else {
+                                "blue"
+                            }
+                        }
+                        on:click=move |_| {
+                            let cell_info = focused_cell.get();
+                            let Some(cell) = cell_info.node.get() else {
+                                return;
+                            };
+                            grid.update(|grid| {
+                                let pos = cell_info.pos;
+                                let num = if notes_active.get() {
+                                    grid[pos].with_toggle_note(n)
+                                } else if grid[pos].num_or_zero() == n {
+                                    Num3x3::new(0)
+                                } else {
+                                    Num3x3::new(n)
+                                };
+                                if let Some(mvs) = grid.place_num(pos, num) {
+                                    count_samurai_mistake(solution, mistakes, pos, num);
+                                    history.update(|hist| hist.update(mvs));
+                                }
+                            });
+                            cell.focus().expect("error focusing cell");
+                        }
+                    >{num_to_str(n)}</div>
+                }).collect_view()
+            }
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn SamuraiCell(
+    grid: RwSignal<Samurai>, history: RwSignal<History<Num3x3>>, hint: RwSignal<Option<Hint>>,
+    solution: RwSignal<Option<Samurai>>, mistakes: RwSignal<u32>, mark_mistakes: Signal<bool>,
+    pos: Pos,
+) -> impl IntoView {
+    let given = grid.with(|grid| grid[pos].is_given());
+
+    let focused_cell = use_context::<RwSignal<CellInfo>>().expect("missing focused_cell context");
+    let notes_active = use_context::<RwSignal<bool>>().expect("missing notes_active context");
+
+    let node_ref = create_node_ref();
+    let cell_info = CellInfo::new(node_ref, pos);
+
+    let display_cell = move || grid.with(|grid| {
+        if let Some(notes) = grid[pos].notes() {
+            notes.into_iter()
+                .enumerate()
+                .map(|(i, b)| view! {
+                    <div class="samurai-note-div">
+                        {if b { num_to_str(i as u8 + 1) } else { " " }}
+                    </div>
+                })
+                .collect_view()
+        } else {
+            num_to_str(grid[pos].num_or_zero()).into_view()
+        }
+    });
+    // The boards' boxes all line up with the whole board's, so box sides are every third square,
+    // and the outer sides are next to squares that aren't on a board
+    let (x, y) = pos;
+    let edge = |next: Option<Pos>| !next.is_some_and(Samurai::contains);
+    view! {
+    <div tabindex={if !given { "0" } else { "" }}
+        node_ref=node_ref
+        class="samurai-cell"
+        class:box-top=y % 3 == 0 && edge(y.checked_sub(1).map(|y| (x, y)))
+        class:box-right=x % 3 == 2
+        class:box-bottom=y % 3 == 2
+        class:box-left=x % 3 == 0 && edge(x.checked_sub(1).map(|x| (x, y)))
+        class:note-cell=move || grid.with(|grid| grid[pos].is_note())
+        class:hint-cell=move || hint.with(|hint| {
+            hint.as_ref().is_some_and(|hint| hint.step.cells.contains(&pos))
+        })
+        class:hint-target=move || hint.with(|hint| {
+            hint.as_ref().is_some_and(|hint| hint.targets().contains(&pos))
+        })
+        class:mistake=move || mark_mistakes.get() && solution.with(|solution| {
+            solution.as_ref().is_some_and(|solution| grid.with(|grid| {
+                let n = grid[pos].num_or_zero();
+                n != 0 && n != solution[pos].num_or_zero()
+            }))
+        })
+        class:focused-cell=move || {
+            let fc = focused_cell.get();
+            fc.node.get().is_some() && fc.pos == cell_info.pos
+        }
+        // Moving to another square keeps the board zoomed
+        on:focusin=move |_| {
+            focused_cell.update(|fc| *fc = CellInfo { zoomed: fc.zoomed, ..cell_info });
+        }
+        on:click=move |_| {
+            if given {
+                return;
+            }
+            let node = node_ref.get().expect("missing node_ref");
+            node.focus().expect("error focusing");
+            let fc = focused_cell.get();
+            if fc.node.get().is_some() && cell_info.pos == fc.pos {
+                focused_cell.update(|fc| { fc.zoomed = !fc.zoomed; });
+                node.scroll_into_view();
+            }
+        }
+        on:keydown=move |ev| {
+            let mut key = ev.key();
+            let val = if key == "Backspace" || key == "Delete" {
+                0
+            } else if key == "Escape" || key == "Tab" {
+                return;
+            } else {
+                key.make_ascii_lowercase();
+                match key.as_bytes() {
+                    &[c @ b'1'..=b'9'] => c - b'0',
+                    _ => {
+                        ev.prevent_default();
+                        return;
+                    }
+                }
+            };
+            grid.update(|grid| {
+                let num = if val == 0 {
+                    Num3x3::new(0)
+                } else if notes_active.get() {
+                    grid[pos].with_toggle_note(val)
+                } else {
+                    Num3x3::new(val)
+                };
+                if let Some(mvs) = grid.place_num(pos, num) {
+                    count_samurai_mistake(solution, mistakes, pos, num);
+                    history.update(|hist| hist.update(mvs));
+                }
+            });
+            ev.prevent_default();
+        }
+        class:given=given
+    >
+        {display_cell}
+    </div>
+    }
+}
+
+// Like `count_mistake`, for a Samurai board.
+fn count_samurai_mistake(
+    solution: RwSignal<Option<Samurai>>, mistakes: RwSignal<u32>, pos: Pos, num: Num3x3,
+) {
+    let n = num.num_or_zero();
+    let wrong = solution.with_untracked(|solution| {
+        solution.as_ref().is_some_and(|solution| solution[pos].num_or_zero() != n)
+    });
+    if n != 0 && wrong {
+        mistakes.update(|mistakes| *mistakes += 1);
+    }
+}
@@ -3,7 +3,8 @@
 use leptos::*;
 use leptos::html::Div;
 use serde::{Deserialize, Serialize};
-use sudku_grid::{Grid3x3, Grid4x4, MultiHistory3x3 as History3x3, MultiHistory4x4 as History4x4, Pos};
+use std::time::Duration;
+use sudku_grid::{Difficulty, GridNum, Pos, PuzzleId, SudokuGrid, Symmetry, Variant};
 use wasm_bindgen::prelude::*;
 use web_sys::Storage;
 
@@ -11,12 +12,14 @@ fn main() {
     run().expect("error running")
 }
 
+// Only decoding is used now, to migrate boards saved before `GameSave`
+#[allow(dead_code)]
 mod base64;
 pub mod console;
-mod grid3;
-use grid3::*;
-mod grid4;
-use grid4::*;
+mod board;
+use board::*;
+mod game;
+use game::*;
 
 #[wasm_bindgen(start)]
 pub fn run() -> Result<(), wasm_bindgen::JsValue> {
@@ -33,10 +36,14 @@ fn init_wasm_hooks() {
 #[component]
 fn App() -> impl IntoView {
     let (settings, set_settings) = create_signal(Settings {
-        num_blanks3: 18,
-        num_blanks4: 18,
-        using_3x3: true,
+        difficulty: 0,
+        symmetry: 0,
+        variant: 0,
+        rank: 3,
+        samurai: false,
+        show_mistakes: false,
     });
+    let show_mistakes = Signal::derive(move || settings.with(|s| s.show_mistakes));
 
     let focused_cell = create_rw_signal(CellInfo::default());
     provide_context(focused_cell);
@@ -44,14 +51,7 @@ fn App() -> impl IntoView {
     let notes_active = create_rw_signal(false);
     provide_context(notes_active);
 
-    let seed3 = create_rw_signal(Grid3x3::empty());
-    let seed4 = create_rw_signal(Grid4x4::empty());
-    let grid3 = create_rw_signal(Grid3x3::empty());
-    let grid4 = create_rw_signal(Grid4x4::empty());
-    let counts3 = create_rw_signal([0usize; 9]);
-    let counts4 = create_rw_signal([0usize; 16]);
-    let history3 = create_rw_signal(History3x3::new());
-    let history4 = create_rw_signal(History4x4::new());
+    let games = Games::new();
     let showing_grid = create_rw_signal(false);
 
     {
@@ -66,42 +66,14 @@ fn App() -> impl IntoView {
                 // TODO: Print error better
                 Err(e) => console::log!("error getting settings from local storage: {e:?}"),
             }
-            if let Some(grid) = load_grid3(Some(&storage), "sudku-grid3") {
-                grid3.set(grid);
-                let mut counts = [0usize; 9];
-                for y in 0..9 {
-                    for x in 0..9 {
-                        let n = grid[(x, y)].num_or_zero() as usize;
-                        if n != 0 {
-                            counts[n - 1] += 1;
-                        }
-                    }
-                }
-                counts3.set(counts);
-            }
-            if let Some(grid) = load_grid4(Some(&storage), "sudku-grid4") {
-                grid4.set(grid);
-                for y in 0..16 {
-                    for x in 0..16 {
-                        let n = grid[(x, y)].num_or_zero() as usize;
-                        if n != 0 {
-                            counts[n - 1] += 1;
-                        }
-                    }
-                }
-                counts4.set(counts);
-            }
-            if let Some(seed) = load_grid3(Some(&storage), "sudku-seed3") {
-                seed3.set(seed);
-            }
-            if let Some(seed) = load_grid4(Some(&storage), "sudku-seed4") {
-                seed4.set(seed);
-            }
+            games.load(&storage);
         }
     }
 
     let (showing_settings, set_showing_settings) = create_signal(false);
     let new_settings = create_rw_signal(settings());
+    // The ID typed in to play someone else's puzzle
+    let play_id = create_rw_signal(String::new());
 
     create_effect(move |_| {
         if showing_settings() {
@@ -113,39 +85,16 @@ fn App() -> impl IntoView {
         if showing_grid.get() {
             return;
         }
-        let using_3x3 = settings.with(|s| s.using_3x3);
-        let is_empty = if using_3x3 {
-            grid3.with(|grid| grid == &Grid3x3::EMPTY)
-        } else {
-            grid4.with(|grid| grid == &Grid4x4::EMPTY)
-        };
+        let board = settings.with(Settings::board);
+        let is_empty = games.is_empty(board);
         spawn_local(async move {
-            if using_3x3 {
-                if is_empty {
-                    if decr_gtns(None, "sudku-gtns3", 5) {
-                        let seed = Grid3x3::generate();
-                        save_grid3(None, "sudku-seed3", &seed);
-                        seed3.set(seed);
-                    }
-                    seed3.with(|seed| {
-                        let (grid, counts) = grid3x3_from_seed(seed, settings.with(|s| s.num_blanks3));
-                        grid3.set(grid);
-                        counts3.set(counts);
-                    });
-                }
-            } else {
-                if is_empty {
-                    if decr_gtns(None, "sudku-gtns4", 5) {
-                        let seed = Grid4x4::generate();
-                        save_grid4(None, "sudku-seed4", &seed);
-                        seed4.set(seed);
-                    }
-                    seed4.with(|seed| {
-                        let (grid, counts) = grid4x4_from_seed(seed, settings.with(|s| s.num_blanks4));
-                        grid4.set(grid);
-                        counts4.set(counts);
-                    });
-                }
+            if is_empty {
+                games.generate(
+                    board,
+                    settings.with(Settings::difficulty),
+                    settings.with(Settings::symmetry),
+                    settings.with(Settings::variant),
+                ).await;
             }
             showing_grid.set(true);
         });
@@ -163,38 +112,75 @@ fn App() -> impl IntoView {
                 .expect("error saving settings to local storage");
         });
     });
-    create_effect(move |_| {
-        grid3.with(|grid| {
-            save_grid3(None, "sudku-grid3", grid);
-        });
-    });
-    create_effect(move |_| {
-        grid4.with(|grid| {
-            save_grid4(None, "sudku-grid4", grid);
-        });
-    });
+    games.save_on_change();
+    games.clear_on_change();
+
+    // The clock only runs while the board is showing
+    set_interval(
+        move || {
+            if showing_grid.get_untracked() && !showing_settings.get_untracked() {
+                games.tick(settings.with_untracked(Settings::board));
+            }
+        },
+        Duration::from_secs(1),
+    );
 
     view! {
         <div id="app">
             <div id="main">
                 {
                     move || if showing_grid() {
-                        if settings.with(|s| s.using_3x3) {
-                            view! {
-                                <Grid3 grid=grid3 counts=counts3 history=history3 />
-                            }
-                        } else {
-                            view! {
-                                <Grid4 grid=grid4 counts=counts4 history=history4 />
-                            }
-                        }
+                        games.view(settings.with(Settings::board), show_mistakes)
                     } else {
-                        view! { <Loading /> }
+                        view! { <Loading /> }.into_view()
                     }
                 }
 
                 <div id="bottom-buttons">
+                    <span id="timer">
+                        {move || fmt_elapsed(games.elapsed(settings.with(Settings::board)))}
+                    </span>
+                    <button
+                        on:click=move |_| {
+                            games.fill_notes(settings.with(Settings::board));
+                            if let Some(cell) = focused_cell.get().node.get() {
+                                cell.focus().expect("error focusing cell");
+                            }
+                        }
+                    >"Fill Notes"</button>
+                    <button
+                        on:click=move |_| {
+                            if !games.show_hint(settings.with(Settings::board)) {
+                                alert("No hint found");
+                            }
+                        }
+                    >"Hint"</button>
+                    <button
+                        on:click=move |_| {
+                            if games.check(settings.with(Settings::board)) == 0 {
+                                alert("No mistakes found");
+                            }
+                        }
+                    >"Check"</button>
+                    {
+                        move || show_mistakes.get().then(|| view! {
+                            <span id="mistakes">
+                                "Mistakes: "
+                                {move || games.mistakes(settings.with(Settings::board))}
+                            </span>
+                        })
+                    }
                 </div>
+                {
+                    move || games.hint_text(settings.with(Settings::board)).map(|text| view! {
+                        <div id="hint">
+                            <span>{text}</span>
+                            <button
+                                on:click=move |_| games.apply_hint(settings.with(Settings::board))
+                            >"Apply"</button>
+                        </div>
+                    })
+                }
 
             </div>
 
@@ -219,59 +205,11 @@ fn App() -> impl IntoView {
                 <div>
                 {
                     move || {
-                        let disabled = if settings.with(|s| s.using_3x3) {
-                            history3.with(|hist| !hist.can_undo())
-                        } else {
-                            history4.with(|hist| !hist.can_undo())
-                        };
+                        let disabled = !games.can_undo(settings.with(Settings::board));
                         if !disabled {
                             view! { <button
                                 //prop:disabled=disabled
-                                on:click=move |_| {
-                                    if settings.with(|s| s.using_3x3) {
-                                        let Some(mvs) = history3.try_update(|hist| hist.undo().cloned())
-                                            .expect("bad history try_update") else {
-                                            // TODO: Disable?
-                                            return;
-                                        };
-                                        grid3.update(|grid| {
-                                            counts3.update(|counts| {
-                                                for mv in mvs {
-                                                    let nn = mv.new.num_or_zero();
-                                                    if nn != 0 {
-                                                        counts[nn as usize - 1] -= 1;
-                                                    }
-                                                    let on = mv.old.num_or_zero();
-                                                    if on != 0 {
-                                                        counts[on as usize - 1] += 1;
-                                                    }
-                                                    grid[mv.pos] = mv.old;
-                                                }
-                                            });
-                                        });
-                                    } else {
-                                        let Some(mvs) = history4.try_update(|hist| hist.undo().cloned())
-                                            .expect("bad history try_update") else {
-                                            // TODO: Disable?
-                                            return;
-                                        };
-                                        grid4.update(|grid| {
-                                            counts4.update(|counts| {
-                                                for mv in mvs {
-                                                    let nn = mv.new.num_or_zero();
-                                                    if nn != 0 {
-                                                        counts[nn as usize - 1] -= 1;
-                                                    }
-                                                    let on = mv.old.num_or_zero();
-                                                    if on != 0 {
-                                                        counts[on as usize - 1] += 1;
-                                                    }
-                                                    grid[mv.pos] = mv.old;
-                                                }
-                                            });
-                                        });
-                                    }
-                                }
+                                on:click=move |_| games.undo(settings.with(Settings::board))
                             >
                                 <img src={UNDO_IMG_SRC} />
                             </button> }.into_any()
@@ -285,59 +223,11 @@ fn App() -> impl IntoView {
                 <div>
                 {
                     move || {
-                        let disabled= if settings.with(|s| s.using_3x3) {
-                            history3.with(|hist| !hist.can_redo())
-                        } else {
-                            history4.with(|hist| !hist.can_redo())
-                        };
+                        let disabled = !games.can_redo(settings.with(Settings::board));
                         if !disabled {
                             view! { <button
                                 //prop:disabled=disabled
-                                on:click=move |_| {
-                                    if settings.with(|s| s.using_3x3) {
-                                        let Some(mvs) = history3.try_update(|hist| hist.redo().cloned())
-                                            .expect("bad history try_update") else {
-                                            // TODO: Disable?
-                                            return;
-                                        };
-                                        grid3.update(|grid| {
-                                            counts3.update(|counts| {
-                                                for mv in mvs {
-                                                    let nn = mv.new.num_or_zero();
-                                                    if nn != 0 {
-                                                        counts[nn as usize - 1] += 1;
-                                                    }
-                                                    let on = mv.old.num_or_zero();
-                                                    if on != 0 {
-                                                        counts[on as usize - 1] -= 1;
-                                                    }
-                                                    grid[mv.pos] = mv.new;
-                                                }
-                                            });
-                                        });
-                                    } else {
-                                        let Some(mvs) = history4.try_update(|hist| hist.redo().cloned())
-                                            .expect("bad history try_update") else {
-                                            // TODO: Disable?
-                                            return;
-                                        };
-                                        grid4.update(|grid| {
-                                            counts4.update(|counts| {
-                                                for mv in mvs {
-                                                    let nn = mv.new.num_or_zero();
-                                                    if nn != 0 {
-                                                        counts[nn as usize - 1] += 1;
-                                                    }
-                                                    let on = mv.old.num_or_zero();
-                                                    if on != 0 {
-                                                        counts[on as usize - 1] -= 1;
-                                                    }
-                                                    grid[mv.pos] = mv.new;
-                                                }
-                                            });
-                                        });
-                                    }
-                                }
+                                on:click=move |_| games.redo(settings.with(Settings::board))
                             >
                                 <img src={REDO_IMG_SRC} />
                             </button>}.into_any()
@@ -373,70 +263,212 @@ fn App() -> impl IntoView {
             >
                 <div id="settings-opts">
                     <label>
-                        "Number of Blanks (9x9): "
-                        <input
-                            type="number" name="num-blank-input" size="5" maxlength="3"
-                            min="1" max="80" step="1" pattern="[0-9]*"
-                            placeholder="Blanks" inputmode="numeric"
-                            prop:value=move || new_settings.with(|s| s.num_blanks3)
-                            on:input=move |ev| {
+                        "Difficulty: "
+                        <select
+                            name="difficulty-select"
+                            on:change=move |ev| {
                                 event_target_value(&ev)
                                     .parse()
                                     .into_iter()
-                                    .for_each(|num: usize| {
-                                        new_settings.update(|s| s.num_blanks3 = num.min(80))
+                                    .for_each(|i: usize| {
+                                        new_settings.update(|s| {
+                                            s.difficulty = i.min(Difficulty::ALL.len() - 1)
+                                        })
                                     });
                             }
-                        />
+                        >
+                        {
+                            Difficulty::ALL
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, difficulty)| view! {
+                                    <option
+                                        value=i.to_string()
+                                        prop:selected=move || new_settings.with(|s| s.difficulty == i)
+                                    >
+                                        {difficulty.name()}
+                                    </option>
+                                })
+                                .collect_view()
+                        }
+                        </select>
                     </label>
-                    <br />
+
                     <label>
-                        "Number of Blanks (16x16): "
-                        <input
-                            type="number" name="num-blank-input" size="5" maxlength="3"
-                            min="1" max="255" step="1" pattern="[0-9]*"
-                            placeholder="Blanks" inputmode="numeric"
-                            prop:value=move || new_settings.with(|s| s.num_blanks4)
-                            on:input=move |ev| {
+                        "Symmetry: "
+                        <select
+                            name="symmetry-select"
+                            on:change=move |ev| {
                                 event_target_value(&ev)
                                     .parse()
                                     .into_iter()
-                                    .for_each(|num: usize| {
-                                        new_settings.update(|s| s.num_blanks4 = num.min(255))
+                                    .for_each(|i: usize| {
+                                        new_settings.update(|s| {
+                                            s.symmetry = i.min(Symmetry::ALL.len() - 1)
+                                        })
                                     });
                             }
-                        />
+                        >
+                        {
+                            Symmetry::ALL
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, symmetry)| view! {
+                                    <option
+                                        value=i.to_string()
+                                        prop:selected=move || new_settings.with(|s| s.symmetry == i)
+                                    >
+                                        {symmetry.name()}
+                                    </option>
+                                })
+                                .collect_view()
+                        }
+                        </select>
+                    </label>
+
+                    <label>
+                        "Variant: "
+                        <select
+                            name="variant-select"
+                            on:change=move |ev| {
+                                event_target_value(&ev)
+                                    .parse()
+                                    .into_iter()
+                                    .for_each(|i: usize| {
+                                        new_settings.update(|s| {
+                                            s.variant = i.min(Variant::ALL.len() - 1)
+                                        })
+                                    });
+                            }
+                        >
+                        {
+                            Variant::ALL
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, variant)| view! {
+                                    <option
+                                        value=i.to_string()
+                                        prop:selected=move || new_settings.with(|s| s.variant == i)
+                                    >
+                                        {variant.name()}
+                                    </option>
+                                })
+                                .collect_view()
+                        }
+                        </select>
                     </label>
 
                     <hr />
 
-                    <label for="use-4x4">
-                        "16x16 Grid:"
-                        <input
-                            type="checkbox" name="use-4x4"
-                            prop:checked=move || new_settings.with(|s| !s.using_3x3)
-                            on:click=move |ev| {
-                                if event_target_checked(&ev) {
-                                    new_settings.update(|s| s.using_3x3 = false);
-                                } else {
-                                    new_settings.update(|s| s.using_3x3 = true);
+                    <label>
+                        "Board Size: "
+                        <select
+                            name="rank-select"
+                            on:change=move |ev| {
+                                let value = event_target_value(&ev);
+                                if value == "samurai" {
+                                    new_settings.update(|s| s.samurai = true);
+                                } else if let Ok(rank) = value.parse() {
+                                    new_settings.update(|s| {
+                                        s.rank = rank;
+                                        s.samurai = false;
+                                    });
                                 }
                             }
+                        >
+                        {
+                            RANKS
+                                .into_iter()
+                                .map(|rank| view! {
+                                    <option
+                                        value=rank.to_string()
+                                        prop:selected=move || new_settings.with(|s| {
+                                            !s.samurai && s.rank() == rank
+                                        })
+                                    >
+                                        {format!("{0}x{0}", rank * rank)}
+                                    </option>
+                                })
+                                .collect_view()
+                        }
+                        <option
+                            value="samurai"
+                            prop:selected=move || new_settings.with(|s| s.samurai)
+                        >"Samurai"</option>
+                        </select>
+                    </label>
+
+                    <hr />
+
+                    <label>
+                        "Show Mistakes: "
+                        <input
+                            type="checkbox"
+                            name="show-mistakes-checkbox"
+                            prop:checked=move || new_settings.with(|s| s.show_mistakes)
+                            on:change=move |ev| {
+                                let checked = event_target_checked(&ev);
+                                new_settings.update(|s| s.show_mistakes = checked);
+                            }
                         />
                     </label>
+
+                    <hr />
+
+                    <div>
+                        "Puzzle ID: "
+                        <span id="puzzle-id">
+                        {
+                            move || games.puzzle_id(settings.with(Settings::board))
+                                .map(|id| id.to_string())
+                                .unwrap_or_default()
+                        }
+                        </span>
+                    </div>
+                    <label>
+                        "Play ID: "
+                        <input
+                            type="text"
+                            name="play-id-input"
+                            prop:value=move || play_id.get()
+                            on:input=move |ev| play_id.set(event_target_value(&ev))
+                        />
+                    </label>
+                    <button
+                        on:click=move |_| {
+                            let id = match play_id.with(|id| id.parse::<PuzzleId>()) {
+                                Ok(id) if RANKS.contains(&id.rank) => id,
+                                _ => {
+                                    alert("Invalid puzzle ID");
+                                    return;
+                                }
+                            };
+                            focused_cell.set(CellInfo::default());
+                            games.reset(id.rank);
+                            games.play(id);
+                            new_settings.update(|s| {
+                                s.rank = id.rank;
+                                s.samurai = false;
+                                s.difficulty = id.difficulty as usize;
+                                s.symmetry = Symmetry::ALL
+                                    .iter()
+                                    .position(|&s| s == id.symmetry)
+                                    .unwrap_or_default();
+                                s.variant = 0;
+                            });
+                            play_id.set(String::new());
+                            set_showing_settings(false);
+                            showing_grid.set(false);
+                            set_settings(new_settings.get());
+                        }
+                    >"Play"</button>
                 </div>
                 <div id="settings-bottom-buttons">
                     <div>
                         <button
                             on:click=move |_| {
                                 focused_cell.set(CellInfo::default());
-                                if new_settings.with(|s| s.using_3x3) {
-                                    grid3.set(Grid3x3::empty());
-                                    history3.set(History3x3::new());
-                                } else {
-                                    grid4.set(Grid4x4::empty());
-                                    history4.set(History4x4::new());
-                                }
+                                games.reset(new_settings.with(Settings::board));
                                 set_showing_settings(false);
                                 showing_grid.set(false);
                                 set_settings(new_settings.get());
@@ -453,18 +485,9 @@ fn App() -> impl IntoView {
                     <div>
                         <button
                             on:click=move |_| {
-                                if new_settings.with(|s| s.using_3x3) {
-                                    if !settings.with(|s| s.using_3x3) {
-                                        showing_grid.set(false);
-                                    }
-                                    //grid3.set(Grid3x3::empty());
-                                    //history3.set(History3x3::new());
-                                } else {
-                                    if settings.with(|s| s.using_3x3) {
-                                        showing_grid.set(false);
-                                    }
-                                    //grid4.set(Grid4x4::empty());
-                                    //history4.set(History4x4::new());
+                                let board = new_settings.with(Settings::board);
+                                if board != settings.with(Settings::board) {
+                                    showing_grid.set(false);
                                 }
                                 set_showing_settings(false);
                                 set_settings(new_settings.get());
@@ -482,14 +505,14 @@ fn Loading() -> impl IntoView {
     view! { <div id="loader"></div> }
 }
 
-#[Component]
+#[component]
 fn About(showing_about: RwSignal<bool>) -> impl IntoView {
     view! {
         <div id="about">
             <div id="about-the-app">
                 <h3>"About The App"</h3>
                 <p>"A simple Sudoku app that, as of right now, neither looks nor functions spectacularly."</p>
-                <p>"One thing to take special note of is that generated Sudoku boards are not guaranteed to be well-formed, that is, it is not guaranteed that a generated with a given number of blanks will have only one solution."
+                <p>"One thing to take special note of is that generated Sudoku boards always have exactly one solution. The difficulty is rated by the hardest solving technique needed and how often the tricky ones are needed; Diabolical boards cannot be finished without techniques beyond X-Wings, Swordfish, and XY-Wings."
                 <p>"Feedback is appreciated, but do not harass me. :)"</p>
                 </p>
             </div>
@@ -497,9 +520,8 @@ fn About(showing_about: RwSignal<bool>) -> impl IntoView {
                 <h3>"How To Play"</h3>
                 <p>"A very simple explanation of how to play is as follows."</p>
                 <p>"The goal is to fill all empty cells on the board. Each cell must have a number that is unique within its row, column, and enclosing 3x3 box. If you attempt to enter a number into a cell but it does not work, the most likely reason is that the number entered violates this uniqueness rule."</p>
-                <p>"If you are stuck in a situation where you feel it could be a toss-up as to what number goes in a cell, it is possible that the generated board is not well-formed and there are multiple solutions. This is usually discovered when choosing between the same pair of numbers for multiple cells. In this case, either number will likely work. If not, Undo is your friend. :)"</p>
                 <p>"Notes can be placed in cells by activating the pencil icon by the number input."</p>
-                <p>"Evertying is the same when playing with the 16x16 board."</p>
+                <p>"Everything is the same when playing with the 4x4, 16x16, and 25x25 boards, except that the boxes are 2x2, 4x4, and 5x5. Numbers above 9 are shown as letters, A being 10."</p>
                 <p>"If you are still confused, go to the Internet. ;]"</p>
             </div>
             <button on:click=move |_| showing_about.set(false)>"Close"</button>
@@ -526,10 +548,29 @@ pub const fn num_to_str(n: u8) -> &'static str {
         14 => "E",
         15 => "F",
         16 => "G",
+        17 => "H",
+        18 => "I",
+        19 => "J",
+        20 => "K",
+        21 => "L",
+        22 => "M",
+        23 => "N",
+        24 => "O",
+        25 => "P",
         _ => "",
     }
 }
 
+/// Formats seconds as `m:ss`, or `h:mm:ss` once past an hour.
+pub fn fmt_elapsed(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
 #[derive(Clone, Copy, Default)]
 pub struct CellInfo {
     pub node: NodeRef<Div>,
@@ -543,7 +584,7 @@ impl CellInfo {
     }
 }
 
-fn load_grid3(storage: Option<&Storage>, name: &str) -> Option<Grid3x3> {
+fn load_grid<G: SudokuGrid>(storage: Option<&Storage>, name: &str) -> Option<G> {
     let mut stor = None;
     let Some(storage) = storage.or_else(|| {
         stor = get_local_storage();
@@ -553,14 +594,14 @@ fn load_grid3(storage: Option<&Storage>, name: &str) -> Option<Grid3x3> {
         return None;
     };
     match storage.get_item(name) {
-        Ok(Some(enc)) => match base64::decode(enc).map(Grid3x3::from_encoded) {
+        Ok(Some(enc)) => match base64::decode(enc).map(G::from_encoded) {
             Some(Some(grid)) => Some(grid),
             Some(None) => {
-                console::log!("bad grid3 encoding for {name}");
+                console::log!("bad grid encoding for {name}");
                 None
             }
             None => {
-                console::log!("bad grid3 base64 encoding for {name}");
+                console::log!("bad grid base64 encoding for {name}");
                 None
             }
         }
@@ -573,157 +614,20 @@ fn load_grid3(storage: Option<&Storage>, name: &str) -> Option<Grid3x3> {
     }
 }
 
-fn load_grid4(storage: Option<&Storage>, name: &str) -> Option<Grid4x4> {
-    let mut stor = None;
-    let Some(storage) = storage.or_else(|| {
-        stor = get_local_storage();
-        stor.as_ref()
-    }) else {
-        console::log!("could not get local storage");
-        return None;
-    };
-    match storage.get_item(name) {
-        Ok(Some(enc)) => match base64::decode(enc).map(Grid4x4::from_encoded) {
-            Some(Some(grid)) => Some(grid),
-            Some(None) => {
-                console::log!("bad grid4 encoding for {name}");
-                None
-            }
-            None => {
-                console::log!("bad grid4 base64 encoding for {name}");
-                None
-            }
-        }
-        Ok(None) => None,
-        // TODO: Print error better
-        Err(e) => {
-            console::log!("error getting {name} from local storage: {e:?}");
-            None
-        }
-    }
-}
-
-// TODO: What to return
-fn save_grid3(storage: Option<&Storage>, name: &str, grid: &Grid3x3) -> bool {
-    let mut stor = None;
-    let Some(storage) = storage.or_else(|| {
-        stor = get_local_storage();
-        stor.as_ref()
-    }) else {
-        console::log!("could not get local storage");
-        return false;
-    };
-    // TODO: What to do
-    /*
-    storage
-        .set_item(name, &base64::encode(grid.encode()))
-        .expect("error saving {name}");
-    */
-    if let Err(e) = storage.set_item(name, &base64::encode(grid.encode())) {
-        console::log!("error saving {name}: {e:?}");
-        return false;
-    }
-    true
-}
-
-// TODO: What to return
-fn save_grid4(storage: Option<&Storage>, name: &str, grid: &Grid4x4) -> bool {
-    let mut stor = None;
-    let Some(storage) = storage.or_else(|| {
-        stor = get_local_storage();
-        stor.as_ref()
-    }) else {
-        console::log!("could not get local storage");
-        return false;
-    };
-    // TODO: What to do
-    /*
-    storage
-        .set_item(name, &base64::encode(grid.encode()))
-        .expect("error saving {name}");
-    */
-    if let Err(e) = storage.set_item(name, &base64::encode(grid.encode())) {
-        console::log!("error saving {name}: {e:?}");
-        return false;
-    }
-    true
-}
+/// How many of each number are on the board.
+pub type Counts = Vec<usize>;
 
-/// Decrements the count for number of games until a new seed should be generated (gtns = games til
-/// new seed). If the count is 0 or was not set, it is reset with the provided value.
-/// Returns true if the count was reset. Returns true if there is any kind of error getting the
-/// count.
-fn decr_gtns(storage: Option<&Storage>, name: &str, reset_val: usize) -> bool {
-    let mut stor = None;
-    let Some(storage) = storage.or_else(|| {
-        stor = get_local_storage();
-        stor.as_ref()
-    }) else {
-        console::log!("could not get local storage");
-        return true;
-    };
-    match storage.get_item(name) {
-        Ok(Some(num_str)) => {
-            let num = if let Ok(num) = num_str.parse::<usize>() {
-                if num == 0 { reset_val } else { num - 1 }
-            } else {
-                reset_val
-            };
-            // TODO: Handle differently?
-            if let Err(e) = storage.set_item(name, &num.to_string()) {
-                console::log!("error saving {name}: {e:?}");
-            }
-            num == reset_val
-        }
-        Ok(None) => {
-            if let Err(e) = storage.set_item(name, &reset_val.to_string()) {
-                console::log!("error saving {name}: {e:?}");
-            }
-            true
-        }
-        // TODO: Print error better
-        Err(e) => {
-            console::log!("error getting {name} from local storage: {e:?}");
-            true
-        }
-    }
-}
-
-pub type Counts3x3 = [usize; 9];
-pub type Counts4x4 = [usize; 16];
-
-fn grid3x3_from_seed(seed: &Grid3x3, num_blank: usize) -> (Grid3x3, Counts3x3) {
-    let mut grid = seed.clone();
-    grid.randomize();
-    grid.remove_nums(num_blank);
-    grid.set_given();
-    let mut counts = [0usize; 9];
-    for y in 0..9 {
-        for x in 0..9 {
-            let n = grid[(x, y)].num_or_zero() as usize;
-            if n != 0 {
-                counts[n - 1] += 1;
-            }
-        }
-    }
-    (grid, counts)
-}
-
-fn grid4x4_from_seed(seed: &Grid4x4, num_blank: usize) -> (Grid4x4, Counts4x4) {
-    let mut grid = seed.clone();
-    grid.randomize();
-    grid.remove_nums(num_blank);
-    grid.set_given();
-    let mut counts = [0usize; 16];
-    for y in 0..16 {
-        for x in 0..16 {
+fn counts_of<G: SudokuGrid>(grid: &G) -> Counts {
+    let mut counts = vec![0; G::SIZE];
+    for y in 0..G::SIZE {
+        for x in 0..G::SIZE {
             let n = grid[(x, y)].num_or_zero() as usize;
             if n != 0 {
                 counts[n - 1] += 1;
             }
         }
     }
-    (grid, counts)
+    counts
 }
 
 fn get_local_storage() -> Option<Storage> {
@@ -743,9 +647,62 @@ fn get_local_storage() -> Option<Storage> {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Settings {
-    pub using_3x3: bool,
-    pub num_blanks3: usize,
-    pub num_blanks4: usize,
+    /// The rank of the board, 3 being the usual 9x9 one.
+    #[serde(default = "default_rank")]
+    pub rank: usize,
+    /// Index into `Difficulty::ALL`.
+    #[serde(default)]
+    pub difficulty: usize,
+    /// Index into `Symmetry::ALL` of the pattern the givens of new boards follow.
+    #[serde(default)]
+    pub symmetry: usize,
+    /// Index into `Variant::ALL` of the rules new boards are played with. Boards bigger than the
+    /// variant's `max_rank` are classic.
+    #[serde(default)]
+    pub variant: usize,
+    /// Whether a Samurai board is played instead of one of the size of `rank`.
+    #[serde(default)]
+    pub samurai: bool,
+    /// Whether wrong numbers are marked as soon as they're placed.
+    #[serde(default)]
+    pub show_mistakes: bool,
+}
+
+/// The ranks of the boards that can be played.
+pub const RANKS: [usize; 4] = [2, 3, 4, 5];
+
+/// The board key of the Samurai game. Other boards are keyed by their rank.
+pub const SAMURAI: usize = 0;
+
+fn default_rank() -> usize {
+    3
+}
+
+impl Settings {
+    pub fn rank(&self) -> usize {
+        self.rank.clamp(RANKS[0], RANKS[RANKS.len() - 1])
+    }
+
+    /// The key of the board being played, either its rank or `SAMURAI`.
+    pub fn board(&self) -> usize {
+        if self.samurai {
+            SAMURAI
+        } else {
+            self.rank()
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::ALL[self.difficulty.min(Difficulty::ALL.len() - 1)]
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        Symmetry::ALL[self.symmetry.min(Symmetry::ALL.len() - 1)]
+    }
+
+    pub fn variant(&self) -> Variant {
+        Variant::ALL[self.variant.min(Variant::ALL.len() - 1)]
+    }
 }
 
 #[wasm_bindgen]
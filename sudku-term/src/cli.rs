@@ -0,0 +1,846 @@
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, Read, Write};
+use sudku_grid::rand::{self, Rng};
+use sudku_grid::{
+    num_symbol, peers, positions, seeded_rng, Collection, CollectionPuzzle, Difficulty, Grid2x2,
+    Grid3x3, Grid4x4, Grid5x5, GridNum, Pos, PuzzleFormat, PuzzleId, SudokuGrid,
+};
+
+/// The batch commands, run as `sudku-term <command>` instead of playing.
+pub const COMMANDS: [&str; 5] = ["generate", "solve", "validate", "rate", "convert"];
+
+/// The exit status when a puzzle failed: it couldn't be generated, solved, or rated, or isn't
+/// valid.
+pub const FAILED: u8 = 1;
+/// The exit status when the arguments are wrong.
+pub const USAGE_ERROR: u8 = 2;
+/// The exit status when the input couldn't be read or isn't puzzles.
+pub const INPUT_ERROR: u8 = 3;
+
+// How many boards `generate --blanks` tries before giving up on leaving that many blanks
+const BLANKS_ATTEMPTS: usize = 50;
+
+// Runs the expression with `$G` as the board type for the rank
+macro_rules! with_rank {
+    ($rank:expr, $G:ident => $e:expr) => {
+        match $rank {
+            2 => {
+                type $G = Grid2x2;
+                $e
+            }
+            3 => {
+                type $G = Grid3x3;
+                $e
+            }
+            4 => {
+                type $G = Grid4x4;
+                $e
+            }
+            _ => {
+                type $G = Grid5x5;
+                $e
+            }
+        }
+    };
+}
+
+/// Runs the command, writing its output to `out` and any problems to stderr. Returns the exit
+/// status.
+pub fn run(cmd: &str, args: impl Iterator<Item = String>, out: &mut impl Write) -> u8 {
+    let res = Args::parse(cmd, args).and_then(|args| {
+        let report = match cmd {
+            "generate" => generate(&args, out)?,
+            _ => {
+                let puzzles = read_puzzles(&args)?;
+                match cmd {
+                    "solve" => solve(&puzzles, &args, out)?,
+                    "validate" => validate(&puzzles, &args, out)?,
+                    "rate" => rate(&puzzles, &args, out)?,
+                    _ => convert(&puzzles, &args, out)?,
+                }
+            }
+        };
+        Ok(report.finish(out)?)
+    });
+    match res {
+        Ok(status) => status,
+        Err(CliError::Usage(e)) => {
+            eprintln!("{e}\n\n{}", crate::USAGE);
+            USAGE_ERROR
+        }
+        Err(CliError::Input(e)) => {
+            eprintln!("error: {e}");
+            INPUT_ERROR
+        }
+    }
+}
+
+/// Reads a board size as it's written on the command line, returning its rank.
+pub fn parse_size(size: &str) -> Result<usize, String> {
+    match size {
+        "9" | "9x9" => Ok(3),
+        "16" | "16x16" => Ok(4),
+        _ => Err(format!("unsupported board size: {size}")),
+    }
+}
+
+pub fn parse_difficulty(name: &str) -> Result<Difficulty, String> {
+    Difficulty::ALL
+        .into_iter()
+        .find(|d| d.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown difficulty: {name}"))
+}
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Input(String),
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Input(e.to_string())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Output {
+    Text(PuzzleFormat),
+    Collection,
+}
+
+impl Output {
+    fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "collection" => Output::Collection,
+            name => Output::Text(parse_format(name)?),
+        })
+    }
+}
+
+fn parse_format(name: &str) -> Result<PuzzleFormat, String> {
+    match name {
+        "line" => Ok(PuzzleFormat::Line),
+        "sdk" => Ok(PuzzleFormat::Sdk),
+        "ss" => Ok(PuzzleFormat::Ss),
+        _ => Err(format!("unknown format: {name}")),
+    }
+}
+
+#[derive(Default, Debug)]
+struct Args {
+    rank: Option<usize>,
+    blanks: Option<usize>,
+    seed: Option<u64>,
+    count: Option<usize>,
+    difficulty: Option<Difficulty>,
+    format: Option<Output>,
+    from: Option<PuzzleFormat>,
+    to: Option<Output>,
+    json: bool,
+    files: Vec<String>,
+}
+
+impl Args {
+    fn parse(cmd: &str, mut args: impl Iterator<Item = String>) -> Result<Self, CliError> {
+        let allowed: &[&str] = match cmd {
+            "generate" => &[
+                "--size",
+                "--blanks",
+                "--seed",
+                "--count",
+                "--difficulty",
+                "--format",
+            ],
+            "solve" => &["--from", "--format"],
+            "convert" => &["--from", "--to"],
+            _ => &["--from"],
+        };
+        let mut opts = Self::default();
+        while let Some(arg) = args.next() {
+            if arg == "--json" {
+                opts.json = true;
+                continue;
+            }
+            if arg == "-" || !arg.starts_with('-') {
+                if cmd == "generate" {
+                    return Err(CliError::Usage(format!("unexpected argument: {arg}")));
+                }
+                opts.files.push(arg);
+                continue;
+            }
+            if !allowed.contains(&arg.as_str()) {
+                return Err(CliError::Usage(format!("unknown option for {cmd}: {arg}")));
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| CliError::Usage(format!("missing value for {arg}")))?;
+            let number = |what| {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("{what} has to be a number: {value}"))
+            };
+            let res = match arg.as_str() {
+                "--size" => parse_size(&value).map(|rank| opts.rank = Some(rank)),
+                "--blanks" => number("--blanks").map(|n| opts.blanks = Some(n as usize)),
+                "--seed" => number("--seed").map(|n| opts.seed = Some(n)),
+                "--count" => number("--count").map(|n| opts.count = Some(n as usize)),
+                "--difficulty" => parse_difficulty(&value).map(|d| opts.difficulty = Some(d)),
+                "--format" => Output::parse(&value).map(|f| opts.format = Some(f)),
+                "--from" => parse_format(&value).map(|f| opts.from = Some(f)),
+                _ => Output::parse(&value).map(|f| opts.to = Some(f)),
+            };
+            res.map_err(CliError::Usage)?;
+        }
+        if opts.blanks.is_some() && opts.difficulty.is_some() {
+            return Err(CliError::Usage(
+                "--blanks and --difficulty can't be used together".into(),
+            ));
+        }
+        if cmd == "convert" && opts.to.is_none() {
+            return Err(CliError::Usage("convert needs --to".into()));
+        }
+        if cmd == "solve" && opts.format == Some(Output::Collection) {
+            return Err(CliError::Usage("solve can't write collections".into()));
+        }
+        if opts.json && opts.to.or(opts.format) == Some(Output::Collection) {
+            return Err(CliError::Usage(
+                "--json can't be used with collections".into(),
+            ));
+        }
+        Ok(opts)
+    }
+
+    // The text format puzzles are written in
+    fn text_format(&self) -> PuzzleFormat {
+        match self.to.or(self.format) {
+            Some(Output::Text(format)) => format,
+            _ => PuzzleFormat::Line,
+        }
+    }
+}
+
+/// A puzzle read from the input, not parsed yet.
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Puzzle {
+    /// Where it's from: the file and line, or the file and ID in a collection.
+    source: String,
+    rank: usize,
+    format: PuzzleFormat,
+    text: String,
+}
+
+// Reads the puzzles in each file, or stdin if there are none or the file is `-`
+fn read_puzzles(args: &Args) -> Result<Vec<Puzzle>, CliError> {
+    let stdin = ["-".to_string()];
+    let files = if args.files.is_empty() {
+        &stdin[..]
+    } else {
+        &args.files
+    };
+    let mut puzzles = Vec::new();
+    for file in files {
+        let (name, text) = if file == "-" {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            ("<stdin>", text)
+        } else {
+            let text =
+                fs::read_to_string(file).map_err(|e| CliError::Input(format!("{file}: {e}")))?;
+            (file.as_str(), text)
+        };
+        puzzles.extend(split(name, &text, args.from)?);
+    }
+    Ok(puzzles)
+}
+
+// Splits the text into puzzles. A collection is read whole. Otherwise puzzles are separated by
+// blank lines, except that lines with a whole board each are separate puzzles. The size of each
+// board is found from how many squares it has.
+fn split(name: &str, text: &str, from: Option<PuzzleFormat>) -> Result<Vec<Puzzle>, CliError> {
+    if text.trim_start().starts_with("sudku-collection") {
+        return split_collection(name, text);
+    }
+    let mut puzzles = Vec::new();
+    for block in blocks(text) {
+        let rows = block
+            .iter()
+            .filter(|(_, l)| !l.starts_with('#') && squares(l) > 0)
+            .collect::<Vec<_>>();
+        let Some(&&(first, _)) = rows.first() else {
+            continue;
+        };
+        let total = rows.iter().map(|(_, l)| squares(l)).sum();
+        let is_board = rank_of(total).is_some_and(|rank| rows.len() == rank * rank);
+        let lines = match from {
+            Some(format) => format == PuzzleFormat::Line,
+            None => {
+                (rows.len() == 1 || !is_board)
+                    && rows.iter().all(|(_, l)| rank_of(squares(l)).is_some())
+            }
+        };
+        let unknown_size = |line, len| {
+            CliError::Input(format!(
+                "{name}:{line}: found {len} squares, which isn't a board size"
+            ))
+        };
+        if lines {
+            for &&(line, l) in &rows {
+                let len = squares(l);
+                puzzles.push(Puzzle {
+                    source: format!("{name}:{line}"),
+                    rank: rank_of(len).ok_or_else(|| unknown_size(line, len))?,
+                    format: PuzzleFormat::Line,
+                    text: l.to_string(),
+                });
+            }
+        } else {
+            let text = block.iter().map(|(_, l)| *l).collect::<Vec<_>>().join("\n");
+            puzzles.push(Puzzle {
+                source: format!("{name}:{first}"),
+                rank: rank_of(total).ok_or_else(|| unknown_size(first, total))?,
+                format: from.unwrap_or_else(|| PuzzleFormat::detect(&text)),
+                text,
+            });
+        }
+    }
+    Ok(puzzles)
+}
+
+fn split_collection(name: &str, text: &str) -> Result<Vec<Puzzle>, CliError> {
+    let rank = text
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("rank "))
+        .and_then(|rank| rank.trim().parse().ok())
+        .filter(|rank| (2..=5).contains(rank))
+        .ok_or_else(|| CliError::Input(format!("{name}: collection has no board size")))?;
+    with_rank!(rank, G => {
+        let collection =
+            Collection::<G>::parse(text).map_err(|e| CliError::Input(format!("{name}: {e}")))?;
+        Ok(collection
+            .puzzles
+            .iter()
+            .map(|p| Puzzle {
+                source: format!("{name}:{}", p.id),
+                rank,
+                format: PuzzleFormat::Line,
+                text: p.puzzle.to_line(),
+            })
+            .collect())
+    })
+}
+
+// The runs of lines without blank lines between them, with their line numbers
+fn blocks(text: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut blocks = vec![Vec::new()];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            blocks.push(Vec::new());
+        } else {
+            blocks.last_mut().unwrap().push((i + 1, line));
+        }
+    }
+    blocks.retain(|b| !b.is_empty());
+    blocks
+}
+
+// The squares on the line: numbers, letters, and `.` or `0` for empty ones
+fn squares(line: &str) -> usize {
+    line.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '.')
+        .count()
+}
+
+fn rank_of(squares: usize) -> Option<usize> {
+    (2..=5).find(|rank: &usize| rank.pow(4) == squares)
+}
+
+fn cell_name((x, y): Pos) -> String {
+    format!("r{}c{}", y + 1, x + 1)
+}
+
+// Finds the solution, failing if the puzzle breaks the rules or doesn't have just one
+fn check<G: SudokuGrid>(grid: &G) -> Result<G, String> {
+    for pos in positions::<G>() {
+        let n = grid[pos].num_or_zero();
+        if n == 0 {
+            continue;
+        }
+        if let Some(other) = peers(G::RANK, pos).find(|&p| grid[p].num_or_zero() == n) {
+            return Err(format!(
+                "{} is in both {} and {}",
+                num_symbol(n),
+                cell_name(pos),
+                cell_name(other)
+            ));
+        }
+    }
+    match grid.unique_solution() {
+        Some(solution) => Ok(solution),
+        None if grid.count_solutions(1) == 0 => Err("no solution".into()),
+        None => Err("more than one solution".into()),
+    }
+}
+
+/// What a command found, written out at the end in JSON mode.
+struct Report {
+    json: bool,
+    results: Vec<Value>,
+    // How many puzzles have been written, to separate the boards written on many lines
+    written: usize,
+    failed: bool,
+    bad_input: bool,
+}
+
+impl Report {
+    fn new(json: bool) -> Self {
+        Self {
+            json,
+            results: Vec::new(),
+            written: 0,
+            failed: false,
+            bad_input: false,
+        }
+    }
+
+    fn parse<G: SudokuGrid>(&mut self, p: &Puzzle) -> Option<G> {
+        match G::from_format(&p.text, p.format) {
+            Ok(grid) => Some(grid),
+            Err(e) => {
+                eprintln!("{}: {e}", p.source);
+                self.bad_input = true;
+                if self.json {
+                    self.results
+                        .push(json!({ "source": p.source, "error": e.to_string() }));
+                }
+                None
+            }
+        }
+    }
+
+    fn fail(&mut self, source: &str, reason: &str) {
+        eprintln!("{source}: {reason}");
+        self.failed = true;
+    }
+
+    fn write_puzzle(
+        &mut self,
+        out: &mut impl Write,
+        text: &str,
+        format: PuzzleFormat,
+    ) -> io::Result<()> {
+        if self.written > 0 && format != PuzzleFormat::Line {
+            writeln!(out)?;
+        }
+        self.written += 1;
+        write!(out, "{text}")
+    }
+
+    // Writes the results in JSON mode, returning the exit status
+    fn finish(self, out: &mut impl Write) -> io::Result<u8> {
+        if self.json {
+            writeln!(out, "{:#}", Value::Array(self.results))?;
+        }
+        Ok(if self.bad_input {
+            INPUT_ERROR
+        } else if self.failed {
+            FAILED
+        } else {
+            0
+        })
+    }
+}
+
+fn generate(args: &Args, out: &mut impl Write) -> Result<Report, CliError> {
+    with_rank!(args.rank.unwrap_or(3), G => generate_puzzles::<G>(args, out))
+}
+
+// Puzzle `i` is generated from the seed plus `i`, so a batch can be made again from its first
+// seed. Puzzles with a number of blanks are named by their seed, and the rest by their ID.
+fn generate_puzzles<G: SudokuGrid>(args: &Args, out: &mut impl Write) -> Result<Report, CliError> {
+    if let Some(blanks) = args.blanks.filter(|&n| n >= G::CELLS) {
+        return Err(CliError::Usage(format!(
+            "can't leave {blanks} blanks on a board with {} squares",
+            G::CELLS
+        )));
+    }
+    let format = args.text_format();
+    let first = args.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut report = Report::new(args.json);
+    let mut collection = Collection::<G>::new();
+    for i in 0..args.count.unwrap_or(1) {
+        let seed = first.wrapping_add(i as u64);
+        let (id, mut puzzle, solution) = match args.blanks {
+            Some(blanks) => match with_blanks::<G>(blanks, seed) {
+                Some((puzzle, solution)) => (seed.to_string(), puzzle, solution),
+                None => {
+                    let reason = format!("couldn't leave {blanks} blanks with one solution");
+                    report.fail(&format!("seed {seed}"), &reason);
+                    continue;
+                }
+            },
+            None => {
+                let difficulty = args.difficulty.unwrap_or(Difficulty::Easy);
+                let id = PuzzleId::new(G::RANK, difficulty, seed);
+                let (puzzle, solution) = id.generate::<G>().expect("ID is for the board's rank");
+                (id.to_string(), puzzle, solution)
+            }
+        };
+        let difficulty = puzzle.difficulty();
+        if args.json {
+            let blanks = positions::<G>()
+                .filter(|&pos| puzzle[pos].num_or_zero() == 0)
+                .count();
+            report.results.push(json!({
+                "id": id,
+                "seed": seed,
+                "size": G::SIZE,
+                "blanks": blanks,
+                "difficulty": difficulty.name(),
+                "puzzle": puzzle.to_format(format).trim_end(),
+                "solution": solution.to_format(format).trim_end(),
+            }));
+        } else if args.format == Some(Output::Collection) {
+            puzzle.set_given();
+            collection.puzzles.push(CollectionPuzzle {
+                id,
+                difficulty,
+                title: None,
+                author: None,
+                puzzle,
+                solution,
+            });
+        } else {
+            report.write_puzzle(out, &puzzle.to_format(format), format)?;
+        }
+    }
+    if args.format == Some(Output::Collection) {
+        write!(out, "{collection}")?;
+    }
+    Ok(report)
+}
+
+// Removes numbers from filled boards until one is left with exactly that many blanks and one
+// solution
+fn with_blanks<G: SudokuGrid>(blanks: usize, seed: u64) -> Option<(G, G)> {
+    let mut rng = seeded_rng(seed);
+    for _ in 0..BLANKS_ATTEMPTS {
+        let solution = G::generate_with_rng(&mut rng);
+        let mut puzzle = solution.clone();
+        if puzzle
+            .try_remove_nums_unique_with_rng(blanks, &mut rng)
+            .ok()?
+            == blanks
+        {
+            return Some((puzzle, solution));
+        }
+    }
+    None
+}
+
+fn solve(puzzles: &[Puzzle], args: &Args, out: &mut impl Write) -> Result<Report, CliError> {
+    let format = args.text_format();
+    let mut report = Report::new(args.json);
+    for p in puzzles {
+        with_rank!(p.rank, G => {
+            let Some(grid) = report.parse::<G>(p) else {
+                continue;
+            };
+            match check(&grid) {
+                Ok(solution) if args.json => report.results.push(json!({
+                    "source": p.source,
+                    "solved": true,
+                    "solution": solution.to_format(format).trim_end(),
+                })),
+                Ok(solution) => report.write_puzzle(out, &solution.to_format(format), format)?,
+                Err(reason) => {
+                    report.fail(&p.source, &reason);
+                    if args.json {
+                        report.results.push(json!({
+                            "source": p.source,
+                            "solved": false,
+                            "error": reason,
+                        }));
+                    }
+                }
+            }
+        });
+    }
+    Ok(report)
+}
+
+fn validate(puzzles: &[Puzzle], args: &Args, out: &mut impl Write) -> Result<Report, CliError> {
+    let mut report = Report::new(args.json);
+    for p in puzzles {
+        with_rank!(p.rank, G => {
+            let Some(grid) = report.parse::<G>(p) else {
+                continue;
+            };
+            let res = check(&grid);
+            report.failed |= res.is_err();
+            if args.json {
+                report.results.push(json!({
+                    "source": p.source,
+                    "valid": res.is_ok(),
+                    "error": res.err(),
+                }));
+            } else {
+                match res {
+                    Ok(_) => writeln!(out, "{}: ok", p.source)?,
+                    Err(reason) => writeln!(out, "{}: invalid: {reason}", p.source)?,
+                }
+            }
+        });
+    }
+    Ok(report)
+}
+
+// Only puzzles with one solution are rated, since the rest can't be solved by logic
+fn rate(puzzles: &[Puzzle], args: &Args, out: &mut impl Write) -> Result<Report, CliError> {
+    let mut report = Report::new(args.json);
+    for p in puzzles {
+        with_rank!(p.rank, G => {
+            let Some(grid) = report.parse::<G>(p) else {
+                continue;
+            };
+            if let Err(reason) = check(&grid) {
+                report.fail(&p.source, &reason);
+                if args.json {
+                    report.results.push(json!({ "source": p.source, "error": reason }));
+                }
+                continue;
+            }
+            let (steps, solved) = grid.solve_logically();
+            let difficulty = Difficulty::rate(&steps, solved);
+            if args.json {
+                report.results.push(json!({
+                    "source": p.source,
+                    "difficulty": difficulty.name(),
+                    "steps": steps.len(),
+                    "solved_logically": solved,
+                }));
+            } else {
+                writeln!(out, "{}: {}", p.source, difficulty.name())?;
+            }
+        });
+    }
+    Ok(report)
+}
+
+// Puzzles put into a collection are solved and rated, and named by their place in the input
+fn convert(puzzles: &[Puzzle], args: &Args, out: &mut impl Write) -> Result<Report, CliError> {
+    let mut report = Report::new(args.json);
+    if args.to == Some(Output::Collection) {
+        let Some(rank) = puzzles.first().map(|p| p.rank) else {
+            write!(out, "{}", Collection::<Grid3x3>::new())?;
+            return Ok(report);
+        };
+        if puzzles.iter().any(|p| p.rank != rank) {
+            return Err(CliError::Input(
+                "can't put boards of different sizes in one collection".into(),
+            ));
+        }
+        with_rank!(rank, G => {
+            let mut collection = Collection::<G>::new();
+            for (i, p) in puzzles.iter().enumerate() {
+                let Some(grid) = report.parse::<G>(p) else {
+                    continue;
+                };
+                match CollectionPuzzle::from_puzzle((i + 1).to_string(), &grid) {
+                    Some(puzzle) => collection.puzzles.push(puzzle),
+                    None => report.fail(&p.source, "doesn't have one solution"),
+                }
+            }
+            write!(out, "{collection}")?;
+        });
+        return Ok(report);
+    }
+    let format = args.text_format();
+    for p in puzzles {
+        with_rank!(p.rank, G => {
+            let Some(grid) = report.parse::<G>(p) else {
+                continue;
+            };
+            if args.json {
+                report.results.push(json!({
+                    "source": p.source,
+                    "puzzle": grid.to_format(format).trim_end(),
+                }));
+            } else {
+                report.write_puzzle(out, &grid.to_format(format), format)?;
+            }
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(cmd: &str, args: &[&str]) -> Args {
+        Args::parse(cmd, args.iter().map(|s| s.to_string())).unwrap()
+    }
+
+    fn output(report: Result<Report, CliError>, mut out: Vec<u8>) -> (u8, String) {
+        let status = report.unwrap().finish(&mut out).unwrap();
+        (status, String::from_utf8(out).unwrap())
+    }
+
+    fn generated(cmd_args: &[&str]) -> (u8, String) {
+        let mut out = Vec::new();
+        let report = generate(&args("generate", cmd_args), &mut out);
+        output(report, out)
+    }
+
+    fn batch(cmd: &str, cmd_args: &[&str], input: &str) -> (u8, String) {
+        let args = args(cmd, cmd_args);
+        let puzzles = split("in", input, args.from).unwrap();
+        let mut out = Vec::new();
+        let report = match cmd {
+            "solve" => solve(&puzzles, &args, &mut out),
+            "validate" => validate(&puzzles, &args, &mut out),
+            "rate" => rate(&puzzles, &args, &mut out),
+            _ => convert(&puzzles, &args, &mut out),
+        };
+        output(report, out)
+    }
+
+    #[test]
+    fn split_input() {
+        let (grid, _) = PuzzleId::new(3, Difficulty::Easy, 1)
+            .generate::<Grid3x3>()
+            .unwrap();
+        let (big, _) = PuzzleId::new(4, Difficulty::Easy, 1)
+            .generate::<Grid4x4>()
+            .unwrap();
+        let text = format!(
+            "# two lines\n{}{}\n{}\n{}",
+            grid.to_line(),
+            grid.to_line(),
+            grid.to_format(PuzzleFormat::Ss),
+            big.to_format(PuzzleFormat::Sdk)
+        );
+        let puzzles = split("in", &text, None).unwrap();
+        let found = puzzles
+            .iter()
+            .map(|p| (p.source.as_str(), p.rank, p.format))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                ("in:2", 3, PuzzleFormat::Line),
+                ("in:3", 3, PuzzleFormat::Line),
+                ("in:5", 3, PuzzleFormat::Ss),
+                ("in:17", 4, PuzzleFormat::Sdk),
+            ]
+        );
+        for p in &puzzles[..3] {
+            assert!(Grid3x3::from_format(&p.text, p.format).unwrap() == grid);
+        }
+        assert!(Grid4x4::from_format(&puzzles[3].text, PuzzleFormat::Sdk).unwrap() == big);
+        assert!(split("in", "123\n", None).is_err());
+
+        let collection = Collection::<Grid3x3>::generate(2, Difficulty::Easy, Default::default());
+        let puzzles = split("c", &collection.to_string(), None).unwrap();
+        assert_eq!(puzzles.len(), 2);
+        assert_eq!(puzzles[0].source, format!("c:{}", collection.puzzles[0].id));
+    }
+
+    #[test]
+    fn generate_blanks() {
+        let cmd = ["--blanks", "45", "--seed", "10", "--count", "3"];
+        let (status, out) = generated(&cmd);
+        assert_eq!(status, 0);
+        assert_eq!(generated(&cmd).1, out);
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        for line in &lines {
+            assert_eq!(line.matches('.').count(), 45);
+            assert!(Grid3x3::from_line(line).unwrap().has_unique_solution());
+        }
+        // The seeds follow on, so a batch can be picked up from any puzzle in it
+        let (_, rest) = generated(&["--blanks", "45", "--seed", "11", "--count", "2"]);
+        assert_eq!(rest.lines().collect::<Vec<_>>(), lines[1..]);
+
+        let (status, out) = generated(&["--seed", "3", "--json", "--difficulty", "medium"]);
+        assert_eq!(status, 0);
+        let json: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(
+            json[0]["id"],
+            PuzzleId::new(3, Difficulty::Medium, 3).to_string()
+        );
+        assert_eq!(json[0]["size"], 9);
+        assert_eq!(json[0]["puzzle"].as_str().unwrap().len(), 81);
+
+        let (status, _) = generated(&["--blanks", "80", "--seed", "1"]);
+        assert_eq!(status, FAILED);
+        let parse = |cmd: &[&str]| Args::parse("generate", cmd.iter().map(|s| s.to_string()));
+        assert!(parse(&["--blanks", "40", "--difficulty", "hard"]).is_err());
+        assert!(parse(&["--format", "collection", "--json"]).is_err());
+        assert!(parse(&["--size", "12"]).is_err());
+    }
+
+    #[test]
+    fn solve_and_validate() {
+        let (puzzle, solution) = PuzzleId::new(3, Difficulty::Easy, 5)
+            .generate::<Grid3x3>()
+            .unwrap();
+        let mut broken = puzzle.clone();
+        let empty = positions::<Grid3x3>()
+            .find(|&pos| puzzle[pos].num_or_zero() == 0)
+            .unwrap();
+        let given = positions::<Grid3x3>()
+            .find(|&pos| pos.1 == empty.1 && puzzle[pos].num_or_zero() != 0)
+            .unwrap();
+        broken[empty] = puzzle[given];
+        let empty_board = ".".repeat(81);
+        let input = format!("{}{}{empty_board}\n", puzzle.to_line(), broken.to_line());
+
+        let (status, out) = batch("solve", &[], &input);
+        assert_eq!(status, FAILED);
+        assert_eq!(out, solution.to_line());
+
+        let (status, out) = batch("validate", &[], &input);
+        assert_eq!(status, FAILED);
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "in:1: ok");
+        assert!(lines[1].starts_with("in:2: invalid: "));
+        assert_eq!(lines[2], "in:3: invalid: more than one solution");
+
+        let (status, out) = batch("validate", &["--json"], &puzzle.to_line());
+        assert_eq!(status, 0);
+        let json: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(json[0]["valid"], true);
+
+        let (status, out) = batch("rate", &[], &puzzle.to_line());
+        assert_eq!(status, 0);
+        assert_eq!(out, format!("in:1: {}\n", puzzle.difficulty().name()));
+
+        let (status, _) = batch("solve", &["--from", "sdk"], &puzzle.to_line());
+        assert_eq!(status, INPUT_ERROR);
+    }
+
+    #[test]
+    fn convert_formats() {
+        let (puzzle, _) = PuzzleId::new(4, Difficulty::Easy, 6)
+            .generate::<Grid4x4>()
+            .unwrap();
+        let input = format!("{}{}", puzzle.to_line(), puzzle.to_line());
+        let (status, ss) = batch("convert", &["--to", "ss"], &input);
+        assert_eq!(status, 0);
+        let one = puzzle.to_format(PuzzleFormat::Ss);
+        assert_eq!(ss, format!("{one}\n{one}"));
+        let (_, lines) = batch("convert", &["--to", "line"], &ss);
+        assert_eq!(lines, input);
+
+        let (status, out) = batch("convert", &["--to", "collection"], &input);
+        assert_eq!(status, 0);
+        let collection = Collection::<Grid4x4>::parse(&out).unwrap();
+        assert_eq!(collection.puzzles.len(), 2);
+        assert_eq!(collection.puzzles[1].id, "2");
+        assert!(Args::parse("convert", std::iter::empty()).is_err());
+    }
+}
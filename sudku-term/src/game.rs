@@ -0,0 +1,276 @@
+use std::time::{Duration, Instant};
+use sudku_grid::{num_symbol, peers, positions, GridNum, MultiHistory as History, Pos, SudokuGrid};
+
+/// Something the player does to the game, read from the keyboard by the caller.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Places the number in the square under the cursor, or toggles it as a note in notes mode.
+    Enter(u8),
+    /// Empties the square under the cursor.
+    Clear,
+    ToggleNotes,
+    Undo,
+    Redo,
+    /// Marks the numbers that don't match the solution.
+    Check,
+}
+
+/// A board being played in the terminal, with everything needed to draw it.
+pub struct Game<G: SudokuGrid> {
+    pub grid: G,
+    pub solution: G,
+    pub history: History<G::Num>,
+    pub cursor: Pos,
+    /// Whether numbers entered are toggled as notes instead of placed.
+    pub notes_mode: bool,
+    /// The squares holding the number last entered that kept it from being placed, shown until
+    /// the next action.
+    pub conflicts: Vec<Pos>,
+    /// The wrong numbers found by the last check, shown until the next action.
+    pub mistakes: Vec<Pos>,
+    /// Shown under the board until the next action.
+    pub message: Option<String>,
+    started: Instant,
+    // How long the board took once it's solved, which stops the clock
+    finished: Option<Duration>,
+}
+
+impl<G: SudokuGrid> Game<G> {
+    /// Starts playing the puzzle, setting its numbers as given.
+    pub fn new(mut grid: G, solution: G) -> Self {
+        grid.set_given();
+        let cursor = positions::<G>()
+            .find(|&pos| grid[pos].num_or_zero() == 0)
+            .unwrap_or_default();
+        Self {
+            grid,
+            solution,
+            history: History::new(),
+            cursor,
+            notes_mode: false,
+            conflicts: Vec::new(),
+            mistakes: Vec::new(),
+            message: None,
+            started: Instant::now(),
+            finished: None,
+        }
+    }
+
+    /// Returns true once every square is filled in without breaking the rules.
+    pub fn is_solved(&self) -> bool {
+        self.finished.is_some()
+    }
+
+    /// How long the board has been played, or took to solve.
+    pub fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(|| self.started.elapsed())
+    }
+
+    pub fn handle(&mut self, action: Action) {
+        if self.is_solved() {
+            return;
+        }
+        self.conflicts.clear();
+        self.mistakes.clear();
+        self.message = None;
+        let (x, y) = self.cursor;
+        let last = G::SIZE - 1;
+        match action {
+            Action::Up => self.cursor = (x, y.saturating_sub(1)),
+            Action::Down => self.cursor = (x, (y + 1).min(last)),
+            Action::Left => self.cursor = (x.saturating_sub(1), y),
+            Action::Right => self.cursor = ((x + 1).min(last), y),
+            Action::Enter(n) => self.enter(n),
+            Action::Clear => self.place(G::Num::new(0)),
+            Action::ToggleNotes => self.notes_mode = !self.notes_mode,
+            Action::Undo => match self.history.undo() {
+                Some(mvs) => {
+                    for mv in mvs.iter().rev() {
+                        self.grid[mv.pos] = mv.old;
+                    }
+                }
+                None => self.message = Some("Nothing to undo".into()),
+            },
+            Action::Redo => match self.history.redo() {
+                Some(mvs) => {
+                    for mv in mvs {
+                        self.grid[mv.pos] = mv.new;
+                    }
+                }
+                None => self.message = Some("Nothing to redo".into()),
+            },
+            Action::Check => {
+                self.mistakes = self.grid.mistakes(&self.solution);
+                self.message = Some(match self.mistakes.len() {
+                    0 => "No mistakes found".into(),
+                    1 => "1 mistake found".into(),
+                    n => format!("{n} mistakes found"),
+                });
+            }
+        }
+        if self.grid.is_valid().is_none() {
+            self.finished = Some(self.started.elapsed());
+        }
+    }
+
+    fn enter(&mut self, n: u8) {
+        if n == 0 || n as usize > G::SIZE {
+            return;
+        }
+        let num = self.grid[self.cursor];
+        if self.notes_mode {
+            if num.num_or_zero() == 0 {
+                self.place(num.with_toggle_note(n));
+            }
+        } else if num.num_or_zero() == n {
+            self.place(G::Num::new(0));
+        } else if self.grid.pos_is_valid(self.cursor, n) {
+            self.place(G::Num::new(n));
+        } else {
+            self.conflicts = peers(G::RANK, self.cursor)
+                .filter(|&pos| self.grid[pos].num_or_zero() == n)
+                .collect();
+            self.message = Some(format!(
+                "{} is already in this row, column, or box",
+                num_symbol(n)
+            ));
+        }
+    }
+
+    fn place(&mut self, num: G::Num) {
+        if self.grid[self.cursor].is_given() {
+            self.message = Some("That square is given".into());
+            return;
+        }
+        // Clearing an empty square changes nothing, and shouldn't take an undo to get past
+        if let Some(mvs) = self.grid.place_num(self.cursor, num) {
+            if mvs.iter().any(|mv| mv.old != mv.new) {
+                self.history.update(mvs);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sudku_grid::{Difficulty, Grid3x3, Grid4x4, PuzzleId};
+
+    fn game<G: SudokuGrid>(seed: u64) -> Game<G> {
+        let (grid, solution) = PuzzleId::new(G::RANK, Difficulty::Easy, seed)
+            .generate::<G>()
+            .unwrap();
+        Game::new(grid, solution)
+    }
+
+    #[test]
+    fn move_and_enter() {
+        let mut game = game::<Grid3x3>(1);
+        let start = game.cursor;
+        assert_eq!(game.grid[start].num_or_zero(), 0);
+        let n = game.solution[start].num_or_zero();
+        game.handle(Action::Enter(n));
+        assert_eq!(game.grid[start].num(), Some(n));
+
+        // The cursor stays on the board
+        for _ in 0..20 {
+            game.handle(Action::Up);
+            game.handle(Action::Left);
+        }
+        assert_eq!(game.cursor, (0, 0));
+        for _ in 0..20 {
+            game.handle(Action::Down);
+            game.handle(Action::Right);
+        }
+        assert_eq!(game.cursor, (8, 8));
+
+        game.cursor = start;
+        game.handle(Action::Undo);
+        assert_eq!(game.grid[start].num_or_zero(), 0);
+        game.handle(Action::Redo);
+        assert_eq!(game.grid[start].num(), Some(n));
+        game.handle(Action::Redo);
+        assert!(game.message.is_some());
+        game.handle(Action::Clear);
+        assert_eq!(game.grid[start].num_or_zero(), 0);
+
+        // A number that fits but isn't the solution's is found by checking
+        let wrong = (1..=9)
+            .find(|&n| n != game.solution[start].num_or_zero() && game.grid.pos_is_valid(start, n));
+        if let Some(n) = wrong {
+            game.handle(Action::Enter(n));
+            game.handle(Action::Check);
+            assert_eq!(game.mistakes, [start]);
+        }
+    }
+
+    #[test]
+    fn clear_empty() {
+        let mut game = game::<Grid3x3>(4);
+        let start = game.cursor;
+        game.handle(Action::Clear);
+        assert!(!game.history.can_undo());
+
+        let n = game.solution[start].num_or_zero();
+        game.handle(Action::Enter(n));
+        let empty = positions::<Grid3x3>()
+            .find(|&pos| game.grid[pos].num_or_zero() == 0)
+            .unwrap();
+        game.cursor = empty;
+        game.handle(Action::Clear);
+        // The undo takes back the number entered, not the clear
+        game.handle(Action::Undo);
+        assert_eq!(game.grid[start].num_or_zero(), 0);
+        assert!(!game.history.can_undo());
+    }
+
+    #[test]
+    fn conflicts_and_notes() {
+        let mut game = game::<Grid4x4>(2);
+        let start = game.cursor;
+        let (x, y) = start;
+        // A number given elsewhere in the row can't be placed
+        let given = (0..16)
+            .map(|px| game.grid[(px, y)].num_or_zero())
+            .find(|&n| n != 0)
+            .unwrap();
+        game.handle(Action::Enter(given));
+        assert_eq!(game.grid[start].num_or_zero(), 0);
+        assert!(!game.conflicts.is_empty() && game.message.is_some());
+        assert!(game
+            .conflicts
+            .iter()
+            .all(|&(px, py)| px == x || py == y || { px / 4 == x / 4 && py / 4 == y / 4 }));
+        game.handle(Action::Right);
+        assert!(game.conflicts.is_empty());
+
+        game.cursor = start;
+        game.handle(Action::ToggleNotes);
+        game.handle(Action::Enter(16));
+        game.handle(Action::Enter(3));
+        game.handle(Action::Enter(16));
+        assert_eq!(game.grid[start].has_note(3), Some(true));
+        assert_eq!(game.grid[start].has_note(16), Some(false));
+    }
+
+    #[test]
+    fn win() {
+        let mut game = game::<Grid3x3>(3);
+        let empty = positions::<Grid3x3>()
+            .filter(|&pos| game.grid[pos].num_or_zero() == 0)
+            .collect::<Vec<_>>();
+        for &pos in &empty {
+            assert!(!game.is_solved());
+            game.cursor = pos;
+            game.handle(Action::Enter(game.solution[pos].num_or_zero()));
+        }
+        assert!(game.is_solved());
+        // Nothing changes once the board is solved
+        game.handle(Action::Clear);
+        assert!(game.grid.is_valid().is_none());
+    }
+}
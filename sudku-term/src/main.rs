@@ -1,3 +1,213 @@
-fn main() {
-    println!("Hello, world!");
+mod cli;
+mod game;
+mod ui;
+
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use game::{Action, Game};
+use std::io::{self, Stdout};
+use std::process::ExitCode;
+use std::time::Duration;
+use sudku_grid::{symbol_num, Difficulty, Grid3x3, Grid4x4, PuzzleId, SudokuGrid};
+
+const USAGE: &str = "\
+Usage: sudku-term [play] [options]
+       sudku-term <command> [options] [files...]
+
+Plays Sudoku in the terminal, or works on batches of puzzles with a command.
+
+Options for playing:
+  -s, --size <9|16>         the size of the board (default 9)
+  -d, --difficulty <name>   easy, medium, hard, expert, or diabolical (default easy)
+  -i, --id <id>             plays the puzzle with the ID instead of a random one
+  -h, --help                prints this help
+
+Commands:
+  generate    prints new puzzles
+  solve       prints the solutions of the puzzles
+  validate    checks that the puzzles break no rules and have one solution
+  rate        prints the difficulty of the puzzles
+  convert     prints the puzzles in another format
+
+Options for commands:
+  --size <9|16>             generate: the size of the boards (default 9)
+  --blanks <n>              generate: leaves n empty squares instead of going by difficulty
+  --difficulty <name>       generate: the difficulty of the puzzles (default easy)
+  --seed <n>                generate: the seed of the first puzzle, which adds one for each
+                            one after it (default random)
+  --count <n>               generate: how many puzzles to generate (default 1)
+  --format <format>         generate, solve: writes boards as line, sdk, ss, or collection
+                            (generate only) (default line)
+  --from <format>           reads the puzzles as line, sdk, or ss instead of guessing
+  --to <format>             convert: writes the puzzles as line, sdk, ss, or collection
+  --json                    prints the results as a JSON array
+
+Puzzles are read from the files, or stdin if there are none or one is -. Boards on many lines are
+separated by blank lines, and collections are read whole.
+
+Exit status: 0 if everything went fine, 1 if a puzzle couldn't be generated, solved, or rated or
+isn't valid, 2 if the arguments are wrong, and 3 if the input couldn't be read.";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).peekable();
+    if let Some(cmd) = args
+        .peek()
+        .filter(|arg| cli::COMMANDS.contains(&arg.as_str()))
+    {
+        let cmd = cmd.clone();
+        args.next();
+        return ExitCode::from(cli::run(&cmd, args, &mut io::stdout().lock()));
+    }
+    if args.peek().is_some_and(|arg| arg == "play") {
+        args.next();
+    }
+    let opts = match Options::parse(args) {
+        Ok(opts) => opts,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::from(cli::USAGE_ERROR);
+        }
+    };
+    if opts.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let rank = opts.id.map_or(opts.rank, |id| id.rank);
+    let res = match rank {
+        3 => play::<Grid3x3>(&opts),
+        4 => play::<Grid4x4>(&opts),
+        _ => {
+            eprintln!("only 9x9 and 16x16 boards can be played");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(e) = res {
+        eprintln!("error: {e}");
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+struct Options {
+    rank: usize,
+    difficulty: Difficulty,
+    id: Option<PuzzleId>,
+    help: bool,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut opts = Self {
+            rank: 3,
+            difficulty: Difficulty::Easy,
+            id: None,
+            help: false,
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {arg}"))
+            };
+            match arg.as_str() {
+                "-s" | "--size" => opts.rank = cli::parse_size(&value()?)?,
+                "-d" | "--difficulty" => opts.difficulty = cli::parse_difficulty(&value()?)?,
+                "-i" | "--id" => {
+                    let id = value()?;
+                    let id = id.parse().map_err(|e| format!("bad puzzle ID {id}: {e}"))?;
+                    opts.id = Some(id);
+                }
+                "-h" | "--help" => opts.help = true,
+                _ => return Err(format!("unknown argument: {arg}")),
+            }
+        }
+        Ok(opts)
+    }
+}
+
+// Puts the terminal back the way it was when dropped, even if the game panics
+struct RawTerminal(Stdout);
+
+impl RawTerminal {
+    fn new() -> io::Result<Self> {
+        let mut out = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(Self(out))
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(self.0, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Plays boards until the player quits. After the first board, which may be from an ID, new
+// boards are random ones of the same difficulty.
+fn play<G: SudokuGrid>(opts: &Options) -> Result<(), String> {
+    let mut id = opts
+        .id
+        .unwrap_or_else(|| PuzzleId::random(G::RANK, opts.difficulty));
+    let mut term = RawTerminal::new().map_err(|e| e.to_string())?;
+    loop {
+        let (grid, solution) = id.generate::<G>().map_err(|e| e.to_string())?;
+        let mut game = Game::new(grid, solution);
+        if !run(&mut term, &mut game).map_err(|e| e.to_string())? {
+            return Ok(());
+        }
+        id = PuzzleId::random(G::RANK, id.difficulty);
+    }
+}
+
+// Runs the game until the player quits, returning true if they asked for a new board.
+fn run<G: SudokuGrid>(term: &mut RawTerminal, game: &mut Game<G>) -> io::Result<bool> {
+    loop {
+        ui::draw(&mut term.0, game)?;
+        // Waking up every so often keeps the clock ticking
+        if !event::poll(Duration::from_millis(250))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            KeyCode::Char('c') if ctrl => return Ok(false),
+            KeyCode::Char('n') | KeyCode::Enter if game.is_solved() => return Ok(true),
+            _ => {}
+        }
+        if let Some(action) = action::<G>(key) {
+            game.handle(action);
+        }
+    }
+}
+
+// The action for the key: arrows or hjkl move, numbers (and letters above 9) are entered, and
+// the rest are single keys
+fn action<G: SudokuGrid>(key: KeyEvent) -> Option<Action> {
+    Some(match key.code {
+        KeyCode::Up | KeyCode::Char('k') => Action::Up,
+        KeyCode::Down | KeyCode::Char('j') => Action::Down,
+        KeyCode::Left | KeyCode::Char('h') => Action::Left,
+        KeyCode::Right | KeyCode::Char('l') => Action::Right,
+        KeyCode::Char('0' | '.') | KeyCode::Backspace | KeyCode::Delete => Action::Clear,
+        KeyCode::Char('n') => Action::ToggleNotes,
+        KeyCode::Char('u') => Action::Undo,
+        KeyCode::Char('r') => Action::Redo,
+        KeyCode::Char('?') => Action::Check,
+        KeyCode::Char(c) => {
+            let n = symbol_num(c).filter(|&n| n as usize <= G::SIZE)?;
+            Action::Enter(n)
+        }
+        _ => return None,
+    })
 }
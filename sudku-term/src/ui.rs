@@ -0,0 +1,126 @@
+use crate::game::Game;
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Attribute, Color, PrintStyledContent, StyledContent, Stylize},
+    terminal::{Clear, ClearType},
+};
+use std::io::{self, Write};
+use std::time::Duration;
+use sudku_grid::{num_symbol, GridNum, SudokuGrid};
+
+/// Draws the board and the lines under it, or the win screen once the board is solved. Lines are
+/// drawn over the old ones instead of clearing the screen first, which would flicker.
+pub fn draw<G: SudokuGrid>(out: &mut impl Write, game: &Game<G>) -> io::Result<()> {
+    let mut row = 0;
+    for line in board_lines(game) {
+        queue!(out, MoveTo(0, row))?;
+        for part in line {
+            queue!(out, PrintStyledContent(part))?;
+        }
+        row += 1;
+    }
+    let status = if game.is_solved() {
+        vec![
+            format!("Solved in {}!", fmt_elapsed(game.elapsed()))
+                .bold()
+                .green(),
+            "Press n for a new board or q to quit".to_string().stylize(),
+        ]
+    } else {
+        let mode = if game.notes_mode { "notes" } else { "numbers" };
+        let notes = game.grid[game.cursor]
+            .notes()
+            .map(|notes| {
+                let nums = (1..=G::SIZE as u8).filter(|&n| notes[n as usize - 1]);
+                nums.map(|n| num_symbol(n).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default();
+        let nums = if G::SIZE > 9 { "1-9/a-g" } else { "1-9" };
+        let keys = format!(
+            "arrows/hjkl move  {nums} enter  0/del clear  n notes  u/r undo/redo  ? check  q quit"
+        );
+        vec![
+            format!("{}  Entering {mode}", fmt_elapsed(game.elapsed())).stylize(),
+            format!("Notes: {notes}").stylize(),
+            game.message.clone().unwrap_or_default().red(),
+            keys.dark_grey(),
+        ]
+    };
+    for line in status {
+        row += 1;
+        queue!(
+            out,
+            MoveTo(0, row),
+            PrintStyledContent(line),
+            Clear(ClearType::UntilNewLine)
+        )?;
+    }
+    queue!(out, Clear(ClearType::FromCursorDown))?;
+    out.flush()
+}
+
+// Each square is a space and its number, and each box is closed with a border
+fn board_lines<G: SudokuGrid>(game: &Game<G>) -> Vec<Vec<StyledContent<String>>> {
+    let rank = G::RANK;
+    let border = |left: &str, mid: &str, right: &str| {
+        let span = "─".repeat(rank * 2 + 1);
+        let line = format!("{left}{}{right}", vec![span; rank].join(mid));
+        vec![line.stylize()]
+    };
+    let mut lines = vec![border("┌", "┬", "┐")];
+    for y in 0..G::SIZE {
+        if y != 0 && y % rank == 0 {
+            lines.push(border("├", "┼", "┤"));
+        }
+        let mut line = Vec::new();
+        for x in 0..G::SIZE {
+            if x % rank == 0 {
+                let border = if x == 0 { "│" } else { " │" };
+                line.push(border.to_string().stylize());
+            }
+            line.push(" ".to_string().stylize());
+            line.push(square(game, (x, y)));
+        }
+        line.push(" │".to_string().stylize());
+        lines.push(line);
+    }
+    lines.push(border("└", "┴", "┘"));
+    lines
+}
+
+// Givens are bold and entered numbers blue, and squares with notes show a colon. The squares that
+// kept a number from being placed are shown on red, and wrong numbers found by a check in red.
+fn square<G: SudokuGrid>(game: &Game<G>, pos: (usize, usize)) -> StyledContent<String> {
+    let num = game.grid[pos];
+    let mut content = match num.num_or_zero() {
+        0 if num.is_note() => ":".to_string().dark_grey(),
+        0 => ".".to_string().dark_grey(),
+        n if num.is_given() => num_symbol(n).to_string().bold(),
+        n => num_symbol(n).to_string().blue(),
+    };
+    if game.is_solved() {
+        content = content.green();
+    } else if game.conflicts.contains(&pos) {
+        content = content.on(Color::Red).with(Color::White);
+    } else if game.mistakes.contains(&pos) {
+        content = content.red();
+    }
+    if pos == game.cursor && !game.is_solved() {
+        content = content.attribute(Attribute::Reverse);
+    }
+    content
+}
+
+/// Formats the time as `m:ss`, or `h:mm:ss` once past an hour.
+pub fn fmt_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
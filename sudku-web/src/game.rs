@@ -0,0 +1,697 @@
+use crate::*;
+use sudku_grid::{
+    GameSave, Grid2x2, Grid3x3, Grid4x4, Grid5x5, Hint, MultiHistory as History, Num3x3, PuzzleId,
+    Rules, Samurai, SudokuGrid,
+};
+use std::time::Duration;
+use web_sys::Storage;
+
+/// The board being played, its history, and how long it's been played for one board size.
+pub struct Game<G: SudokuGrid + 'static> {
+    pub grid: RwSignal<G>,
+    pub history: RwSignal<History<G::Num>>,
+    /// Seconds spent on the board.
+    pub elapsed: RwSignal<u64>,
+    /// Index into `Difficulty::ALL` of the difficulty the board was generated with.
+    pub difficulty: RwSignal<usize>,
+    /// The hint being shown, cleared whenever the board changes.
+    pub hint: RwSignal<Option<Hint>>,
+    /// The solution of the board, if known.
+    pub solution: RwSignal<Option<G>>,
+    /// How many wrong numbers have been placed.
+    pub mistakes: RwSignal<u32>,
+    /// Whether the player asked for the board to be checked, cleared whenever it changes.
+    pub checking: RwSignal<bool>,
+    /// The ID that regenerates the board, if it was generated from one.
+    pub puzzle_id: RwSignal<Option<PuzzleId>>,
+    /// The rules the board is played with, such as Killer Sudoku cages.
+    pub rules: RwSignal<Rules>,
+}
+
+impl<G: SudokuGrid + 'static> Clone for Game<G> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<G: SudokuGrid + 'static> Copy for Game<G> {}
+
+impl<G: SudokuGrid + 'static> Game<G> {
+    pub fn new() -> Self {
+        Self {
+            grid: create_rw_signal(G::empty()),
+            history: create_rw_signal(History::new()),
+            elapsed: create_rw_signal(0),
+            difficulty: create_rw_signal(0),
+            hint: create_rw_signal(None),
+            solution: create_rw_signal(None),
+            mistakes: create_rw_signal(0),
+            checking: create_rw_signal(false),
+            puzzle_id: create_rw_signal(None),
+            rules: create_rw_signal(Rules::default()),
+        }
+    }
+
+    fn storage_key() -> String {
+        format!("sudku-game{}", G::RANK)
+    }
+
+    // Where the board alone was kept before games were saved with `GameSave`.
+    fn legacy_storage_key() -> String {
+        format!("sudku-grid{}", G::RANK)
+    }
+
+    pub fn load(&self, storage: &Storage) {
+        let key = Self::storage_key();
+        let save = match storage.get_item(&key) {
+            Ok(Some(text)) => match GameSave::<G>::parse(&text) {
+                Ok(save) => Some(save),
+                Err(e) => {
+                    console::log!("bad {key} save: {e}");
+                    None
+                }
+            },
+            Ok(None) => self.load_legacy(storage),
+            // TODO: Print error better
+            Err(e) => {
+                console::log!("error getting {key} from local storage: {e:?}");
+                None
+            }
+        };
+        let Some(save) = save else {
+            return;
+        };
+        self.history.set(save.multi_history());
+        self.elapsed.set(save.elapsed.as_secs());
+        if let Some(i) = save.setting("difficulty").and_then(|d| d.parse().ok()) {
+            self.difficulty.set(i);
+        }
+        if let Some(n) = save.setting("mistakes").and_then(|n| n.parse().ok()) {
+            self.mistakes.set(n);
+        }
+        self.puzzle_id.set(save.setting("puzzle-id").and_then(|id| id.parse().ok()));
+        // Games saved before the solution was kept have it worked out from the givens
+        let solution = save.solution.or_else(|| save.rules.unique_solution(&save.grid.givens()));
+        self.solution.set(solution);
+        self.rules.set(save.rules);
+        self.grid.set(save.grid);
+    }
+
+    // Reads a board saved in the old format, removing it so the game is only kept in the new one.
+    fn load_legacy(&self, storage: &Storage) -> Option<GameSave<G>> {
+        let key = Self::legacy_storage_key();
+        let save = match storage.get_item(&key) {
+            Ok(Some(enc)) => match base64::decode(enc).map(GameSave::from_legacy) {
+                Some(Some(save)) => Some(save),
+                Some(None) => {
+                    console::log!("bad {key} encoding");
+                    None
+                }
+                None => {
+                    console::log!("bad {key} base64 encoding");
+                    None
+                }
+            }
+            Ok(None) => None,
+            // TODO: Print error better
+            Err(e) => {
+                console::log!("error getting {key} from local storage: {e:?}");
+                None
+            }
+        };
+        if let Err(e) = storage.remove_item(&key) {
+            console::log!("error removing {key} from local storage: {e:?}");
+        }
+        save
+    }
+
+    /// Saves the game to local storage every time it changes.
+    pub fn save_on_change(self) {
+        create_effect(move |_| {
+            let mut save = GameSave::new(self.grid.get());
+            self.history.with(|hist| save.set_multi_history(hist));
+            save.elapsed = Duration::from_secs(self.elapsed.get());
+            save.solution = self.solution.get();
+            save.rules = self.rules.get();
+            save.set_setting("difficulty", self.difficulty.get().to_string());
+            save.set_setting("mistakes", self.mistakes.get().to_string());
+            if let Some(id) = self.puzzle_id.get() {
+                save.set_setting("puzzle-id", id.to_string());
+            }
+            let Some(storage) = get_local_storage() else {
+                return;
+            };
+            let key = Self::storage_key();
+            storage
+                .set_item(&key, &save.to_string())
+                .unwrap_or_else(|_| panic!("error saving {key}"));
+        });
+    }
+
+    /// Clears the hint and the board check whenever the board changes, since they may no longer
+    /// apply.
+    pub fn clear_on_change(self) {
+        create_effect(move |_| {
+            self.grid.track();
+            self.hint.set(None);
+            self.checking.set(false);
+        });
+    }
+
+    /// Counts another second spent on the board, unless it's empty or completed.
+    pub fn tick(&self) {
+        let completed = self.rules.with(|rules| {
+            self.grid.with(|grid| rules.is_valid(grid).is_none())
+        });
+        if !self.is_empty() && !completed {
+            self.elapsed.update(|secs| *secs += 1);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.grid.with(|grid| grid == &G::empty())
+    }
+
+    pub fn reset(&self) {
+        self.grid.set(G::empty());
+        self.history.set(History::new());
+        self.elapsed.set(0);
+        self.solution.set(None);
+        self.mistakes.set(0);
+        self.puzzle_id.set(None);
+        self.rules.set(Rules::default());
+    }
+
+    pub async fn generate(self, difficulty: Difficulty, symmetry: Symmetry, variant: Variant) {
+        if variant == Variant::Classic || G::RANK > variant.max_rank() {
+            self.play(PuzzleId::random(G::RANK, difficulty).with_symmetry(symmetry));
+        } else {
+            self.generate_variant(variant, difficulty, symmetry);
+        }
+    }
+
+    // Variants can't be made from a puzzle ID, so they have none.
+    fn generate_variant(&self, variant: Variant, difficulty: Difficulty, symmetry: Symmetry) {
+        let (mut grid, solution, rules) = G::generate_variant(variant, difficulty, symmetry);
+        grid.set_given();
+        self.solution.set(Some(solution));
+        self.rules.set(rules);
+        self.grid.set(grid);
+        self.puzzle_id.set(None);
+    }
+
+    /// Starts the puzzle with the ID, which has to be for this board size.
+    pub fn play(&self, id: PuzzleId) {
+        let (mut grid, solution) = match id.generate::<G>() {
+            Ok(puzzle) => puzzle,
+            Err(e) => {
+                console::log!("error generating {id}: {e}");
+                return;
+            }
+        };
+        grid.set_given();
+        self.solution.set(Some(solution));
+        self.rules.set(Rules::default());
+        self.grid.set(grid);
+        self.difficulty.set(id.difficulty as usize);
+        self.puzzle_id.set(Some(id));
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.with(|hist| hist.can_undo())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.with(|hist| hist.can_redo())
+    }
+
+    pub fn undo(&self) {
+        let Some(mvs) = self.history.try_update(|hist| hist.undo().cloned())
+            .expect("bad history try_update") else {
+            // TODO: Disable?
+            return;
+        };
+        self.grid.update(|grid| {
+            for mv in mvs.into_iter().rev() {
+                grid[mv.pos] = mv.old;
+            }
+        });
+    }
+
+    pub fn redo(&self) {
+        let Some(mvs) = self.history.try_update(|hist| hist.redo().cloned())
+            .expect("bad history try_update") else {
+            // TODO: Disable?
+            return;
+        };
+        self.grid.update(|grid| {
+            for mv in mvs {
+                grid[mv.pos] = mv.new;
+            }
+        });
+    }
+
+    /// Fills in the notes of every empty square as one move that can be undone.
+    pub fn fill_notes(&self) {
+        let mvs = self.rules.with(|rules| self.grid.try_update(|grid| rules.fill_notes(grid)))
+            .expect("bad grid try_update");
+        if !mvs.is_empty() {
+            self.history.update(|hist| hist.update(mvs));
+        }
+    }
+
+    /// Marks the wrong numbers on the board until it next changes. Returns how many there are.
+    pub fn check(&self) -> usize {
+        let Some(mistakes) = self.solution.with(|solution| {
+            solution.as_ref().map(|solution| self.grid.with(|grid| grid.mistakes(solution).len()))
+        }) else {
+            return 0;
+        };
+        self.checking.set(true);
+        mistakes
+    }
+
+    /// Finds the next step for the board and shows it. Returns false if there isn't one, which is
+    /// always the case on Jigsaw boards since the steps are only found in the usual boxes.
+    pub fn show_hint(&self) -> bool {
+        let jigsaw = self.rules.with(|rules| rules.boxes.is_some());
+        let hint = if jigsaw { None } else { self.grid.with(G::hint) };
+        let found = hint.is_some();
+        self.hint.set(hint);
+        found
+    }
+
+    /// Applies the hint being shown as one move that can be undone.
+    pub fn apply_hint(&self) {
+        let Some(hint) = self.hint.get_untracked() else {
+            return;
+        };
+        let mvs = self.grid.try_update(|grid| grid.apply_step(&hint.step))
+            .expect("bad grid try_update");
+        if !mvs.is_empty() {
+            self.history.update(|hist| hist.update(mvs));
+        }
+    }
+
+    /// Shows the board, marking wrong numbers while `show_mistakes` is set or the board is being
+    /// checked.
+    pub fn view(&self, show_mistakes: Signal<bool>) -> View {
+        let checking = self.checking;
+        let mark_mistakes = Signal::derive(move || show_mistakes.get() || checking.get());
+        view! {
+            <Board
+                grid=self.grid history=self.history hint=self.hint
+                solution=self.solution mistakes=self.mistakes mark_mistakes=mark_mistakes
+                rules=self.rules
+            />
+        }.into_view()
+    }
+}
+
+/// A Samurai board being played. It's kept apart from the single boards since the five boards
+/// share squares, so it has no rules, puzzle ID, or `GameSave`.
+#[derive(Clone, Copy)]
+pub struct SamuraiGame {
+    pub grid: RwSignal<Samurai>,
+    pub history: RwSignal<History<Num3x3>>,
+    /// Seconds spent on the board.
+    pub elapsed: RwSignal<u64>,
+    /// The hint being shown, cleared whenever the board changes.
+    pub hint: RwSignal<Option<Hint>>,
+    pub solution: RwSignal<Option<Samurai>>,
+    /// How many wrong numbers have been placed.
+    pub mistakes: RwSignal<u32>,
+    /// Whether the player asked for the board to be checked, cleared whenever it changes.
+    pub checking: RwSignal<bool>,
+}
+
+// How a Samurai game is kept in local storage, with the boards written as text. The history
+// isn't kept.
+#[derive(Serialize, Deserialize)]
+struct SamuraiSave {
+    board: String,
+    #[serde(default)]
+    solution: Option<String>,
+    #[serde(default)]
+    elapsed: u64,
+    #[serde(default)]
+    mistakes: u32,
+}
+
+impl SamuraiGame {
+    const STORAGE_KEY: &'static str = "sudku-samurai";
+
+    pub fn new() -> Self {
+        Self {
+            grid: create_rw_signal(Samurai::empty()),
+            history: create_rw_signal(History::new()),
+            elapsed: create_rw_signal(0),
+            hint: create_rw_signal(None),
+            solution: create_rw_signal(None),
+            mistakes: create_rw_signal(0),
+            checking: create_rw_signal(false),
+        }
+    }
+
+    pub fn load(&self, storage: &Storage) {
+        let key = Self::STORAGE_KEY;
+        let save = match storage.get_item(key) {
+            Ok(Some(json)) => match serde_json::from_str::<SamuraiSave>(&json) {
+                Ok(save) => save,
+                Err(e) => {
+                    console::log!("bad {key} json: {e:?}");
+                    return;
+                }
+            }
+            Ok(None) => return,
+            // TODO: Print error better
+            Err(e) => {
+                console::log!("error getting {key} from local storage: {e:?}");
+                return;
+            }
+        };
+        let grid = match Samurai::parse(&save.board) {
+            Ok(grid) => grid,
+            Err(e) => {
+                console::log!("bad {key} board: {e}");
+                return;
+            }
+        };
+        self.elapsed.set(save.elapsed);
+        self.mistakes.set(save.mistakes);
+        self.solution.set(save.solution.as_deref().and_then(|solution| {
+            Samurai::parse(solution)
+                .map_err(|e| console::log!("bad {key} solution: {e}"))
+                .ok()
+        }));
+        self.grid.set(grid);
+    }
+
+    /// Saves the game to local storage every time it changes.
+    pub fn save_on_change(self) {
+        create_effect(move |_| {
+            let save = SamuraiSave {
+                board: self.grid.with(Samurai::to_string),
+                solution: self.solution.with(|solution| solution.as_ref().map(Samurai::to_string)),
+                elapsed: self.elapsed.get(),
+                mistakes: self.mistakes.get(),
+            };
+            let Some(storage) = get_local_storage() else {
+                return;
+            };
+            let key = Self::STORAGE_KEY;
+            let json = serde_json::to_string(&save).expect("error serializing samurai save");
+            storage
+                .set_item(key, &json)
+                .unwrap_or_else(|_| panic!("error saving {key}"));
+        });
+    }
+
+    /// Clears the hint and the board check whenever the board changes.
+    pub fn clear_on_change(self) {
+        create_effect(move |_| {
+            self.grid.track();
+            self.hint.set(None);
+            self.checking.set(false);
+        });
+    }
+
+    /// Counts another second spent on the board, unless it's empty or completed.
+    pub fn tick(&self) {
+        let completed = self.grid.with(|grid| grid.is_valid().is_none());
+        if !self.is_empty() && !completed {
+            self.elapsed.update(|secs| *secs += 1);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.grid.with(|grid| grid == &Samurai::empty())
+    }
+
+    pub fn reset(&self) {
+        self.grid.set(Samurai::empty());
+        self.history.set(History::new());
+        self.elapsed.set(0);
+        self.solution.set(None);
+        self.mistakes.set(0);
+    }
+
+    /// Generates a new board. The difficulty, symmetry, and variant settings don't apply.
+    pub async fn generate(self) {
+        let (mut grid, solution) = Samurai::generate();
+        grid.set_given();
+        self.solution.set(Some(solution));
+        self.grid.set(grid);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.with(|hist| hist.can_undo())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.with(|hist| hist.can_redo())
+    }
+
+    pub fn undo(&self) {
+        let Some(mvs) = self.history.try_update(|hist| hist.undo().cloned())
+            .expect("bad history try_update") else {
+            return;
+        };
+        self.grid.update(|grid| {
+            for mv in mvs.into_iter().rev() {
+                grid[mv.pos] = mv.old;
+            }
+        });
+    }
+
+    pub fn redo(&self) {
+        let Some(mvs) = self.history.try_update(|hist| hist.redo().cloned())
+            .expect("bad history try_update") else {
+            return;
+        };
+        self.grid.update(|grid| {
+            for mv in mvs {
+                grid[mv.pos] = mv.new;
+            }
+        });
+    }
+
+    /// Fills in the notes of every empty square as one move that can be undone.
+    pub fn fill_notes(&self) {
+        let mvs = self.grid.try_update(Samurai::fill_notes).expect("bad grid try_update");
+        if !mvs.is_empty() {
+            self.history.update(|hist| hist.update(mvs));
+        }
+    }
+
+    /// Marks the wrong numbers on the board until it next changes. Returns how many there are.
+    pub fn check(&self) -> usize {
+        let Some(mistakes) = self.solution.with(|solution| {
+            solution.as_ref().map(|solution| self.grid.with(|grid| grid.mistakes(solution).len()))
+        }) else {
+            return 0;
+        };
+        self.checking.set(true);
+        mistakes
+    }
+
+    /// Finds the next step on one of the five boards and shows it. Returns false if there isn't
+    /// one.
+    pub fn show_hint(&self) -> bool {
+        let hint = self.grid.with(Samurai::hint);
+        let found = hint.is_some();
+        self.hint.set(hint);
+        found
+    }
+
+    /// Applies the hint being shown as one move that can be undone.
+    pub fn apply_hint(&self) {
+        let Some(hint) = self.hint.get_untracked() else {
+            return;
+        };
+        let mvs = self.grid.try_update(|grid| grid.apply_step(&hint.step))
+            .expect("bad grid try_update");
+        if !mvs.is_empty() {
+            self.history.update(|hist| hist.update(mvs));
+        }
+    }
+
+    /// Shows the board, marking wrong numbers while `show_mistakes` is set or the board is being
+    /// checked.
+    pub fn view(&self, show_mistakes: Signal<bool>) -> View {
+        let checking = self.checking;
+        let mark_mistakes = Signal::derive(move || show_mistakes.get() || checking.get());
+        view! {
+            <SamuraiBoard
+                grid=self.grid history=self.history hint=self.hint
+                solution=self.solution mistakes=self.mistakes mark_mistakes=mark_mistakes
+            />
+        }.into_view()
+    }
+}
+
+// Runs the expression with `$game` bound to the game for the rank, falling back to the 9x9 one.
+macro_rules! with_game {
+    ($games:expr, $rank:expr, $game:ident => $e:expr) => {
+        match $rank {
+            2 => {
+                let $game = $games.g2;
+                $e
+            }
+            4 => {
+                let $game = $games.g4;
+                $e
+            }
+            5 => {
+                let $game = $games.g5;
+                $e
+            }
+            _ => {
+                let $game = $games.g3;
+                $e
+            }
+        }
+    };
+}
+
+// Like `with_game!`, but for a board key, which may also be `SAMURAI`.
+macro_rules! with_board {
+    ($games:expr, $board:expr, $game:ident => $e:expr) => {
+        if $board == SAMURAI {
+            let $game = $games.samurai;
+            $e
+        } else {
+            with_game!($games, $board, $game => $e)
+        }
+    };
+}
+
+/// A game for each of the board sizes, picked by their rank, and the Samurai game.
+#[derive(Clone, Copy)]
+pub struct Games {
+    g2: Game<Grid2x2>,
+    g3: Game<Grid3x3>,
+    g4: Game<Grid4x4>,
+    g5: Game<Grid5x5>,
+    samurai: SamuraiGame,
+}
+
+impl Games {
+    pub fn new() -> Self {
+        Self {
+            g2: Game::new(),
+            g3: Game::new(),
+            g4: Game::new(),
+            g5: Game::new(),
+            samurai: SamuraiGame::new(),
+        }
+    }
+
+    pub fn load(&self, storage: &Storage) {
+        self.g2.load(storage);
+        self.g3.load(storage);
+        self.g4.load(storage);
+        self.g5.load(storage);
+        self.samurai.load(storage);
+    }
+
+    pub fn save_on_change(self) {
+        self.g2.save_on_change();
+        self.g3.save_on_change();
+        self.g4.save_on_change();
+        self.g5.save_on_change();
+        self.samurai.save_on_change();
+    }
+
+    pub fn clear_on_change(self) {
+        self.g2.clear_on_change();
+        self.g3.clear_on_change();
+        self.g4.clear_on_change();
+        self.g5.clear_on_change();
+        self.samurai.clear_on_change();
+    }
+
+    pub fn is_empty(&self, board: usize) -> bool {
+        with_board!(self, board, game => game.is_empty())
+    }
+
+    pub fn reset(&self, board: usize) {
+        with_board!(self, board, game => game.reset())
+    }
+
+    pub async fn generate(
+        self, board: usize, difficulty: Difficulty, symmetry: Symmetry, variant: Variant,
+    ) {
+        if board == SAMURAI {
+            self.samurai.generate().await
+        } else {
+            with_game!(self, board, game => game.generate(difficulty, symmetry, variant).await)
+        }
+    }
+
+    pub fn tick(&self, board: usize) {
+        with_board!(self, board, game => game.tick())
+    }
+
+    pub fn elapsed(&self, board: usize) -> u64 {
+        with_board!(self, board, game => game.elapsed.get())
+    }
+
+    pub fn can_undo(&self, board: usize) -> bool {
+        with_board!(self, board, game => game.can_undo())
+    }
+
+    pub fn can_redo(&self, board: usize) -> bool {
+        with_board!(self, board, game => game.can_redo())
+    }
+
+    pub fn undo(&self, board: usize) {
+        with_board!(self, board, game => game.undo())
+    }
+
+    pub fn redo(&self, board: usize) {
+        with_board!(self, board, game => game.redo())
+    }
+
+    pub fn fill_notes(&self, board: usize) {
+        with_board!(self, board, game => game.fill_notes())
+    }
+
+    pub fn check(&self, board: usize) -> usize {
+        with_board!(self, board, game => game.check())
+    }
+
+    pub fn mistakes(&self, board: usize) -> u32 {
+        with_board!(self, board, game => game.mistakes.get())
+    }
+
+    /// Starts the puzzle with the ID on the board of its rank.
+    pub fn play(&self, id: PuzzleId) {
+        with_game!(self, id.rank, game => game.play(id))
+    }
+
+    /// The ID of the board's puzzle, which Samurai boards never have.
+    pub fn puzzle_id(&self, board: usize) -> Option<PuzzleId> {
+        if board == SAMURAI {
+            return None;
+        }
+        with_game!(self, board, game => game.puzzle_id.get())
+    }
+
+    pub fn show_hint(&self, board: usize) -> bool {
+        with_board!(self, board, game => game.show_hint())
+    }
+
+    pub fn apply_hint(&self, board: usize) {
+        with_board!(self, board, game => game.apply_hint())
+    }
+
+    /// The text of the hint being shown, if any.
+    pub fn hint_text(&self, board: usize) -> Option<String> {
+        with_board!(self, board, game => game.hint.with(|hint| hint.as_ref().map(Hint::to_string)))
+    }
+
+    pub fn view(&self, board: usize, show_mistakes: Signal<bool>) -> View {
+        with_board!(self, board, game => game.view(show_mistakes))
+    }
+}
@@ -4,15 +4,18 @@
 use leptos::*;
 use leptos::html::Div;
 use serde::{Deserialize, Serialize};
-use sudku_grid::{Grid3x3, Grid4x4, History3x3, History4x4, Pos};
+use std::time::Duration;
+use sudku_grid::{Difficulty, Pos, PuzzleId, Symmetry, Variant};
 use wasm_bindgen::prelude::*;
 
+// Only decoding is used now, to migrate boards saved before `GameSave`
+#[allow(dead_code)]
 mod base64;
 pub mod console;
-mod grid3;
-use grid3::*;
-mod grid4;
-use grid4::*;
+mod board;
+use board::*;
+mod game;
+use game::*;
 //mod settings;
 //use settings::*;
 
@@ -31,10 +34,14 @@ fn init_wasm_hooks() {
 #[component]
 fn App() -> impl IntoView {
     let (settings, set_settings) = create_signal(Settings {
-        num_blanks3: 18,
-        num_blanks4: 18,
-        using_3x3: true,
+        difficulty: 0,
+        symmetry: 0,
+        variant: 0,
+        rank: 3,
+        samurai: false,
+        show_mistakes: false,
     });
+    let show_mistakes = Signal::derive(move || settings.with(|s| s.show_mistakes));
 
     let focused_cell = create_rw_signal(CellInfo::default());
     provide_context(focused_cell);
@@ -42,10 +49,7 @@ fn App() -> impl IntoView {
     let notes_active = create_rw_signal(false);
     provide_context(notes_active);
 
-    let grid3 = create_rw_signal(Grid3x3::empty());
-    let grid4 = create_rw_signal(Grid4x4::empty());
-    let history3 = create_rw_signal(History3x3::new());
-    let history4 = create_rw_signal(History4x4::new());
+    let games = Games::new();
     let showing_grid = create_rw_signal(false);
 
     {
@@ -60,31 +64,14 @@ fn App() -> impl IntoView {
                 // TODO: Print error better
                 Err(e) => console::log!("error getting settings from local storage: {e:?}"),
             }
-            match storage.get_item("sudku-grid3") {
-                Ok(Some(enc)) => match base64::decode(enc).map(Grid3x3::from_encoded) {
-                    Some(Some(grid)) => grid3.set(grid),
-                    Some(None) => console::log!("bad grid encoding"),
-                    None => console::log!("bad grid3 base64 encoding"),
-                }
-                Ok(None) => (),
-                // TODO: Print error better
-                Err(e) => console::log!("error getting grid3 from local storage: {e:?}"),
-            }
-            match storage.get_item("sudku-grid4") {
-                Ok(Some(enc)) => match base64::decode(enc).map(Grid4x4::from_encoded) {
-                    Some(Some(grid)) => grid4.set(grid),
-                    Some(None) => console::log!("bad grid encoding"),
-                    None => console::log!("bad grid4 base64 encoding"),
-                }
-                Ok(None) => console::log!("no grid4"),
-                // TODO: Print error better
-                Err(e) => console::log!("error getting grid4 from local storage: {e:?}"),
-            }
+            games.load(&storage);
         }
     }
 
     let (showing_settings, set_showing_settings) = create_signal(false);
     let new_settings = create_rw_signal(settings());
+    // The ID typed in to play someone else's puzzle
+    let play_id = create_rw_signal(String::new());
 
     create_effect(move |_| {
         if showing_settings() {
@@ -97,14 +84,14 @@ fn App() -> impl IntoView {
             return;
         }
         spawn_local(async move {
-            if settings.with(|s| s.using_3x3) {
-                if grid3.with(|grid| grid == &Grid3x3::EMPTY) {
-                    grid3.set(create_grid3x3(settings.with(|s| s.num_blanks3)).await);
-                }
-            } else {
-                if grid4.with(|grid| grid == &Grid4x4::EMPTY) {
-                    grid4.set(create_grid4x4(settings.with(|s| s.num_blanks4)).await);
-                }
+            let board = settings.with(Settings::board);
+            if games.is_empty(board) {
+                games.generate(
+                    board,
+                    settings.with(Settings::difficulty),
+                    settings.with(Settings::symmetry),
+                    settings.with(Settings::variant),
+                ).await;
             }
             showing_grid.set(true);
         });
@@ -122,48 +109,75 @@ fn App() -> impl IntoView {
                 .expect("error saving settings to local storage");
         });
     });
-    create_effect(move |_| {
-        grid3.with(|grid| {
-            let Some(storage) = get_local_storage() else {
-                return;
-            };
-            storage
-                .set_item("sudku-grid3", &base64::encode(grid.encode()))
-                .expect("error saving grid3");
-        });
-    });
-    create_effect(move |_| {
-        grid4.with(|grid| {
-            let Some(storage) = get_local_storage() else {
-                return;
-            };
-            storage
-                .set_item("sudku-grid4", &base64::encode(grid.encode()))
-                .expect("error saving grid4");
-        });
-    });
+    games.save_on_change();
+    games.clear_on_change();
+
+    // The clock only runs while the board is showing
+    set_interval(
+        move || {
+            if showing_grid.get_untracked() && !showing_settings.get_untracked() {
+                games.tick(settings.with_untracked(Settings::board));
+            }
+        },
+        Duration::from_secs(1),
+    );
 
     view! {
         <div id="app">
             <div id="main">
                 {
                     move || if showing_grid() {
-                        if settings.with(|s| s.using_3x3) {
-                            view! {
-                                <Grid3 grid=grid3 history=history3 />
-                            }
-                        } else {
-                            view! {
-                                <Grid4 grid=grid4 history=history4 />
-                            }
-                        }
+                        games.view(settings.with(Settings::board), show_mistakes)
                     } else {
-                        view! { <Loading /> }
+                        view! { <Loading /> }.into_view()
                     }
                 }
 
                 <div id="bottom-buttons">
+                    <span id="timer">
+                        {move || fmt_elapsed(games.elapsed(settings.with(Settings::board)))}
+                    </span>
+                    <button
+                        on:click=move |_| {
+                            games.fill_notes(settings.with(Settings::board));
+                            if let Some(cell) = focused_cell.get().node.get() {
+                                cell.focus().expect("error focusing cell");
+                            }
+                        }
+                    >"Fill Notes"</button>
+                    <button
+                        on:click=move |_| {
+                            if !games.show_hint(settings.with(Settings::board)) {
+                                alert("No hint found");
+                            }
+                        }
+                    >"Hint"</button>
+                    <button
+                        on:click=move |_| {
+                            if games.check(settings.with(Settings::board)) == 0 {
+                                alert("No mistakes found");
+                            }
+                        }
+                    >"Check"</button>
+                    {
+                        move || show_mistakes.get().then(|| view! {
+                            <span id="mistakes">
+                                "Mistakes: "
+                                {move || games.mistakes(settings.with(Settings::board))}
+                            </span>
+                        })
+                    }
                 </div>
+                {
+                    move || games.hint_text(settings.with(Settings::board)).map(|text| view! {
+                        <div id="hint">
+                            <span>{text}</span>
+                            <button
+                                on:click=move |_| games.apply_hint(settings.with(Settings::board))
+                            >"Apply"</button>
+                        </div>
+                    })
+                }
 
             </div>
 
@@ -188,31 +202,11 @@ fn App() -> impl IntoView {
                 <div>
                 {
                     move || {
-                        let disabled = if settings.with(|s| s.using_3x3) {
-                            history3.with(|hist| !hist.can_undo())
-                        } else {
-                            history4.with(|hist| !hist.can_undo())
-                        };
+                        let disabled = !games.can_undo(settings.with(Settings::board));
                         if !disabled {
                             view! { <button
                                 //prop:disabled=disabled
-                                on:click=move |_| {
-                                    if settings.with(|s| s.using_3x3) {
-                                        let Some(mv) = history3.try_update(|hist| hist.undo().copied())
-                                            .expect("bad history try_update") else {
-                                            // TODO: Disable?
-                                            return;
-                                        };
-                                        grid3.update(|grid| grid[mv.pos] = mv.old);
-                                    } else {
-                                        let Some(mv) = history4.try_update(|hist| hist.undo().copied())
-                                            .expect("bad history try_update") else {
-                                            // TODO: Disable?
-                                            return;
-                                        };
-                                        grid4.update(|grid| grid[mv.pos] = mv.old);
-                                    }
-                                }
+                                on:click=move |_| games.undo(settings.with(Settings::board))
                             >
                                 <img src={UNDO_IMG_SRC} />
                             </button> }.into_any()
@@ -226,31 +220,11 @@ fn App() -> impl IntoView {
                 <div>
                 {
                     move || {
-                        let disabled= if settings.with(|s| s.using_3x3) {
-                            history3.with(|hist| !hist.can_redo())
-                        } else {
-                            history4.with(|hist| !hist.can_redo())
-                        };
+                        let disabled = !games.can_redo(settings.with(Settings::board));
                         if !disabled {
                             view! { <button
                                 //prop:disabled=disabled
-                                on:click=move |_| {
-                                    if settings.with(|s| s.using_3x3) {
-                                        let Some(mv) = history3.try_update(|hist| hist.redo().copied())
-                                            .expect("bad history try_update") else {
-                                            // TODO: Disable?
-                                            return;
-                                        };
-                                        grid3.update(|grid| grid[mv.pos] = mv.new);
-                                    } else {
-                                        let Some(mv) = history4.try_update(|hist| hist.redo().copied())
-                                            .expect("bad history try_update") else {
-                                            // TODO: Disable?
-                                            return;
-                                        };
-                                        grid4.update(|grid| grid[mv.pos] = mv.new);
-                                    }
-                                }
+                                on:click=move |_| games.redo(settings.with(Settings::board))
                             >
                                 <img src={REDO_IMG_SRC} />
                             </button>}.into_any()
@@ -286,70 +260,212 @@ fn App() -> impl IntoView {
             >
                 <div id="settings-opts">
                     <label>
-                        "Number of Blanks (3x3): "
-                        <input
-                            type="number" name="num-blank-input" size="5" maxlength="3"
-                            min="1" max="80" step="1"
-                            placeholder="Blanks"
-                            prop:value=move || new_settings.with(|s| s.num_blanks3)
-                            on:input=move |ev| {
+                        "Difficulty: "
+                        <select
+                            name="difficulty-select"
+                            on:change=move |ev| {
                                 event_target_value(&ev)
                                     .parse()
                                     .into_iter()
-                                    .for_each(|num: usize| {
-                                        new_settings.update(|s| s.num_blanks3 = num.min(80))
+                                    .for_each(|i: usize| {
+                                        new_settings.update(|s| {
+                                            s.difficulty = i.min(Difficulty::ALL.len() - 1)
+                                        })
                                     });
                             }
-                        />
+                        >
+                        {
+                            Difficulty::ALL
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, difficulty)| view! {
+                                    <option
+                                        value=i.to_string()
+                                        prop:selected=move || new_settings.with(|s| s.difficulty == i)
+                                    >
+                                        {difficulty.name()}
+                                    </option>
+                                })
+                                .collect_view()
+                        }
+                        </select>
                     </label>
-                    <br />
+
                     <label>
-                        "Number of Blanks (4x4): "
-                        <input
-                            type="number" name="num-blank-input" size="5" maxlength="3"
-                            min="1" max="255" step="1"
-                            placeholder="Blanks"
-                            prop:value=move || new_settings.with(|s| s.num_blanks4)
-                            on:input=move |ev| {
+                        "Symmetry: "
+                        <select
+                            name="symmetry-select"
+                            on:change=move |ev| {
                                 event_target_value(&ev)
                                     .parse()
                                     .into_iter()
-                                    .for_each(|num: usize| {
-                                        new_settings.update(|s| s.num_blanks4 = num.min(255))
+                                    .for_each(|i: usize| {
+                                        new_settings.update(|s| {
+                                            s.symmetry = i.min(Symmetry::ALL.len() - 1)
+                                        })
                                     });
                             }
-                        />
+                        >
+                        {
+                            Symmetry::ALL
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, symmetry)| view! {
+                                    <option
+                                        value=i.to_string()
+                                        prop:selected=move || new_settings.with(|s| s.symmetry == i)
+                                    >
+                                        {symmetry.name()}
+                                    </option>
+                                })
+                                .collect_view()
+                        }
+                        </select>
+                    </label>
+
+                    <label>
+                        "Variant: "
+                        <select
+                            name="variant-select"
+                            on:change=move |ev| {
+                                event_target_value(&ev)
+                                    .parse()
+                                    .into_iter()
+                                    .for_each(|i: usize| {
+                                        new_settings.update(|s| {
+                                            s.variant = i.min(Variant::ALL.len() - 1)
+                                        })
+                                    });
+                            }
+                        >
+                        {
+                            Variant::ALL
+                                .into_iter()
+                                .enumerate()
+                                .map(|(i, variant)| view! {
+                                    <option
+                                        value=i.to_string()
+                                        prop:selected=move || new_settings.with(|s| s.variant == i)
+                                    >
+                                        {variant.name()}
+                                    </option>
+                                })
+                                .collect_view()
+                        }
+                        </select>
                     </label>
 
                     <hr />
 
-                    <label for="use-4x4">
-                        "4x4 Grid:"
-                        <input
-                            type="checkbox" name="use-4x4"
-                            prop:checked=move || new_settings.with(|s| !s.using_3x3)
-                            on:click=move |ev| {
-                                if event_target_checked(&ev) {
-                                    new_settings.update(|s| s.using_3x3 = false);
-                                } else {
-                                    new_settings.update(|s| s.using_3x3 = true);
+                    <label>
+                        "Board Size: "
+                        <select
+                            name="rank-select"
+                            on:change=move |ev| {
+                                let value = event_target_value(&ev);
+                                if value == "samurai" {
+                                    new_settings.update(|s| s.samurai = true);
+                                } else if let Ok(rank) = value.parse() {
+                                    new_settings.update(|s| {
+                                        s.rank = rank;
+                                        s.samurai = false;
+                                    });
                                 }
                             }
+                        >
+                        {
+                            RANKS
+                                .into_iter()
+                                .map(|rank| view! {
+                                    <option
+                                        value=rank.to_string()
+                                        prop:selected=move || new_settings.with(|s| {
+                                            !s.samurai && s.rank() == rank
+                                        })
+                                    >
+                                        {format!("{0}x{0}", rank * rank)}
+                                    </option>
+                                })
+                                .collect_view()
+                        }
+                        <option
+                            value="samurai"
+                            prop:selected=move || new_settings.with(|s| s.samurai)
+                        >"Samurai"</option>
+                        </select>
+                    </label>
+
+                    <hr />
+
+                    <label>
+                        "Show Mistakes: "
+                        <input
+                            type="checkbox"
+                            name="show-mistakes-checkbox"
+                            prop:checked=move || new_settings.with(|s| s.show_mistakes)
+                            on:change=move |ev| {
+                                let checked = event_target_checked(&ev);
+                                new_settings.update(|s| s.show_mistakes = checked);
+                            }
+                        />
+                    </label>
+
+                    <hr />
+
+                    <div>
+                        "Puzzle ID: "
+                        <span id="puzzle-id">
+                        {
+                            move || games.puzzle_id(settings.with(Settings::board))
+                                .map(|id| id.to_string())
+                                .unwrap_or_default()
+                        }
+                        </span>
+                    </div>
+                    <label>
+                        "Play ID: "
+                        <input
+                            type="text"
+                            name="play-id-input"
+                            prop:value=move || play_id.get()
+                            on:input=move |ev| play_id.set(event_target_value(&ev))
                         />
                     </label>
+                    <button
+                        on:click=move |_| {
+                            let id = match play_id.with(|id| id.parse::<PuzzleId>()) {
+                                Ok(id) if RANKS.contains(&id.rank) => id,
+                                _ => {
+                                    alert("Invalid puzzle ID");
+                                    return;
+                                }
+                            };
+                            focused_cell.set(CellInfo::default());
+                            games.reset(id.rank);
+                            games.play(id);
+                            new_settings.update(|s| {
+                                s.rank = id.rank;
+                                s.samurai = false;
+                                s.difficulty = id.difficulty as usize;
+                                s.variant = 0;
+                                s.symmetry = Symmetry::ALL
+                                    .iter()
+                                    .position(|&s| s == id.symmetry)
+                                    .unwrap_or_default();
+                            });
+                            play_id.set(String::new());
+                            set_showing_settings(false);
+                            showing_grid.set(false);
+                            set_settings(new_settings.get());
+                        }
+                    >"Play"</button>
                 </div>
                 <div id="settings-bottom-buttons">
                     <div>
                         <button
                             on:click=move |_| {
                                 focused_cell.set(CellInfo::default());
-                                if new_settings.with(|s| s.using_3x3) {
-                                    grid3.set(Grid3x3::empty());
-                                    history3.set(History3x3::new());
-                                } else {
-                                    grid4.set(Grid4x4::empty());
-                                    history4.set(History4x4::new());
-                                }
+                                games.reset(new_settings.with(Settings::board));
                                 set_showing_settings(false);
                                 showing_grid.set(false);
                                 set_settings(new_settings.get());
@@ -366,18 +482,9 @@ fn App() -> impl IntoView {
                     <div>
                         <button
                             on:click=move |_| {
-                                if new_settings.with(|s| s.using_3x3) {
-                                    if !settings.with(|s| s.using_3x3) {
-                                        showing_grid.set(false);
-                                    }
-                                    //grid3.set(Grid3x3::empty());
-                                    //history3.set(History3x3::new());
-                                } else {
-                                    if settings.with(|s| s.using_3x3) {
-                                        showing_grid.set(false);
-                                    }
-                                    //grid4.set(Grid4x4::empty());
-                                    //history4.set(History4x4::new());
+                                let board = new_settings.with(Settings::board);
+                                if board != settings.with(Settings::board) {
+                                    showing_grid.set(false);
                                 }
                                 set_showing_settings(false);
                                 set_settings(new_settings.get());
@@ -414,10 +521,29 @@ pub const fn num_to_str(n: u8) -> &'static str {
         14 => "E",
         15 => "F",
         16 => "G",
+        17 => "H",
+        18 => "I",
+        19 => "J",
+        20 => "K",
+        21 => "L",
+        22 => "M",
+        23 => "N",
+        24 => "O",
+        25 => "P",
         _ => "",
     }
 }
 
+/// Formats seconds as `m:ss`, or `h:mm:ss` once past an hour.
+pub fn fmt_elapsed(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}")
+    }
+}
+
 #[derive(Clone, Copy, Default)]
 pub struct CellInfo {
     pub node: NodeRef<Div>,
@@ -431,20 +557,6 @@ impl CellInfo {
     }
 }
 
-async fn create_grid3x3(num_blank: usize) -> Grid3x3 {
-    let mut grid = Grid3x3::randomized();
-    grid.remove_nums(num_blank);
-    grid.set_given();
-    grid
-}
-
-async fn create_grid4x4(num_blank: usize) -> Grid4x4 {
-    let mut grid = Grid4x4::randomized();
-    grid.remove_nums(num_blank);
-    grid.set_given();
-    grid
-}
-
 fn get_local_storage() -> Option<web_sys::Storage> {
     match window().local_storage() {
         Ok(Some(stor)) => Some(stor),
@@ -462,9 +574,62 @@ fn get_local_storage() -> Option<web_sys::Storage> {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Settings {
-    pub using_3x3: bool,
-    pub num_blanks3: usize,
-    pub num_blanks4: usize,
+    /// The rank of the board, 3 being the usual 9x9 one.
+    #[serde(default = "default_rank")]
+    pub rank: usize,
+    /// Index into `Difficulty::ALL`.
+    #[serde(default)]
+    pub difficulty: usize,
+    /// Index into `Symmetry::ALL` of the pattern the givens of new boards follow.
+    #[serde(default)]
+    pub symmetry: usize,
+    /// Index into `Variant::ALL` of the rules new boards are played with. Boards bigger than the
+    /// variant's `max_rank` are classic.
+    #[serde(default)]
+    pub variant: usize,
+    /// Whether a Samurai board is played instead of one of the size of `rank`.
+    #[serde(default)]
+    pub samurai: bool,
+    /// Whether wrong numbers are marked as soon as they're placed.
+    #[serde(default)]
+    pub show_mistakes: bool,
+}
+
+/// The ranks of the boards that can be played.
+pub const RANKS: [usize; 4] = [2, 3, 4, 5];
+
+/// The board key of the Samurai game. Other boards are keyed by their rank.
+pub const SAMURAI: usize = 0;
+
+fn default_rank() -> usize {
+    3
+}
+
+impl Settings {
+    pub fn rank(&self) -> usize {
+        self.rank.clamp(RANKS[0], RANKS[RANKS.len() - 1])
+    }
+
+    /// The key of the board being played, either its rank or `SAMURAI`.
+    pub fn board(&self) -> usize {
+        if self.samurai {
+            SAMURAI
+        } else {
+            self.rank()
+        }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        Difficulty::ALL[self.difficulty.min(Difficulty::ALL.len() - 1)]
+    }
+
+    pub fn symmetry(&self) -> Symmetry {
+        Symmetry::ALL[self.symmetry.min(Symmetry::ALL.len() - 1)]
+    }
+
+    pub fn variant(&self) -> Variant {
+        Variant::ALL[self.variant.min(Variant::ALL.len() - 1)]
+    }
 }
 
 #[wasm_bindgen]